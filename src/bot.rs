@@ -1,39 +1,104 @@
 use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::time::{interval, Duration};
 use tracing::{info, warn, error};
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 
 use crate::config::Config;
-use crate::exchange::{ExchangeConnector, AccountBalance};
-use crate::intelligence::{ConfluenceScorer, AssetRanker};
-use crate::risk_v2::AdaptiveRiskManager;
-use crate::execution_v2::{SmartEntryManager, DynamicTPManager, NewsCalendar};
-use crate::monitoring::{PerformanceMetrics, MetricsCalculator, TelegramAlerter};
-use crate::types::Timeframe;
-use chrono::Datelike;
+use crate::exchange::{ExchangeConnector, AccountBalance, Instrument, LiquidationStream, OrderSide, Ticker};
+use crate::intelligence::{ConfluenceScorer, AssetRanker, MarketRegimeDetector, RegimeChange, VolatilityGuard, VolatilityBlock, LiquidationTracker, LiquidationCascade, SessionCalendar, BtcLeaderFilter, LeaderVerdict, ScoreCalibration, ScoredOutcome, ScoreJournal};
+use crate::intelligence::btc_leader::LEADER_SYMBOL;
+use crate::intelligence::liquidations::{self, DEFAULT_MIN_CASCADE_USD};
+use crate::intelligence::volatility_guard::DEFAULT_COOLDOWN_MS;
+use crate::intelligence::asset_ranker::{DEFAULT_MAX_UNIVERSE, DEFAULT_MIN_TURNOVER_USD};
+use crate::intelligence::confluence_scorer::DEFAULT_CONFLICT_THRESHOLD;
+use crate::intelligence::setup_rules;
+use crate::risk_v2::{AccountState, AdaptiveRiskManager, ChallengeRules, CorrelationMatrix, Exposure, ExposureVerdict, DailyLossEvent, DailyLossLedger, PortfolioDecision, PortfolioManager, PositionExposure, PositionSizer, ProposedOrder, RuleViolation, TradeEvent, TradeResult, TradingDayTracker, ViolationLog};
+use crate::risk_v2::position_sizer;
+use crate::execution_v2::NewsCalendar;
+use crate::monitoring::{PerformanceMetrics, MetricsCalculator, TelegramAlerter, CompletedTrade, TradeInfo};
+use crate::strategy::{BreakoutRetestStrategy, RangeReversionStrategy, EntryIntent, Fill, FillKind, Intent, Strategy, StrategyContext};
+use crate::types::{MultiTimeframeData, OrderBook, Timeframe, TrendDirection};
+
+// Order book levels fetched per side and snapshots kept per symbol, one per
+// cycle, for wall and spoofing detection
+const ORDER_BOOK_DEPTH: usize = 50;
+const ORDER_BOOK_HISTORY: usize = 5;
 
 pub struct TradingBot {
     config: Config,
     exchange: Arc<dyn ExchangeConnector>,
-    confluence_scorer: ConfluenceScorer,
+    strategies: Vec<Box<dyn Strategy>>,
     asset_ranker: AssetRanker,
+    regime_detector: MarketRegimeDetector,
+    volatility_guard: VolatilityGuard,
+    liquidation_tracker: LiquidationTracker,
+    // Feed for the symbols it was opened with, reopened when they change
+    liquidation_stream: Option<LiquidationStream>,
+    liquidation_stream_enabled: bool,
+    // None when the filter is disabled
+    btc_leader: Option<BtcLeaderFilter>,
+    // Closed trades by entry score and the calibration fitted on them
+    score_journal: ScoreJournal,
+    score_outcomes: Vec<ScoredOutcome>,
+    calibration: ScoreCalibration,
     risk_manager: AdaptiveRiskManager,
-    entry_manager: SmartEntryManager,
-    tp_manager: DynamicTPManager,
+    daily_loss: DailyLossLedger,
+    // Rolling correlations of ranked symbols, for correlated exposure caps
+    correlation_matrix: CorrelationMatrix,
+    // Concurrent position, total risk and net exposure limits
+    portfolio: PortfolioManager,
+    // Firm limits for the current phase and where breaches are recorded
+    challenge_rules: ChallengeRules,
+    violation_log: ViolationLog,
     news_calendar: NewsCalendar,
+    session_calendar: SessionCalendar,
+    // Timeframes read by setup rules, fetched along with the strategies' own
+    setup_timeframes: Vec<Timeframe>,
     alerter: Option<TelegramAlerter>,
     metrics_calculator: MetricsCalculator,
 
     // State
     initial_balance: Decimal,
     current_balance: Decimal,
-    valid_trading_days: u32,
+    // Distinct days with qualifying trades, persisted in the data dir
+    trading_days: TradingDayTracker,
     is_running: bool,
+    // Close time of the last bar each (strategy, symbol) has seen
+    last_bars: HashMap<(usize, String), i64>,
+    positions: HashMap<String, OpenPosition>,
+    // Entries waiting for room under the portfolio limits, oldest first
+    entry_queue: VecDeque<QueuedEntry>,
+    order_books: HashMap<String, VecDeque<OrderBook>>,
+    // Lot rules by symbol for position sizing
+    instruments: HashMap<String, Instrument>,
+}
+
+struct OpenPosition {
+    // Index into `strategies` of the owner
+    strategy: usize,
+    direction: TrendDirection,
+    entry_price: Decimal,
+    // Loss at the initial stop, the unit of the trade's R multiple
+    risk_amount: Decimal,
+    initial_quantity: Decimal,
+    quantity: Decimal,
+    stop_loss: Decimal,
+    opened_at: i64,
+    realized_pnl: Decimal,
+    info: TradeInfo,
+}
+
+struct QueuedEntry {
+    strategy: usize,
+    entry: EntryIntent,
+    queued_at: i64,
 }
 
 impl TradingBot {
-    pub fn new(config: Config, exchange: Arc<dyn ExchangeConnector>) -> Self {
+    pub fn new(config: Config, exchange: Arc<dyn ExchangeConnector>) -> Result<Self> {
         let initial_balance = Decimal::try_from(config.initial_capital).unwrap();
 
         let alerter = if config.enable_alerts {
@@ -50,26 +115,117 @@ impl TradingBot {
             None
         };
 
-        Self {
-            confluence_scorer: ConfluenceScorer::new(config.min_confluence_score),
-            asset_ranker: AssetRanker::new(),
+        // Signal weights come from config and must add up to 100
+        let mut confluence_scorer = ConfluenceScorer::new(config.min_confluence_score)
+            .with_conflict_threshold(
+                config.confluence_conflict_threshold.unwrap_or(DEFAULT_CONFLICT_THRESHOLD),
+            );
+
+        // Declarative setups join as extra checks, keyed by setup name
+        let setup_rules = config.setup_rules()?;
+        setup_rules::register_setups(confluence_scorer.registry_mut(), &setup_rules)?;
+        let mut setup_timeframes: Vec<Timeframe> = setup_rules.iter().flat_map(|s| s.timeframes()).collect();
+        setup_timeframes.sort_by_key(|tf| tf.to_minutes());
+        setup_timeframes.dedup();
+        for setup in &setup_rules {
+            info!("Setup rule '{}' ({:?}, {} conditions)", setup.name, setup.direction, setup.conditions.len());
+        }
+
+        confluence_scorer.registry_mut().apply_weights(&config.confluence_weight_overrides()?)?;
+        confluence_scorer.registry().validate()?;
+
+        // Holidays and no-trade windows also turn the session signal off
+        let session_calendar = config.session_calendar()?;
+        confluence_scorer.registry_mut().replace("session", Arc::new(session_calendar.clone()))?;
+        let min_cascade_usd = config.liquidation_cascade_min_usd.unwrap_or(DEFAULT_MIN_CASCADE_USD);
+        confluence_scorer.registry_mut().replace("liquidations", Arc::new(liquidations::liquidation_cascade_check(min_cascade_usd)))?;
+
+        info!("Confluence weights:");
+        for check in confluence_scorer.registry().checks() {
+            info!("  {} = {}", check.key, check.weight);
+        }
+
+        // Trend setups may use the full risk range, range fades the base risk
+        let mut strategies: Vec<Box<dyn Strategy>> = vec![
+            Box::new(BreakoutRetestStrategy::new(confluence_scorer, config.risk_per_trade_max)),
+        ];
+        if config.enable_range_reversion.unwrap_or(false) {
+            strategies.push(Box::new(RangeReversionStrategy::new(config.risk_per_trade_base)));
+        }
+        info!("Strategies: {}", strategies.iter().map(|s| s.settings().name.as_str()).collect::<Vec<_>>().join(", "));
+
+        let trading_days = TradingDayTracker::load(
+            config.data_dir().join("trading_days.json"),
+            config.valid_day_rule()?,
+            config.valid_day_min_risk_percent.unwrap_or(0.0),
+        )?;
+        info!("Valid trading days so far: {}", trading_days.count());
+
+        let score_journal = ScoreJournal::new(config.data_dir().join("confluence_journal.jsonl"));
+        let score_outcomes = score_journal.load().unwrap_or_else(|e| {
+            warn!("Could not read the confluence journal, starting empty: {}", e);
+            Vec::new()
+        });
+        let calibration = ScoreCalibration::fit(&score_outcomes, config.calibration_method()?);
+        info!("Confluence calibration ({:?}, {} trades):", calibration.method, calibration.trades);
+        for line in calibration.report() {
+            info!("  {}", line);
+        }
+
+        Ok(Self {
+            strategies,
+            asset_ranker: AssetRanker::with_universe_filter(
+                config.min_universe_turnover_usd.unwrap_or(DEFAULT_MIN_TURNOVER_USD),
+                config.max_universe_size.unwrap_or(DEFAULT_MAX_UNIVERSE),
+            ),
+            regime_detector: MarketRegimeDetector::new(),
+            volatility_guard: VolatilityGuard::with_cooldown(
+                config.volatility_cooldown_minutes.map_or(DEFAULT_COOLDOWN_MS, |m| m as i64 * 60_000),
+                config.volatility_tighten_stops.unwrap_or(false),
+            ),
+            liquidation_tracker: LiquidationTracker::with_thresholds(
+                min_cascade_usd,
+                config.liquidation_cooldown_minutes.map_or(liquidations::DEFAULT_COOLDOWN_MS, |m| m as i64 * 60_000),
+            ),
+            liquidation_stream: None,
+            liquidation_stream_enabled: config.enable_liquidation_stream.unwrap_or(true),
+            btc_leader: config.enable_btc_leader_filter.unwrap_or(true).then(BtcLeaderFilter::new),
+            score_journal,
+            score_outcomes,
+            calibration,
             risk_manager: AdaptiveRiskManager::new(
                 config.risk_per_trade_base,
                 config.risk_per_trade_min,
                 config.risk_per_trade_max,
             ),
-            entry_manager: SmartEntryManager::new(config.min_confluence_score),
-            tp_manager: DynamicTPManager::new(),
+            daily_loss: config.daily_loss_ledger()?,
+            correlation_matrix: config.correlation_matrix()?,
+            portfolio: PortfolioManager::new(config.portfolio_limits()?),
+            challenge_rules: config.challenge_rules()?,
+            violation_log: ViolationLog::new(config.data_dir().join("challenge_violations.jsonl")),
             news_calendar: NewsCalendar::new(),
+            session_calendar,
+            setup_timeframes,
             alerter,
             metrics_calculator: MetricsCalculator::new(1000),
             initial_balance,
             current_balance: initial_balance,
-            valid_trading_days: 0,
+            trading_days,
             is_running: false,
+            last_bars: HashMap::new(),
+            positions: HashMap::new(),
+            entry_queue: VecDeque::new(),
+            order_books: HashMap::new(),
+            instruments: HashMap::new(),
             config,
             exchange,
-        }
+        })
+    }
+
+    // Strategies run alongside the built-in breakout-retest one
+    pub fn add_strategy(&mut self, strategy: Box<dyn Strategy>) {
+        info!("Strategy added: {}", strategy.settings().name);
+        self.strategies.push(strategy);
     }
 
     pub async fn start(&mut self) -> Result<()> {
@@ -79,15 +235,15 @@ impl TradingBot {
         if let Some(alerter) = &self.alerter {
             let startup_config = crate::monitoring::StartupConfig {
                 initial_balance: self.config.initial_capital,
-                target_profit_pct: self.config.target_profit_percent,
-                min_days: self.config.min_trading_days,
+                target_profit_pct: self.challenge_rules.limits.profit_target_percent.unwrap_or(0.0),
+                min_days: self.challenge_rules.limits.min_trading_days,
                 exchange_name: "Bybit".to_string(),
                 testnet: self.config.exchange_testnet,
                 risk_base: self.config.risk_per_trade_base,
                 risk_min: self.config.risk_per_trade_min,
                 risk_max: self.config.risk_per_trade_max,
                 min_confluence: self.config.min_confluence_score,
-                max_drawdown: self.config.max_total_dd_percent,
+                max_drawdown: self.challenge_rules.limits.max_drawdown_percent,
             };
 
             alerter.send_startup(
@@ -153,73 +309,938 @@ impl TradingBot {
         let account = self.exchange.get_account_balance().await?;
         self.update_balance(&account);
 
-        // 2. Check if it's safe to trade (news, market hours, etc.)
-        if !self.is_safe_to_trade() {
-            return Ok(());
+        // 2. News, weekends, holidays and no-trade windows only block new
+        // entries (see open_position), so open positions keep their stop
+        // checks and loss limits every cycle
+
+        // 3. Re-rank the universe once per UTC day, then trade the active assets
+        if self.config.enable_dynamic_asset_selection && self.asset_ranker.needs_rerank(chrono::Utc::now()) {
+            if let Err(e) = self.refresh_asset_universe().await {
+                warn!("Asset universe refresh failed, keeping {:?}: {}", self.asset_ranker.active_symbols(), e);
+            }
+        }
+
+        // Symbols and timeframes any strategy needs, plus open positions
+        let active = self.asset_ranker.active_symbols();
+        let mut symbols = active.clone();
+        let mut timeframes = MultiTimeframeData::DEFAULT_TIMEFRAMES.to_vec();
+        for strategy in &self.strategies {
+            symbols.extend(strategy.settings().symbols.iter().cloned());
+            timeframes.extend(strategy.settings().timeframes.iter().copied());
+        }
+        timeframes.extend(self.setup_timeframes.iter().copied());
+        symbols.extend(self.positions.keys().cloned());
+        if self.btc_leader.is_some() {
+            symbols.push(LEADER_SYMBOL.to_string());
         }
+        symbols.sort();
+        symbols.dedup();
+        timeframes.sort_by_key(|tf| tf.to_minutes());
+        timeframes.dedup();
+
+        self.sync_liquidation_stream(&symbols).await;
+        let mut market_data_map = HashMap::new();
+
+        for symbol in &symbols {
+            // One symbol's failed fetch must not stop the cycle for the others
+            let mut data = match self.exchange.get_multi_timeframe_data(symbol, &timeframes, 200).await {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Skipping {} this cycle: {}", symbol, e);
+                    continue;
+                }
+            };
 
-        // 3. Rank assets and select best candidates
-        let assets = vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()];
-        let mut market_data_map = std::collections::HashMap::new();
+            // A failed fetch drops the history so stale books are never scored
+            let history = self.order_books.entry(symbol.clone()).or_default();
+            match self.exchange.get_order_book(symbol, ORDER_BOOK_DEPTH).await {
+                Ok(book) => {
+                    history.push_back(book);
+                    if history.len() > ORDER_BOOK_HISTORY {
+                        history.pop_front();
+                    }
+                }
+                Err(e) => {
+                    warn!("Order book fetch failed for {}: {}", symbol, e);
+                    history.clear();
+                }
+            }
+            for book in history.iter() {
+                data.insert_order_book(book.clone());
+            }
+            let spread = data.order_book().and_then(|b| b.spread_bps());
 
-        for symbol in &assets {
-            let data = self.exchange.get_market_data(symbol, Timeframe::H1, 200).await?;
+            if let Some(h1) = data.aligned(Timeframe::H1) {
+                if let Some(change) = self.regime_detector.update(&h1, spread) {
+                    self.report_regime_change(&change).await;
+                }
+            }
+            if let Some(m15) = data.aligned(Timeframe::M15) {
+                if let Some(block) = self.volatility_guard.update(&m15, data.as_of) {
+                    self.report_volatility_block(&block).await;
+                }
+            }
+            if let Some(cascade) = self.liquidation_tracker.update(symbol, data.as_of) {
+                self.report_liquidation_cascade(&cascade).await;
+            }
+            data.insert_liquidations(self.liquidation_tracker.recent(symbol));
             market_data_map.insert(symbol.clone(), data);
         }
 
-        let ranked_assets = self.asset_ranker.rank_assets(assets, &market_data_map).await;
+        // BTC state and each symbol's beta to it for alt entries
+        if let (Some(leader), Some(btc)) = (&mut self.btc_leader, market_data_map.get(LEADER_SYMBOL)) {
+            leader.update(btc);
+            for data in market_data_map.values().filter(|d| d.symbol != LEADER_SYMBOL) {
+                leader.update_beta(data, btc);
+            }
+        }
 
-        info!("Asset rankings:");
-        for asset in &ranked_assets {
-            info!("  {} - Score: {:.1}", asset.symbol, asset.total_score);
+        if self.correlation_matrix.needs_refresh(chrono::Utc::now().timestamp_millis()) {
+            self.refresh_correlations(&symbols).await;
         }
 
-        // 4. Check for trade opportunities on top-ranked assets
-        for asset in ranked_assets.iter().take(2) {
-            if asset.total_score < 75.0 {
-                continue;
-            }
+        // Daily loss against the equity snapshot at the day boundary
+        self.update_daily_loss(&market_data_map).await?;
+        self.monitor_challenge_rules().await;
+
+        // 4. Feed newly closed bars and the timer to every strategy
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut intents = Vec::new();
+
+        for (index, strategy) in self.strategies.iter_mut().enumerate() {
+            let bar_timeframe = strategy.settings().bar_timeframe;
+
+            for symbol in &symbols {
+                let has_position = self.positions.get(symbol).is_some_and(|p| p.strategy == index);
+                if !has_position && !strategy.settings().trades(symbol, &active) {
+                    continue;
+                }
 
-            if let Some(data) = market_data_map.get(&asset.symbol) {
-                self.evaluate_trade_opportunity(&asset.symbol, data).await?;
+                let Some(data) = market_data_map.get(symbol) else {
+                    continue;
+                };
+                let Some(bar) = data.closed_candles(bar_timeframe).last() else {
+                    continue;
+                };
+
+                let bar_close = bar.close_time(bar_timeframe);
+                if self.last_bars.insert((index, symbol.clone()), bar_close) == Some(bar_close) {
+                    continue;
+                }
+
+                let ctx = StrategyContext {
+                    symbol: symbol.clone(),
+                    regime: self.regime_detector.current(symbol),
+                    balance: self.current_balance,
+                    has_position,
+                    now,
+                };
+                intents.extend(strategy.on_bar(data, &ctx).await.into_iter().map(|i| (index, i)));
             }
+
+            intents.extend(strategy.on_timer(now).into_iter().map(|i| (index, i)));
         }
 
+        // Queued entries go first, in the order they were queued
+        intents.splice(0..0, self.queued_entries(&market_data_map, now));
+        self.execute_intents(intents).await?;
+        self.entry_queue.retain(|q| !self.positions.contains_key(&q.entry.symbol));
+
         // 5. Manage open positions
-        self.manage_open_positions().await?;
+        self.manage_open_positions(&market_data_map).await?;
 
         Ok(())
     }
 
-    async fn evaluate_trade_opportunity(&mut self, symbol: &str, data: &crate::types::MarketData) -> Result<()> {
-        // Calculate confluence score
-        let confluence = self.confluence_scorer.calculate_score(data, Timeframe::M15).await;
+    // Recomputes correlations for the ranked symbols and those traded now
+    async fn refresh_correlations(&mut self, symbols: &[String]) {
+        let timeframe = self.correlation_matrix.timeframe();
+        let mut tracked: Vec<String> = self.asset_ranker.ranking().iter().map(|a| a.symbol.clone()).collect();
+        tracked.extend(symbols.iter().cloned());
+        tracked.sort();
+        tracked.dedup();
+
+        let mut series = HashMap::new();
+        for symbol in &tracked {
+            match self.exchange
+                .get_multi_timeframe_data(symbol, &[timeframe], self.correlation_matrix.bars_needed() + 1)
+                .await
+            {
+                Ok(data) => {
+                    if let Some(closed) = data.aligned(timeframe) {
+                        series.insert(symbol.clone(), closed);
+                    }
+                }
+                Err(e) => warn!("Skipping {} in correlations: {}", symbol, e),
+            }
+        }
+
+        self.correlation_matrix.update(&series, chrono::Utc::now().timestamp_millis());
+        let mut pairs: Vec<_> = self.correlation_matrix.pairs().collect();
+        pairs.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        info!("🔗 Correlations refreshed for {} symbols, {} pairs", series.len(), pairs.len());
+        for ((a, b), correlation) in pairs.iter().take(5) {
+            info!("  {}/{} {:+.2}", a, b, correlation);
+        }
+    }
+
+    // Discover the universe, score it on H1 and keep today's ranking
+    async fn refresh_asset_universe(&mut self) -> Result<()> {
+        let instruments = self.exchange.get_instruments().await?;
+        let tickers = self.exchange.get_tickers().await?;
+        let universe = self.asset_ranker.build_universe(&instruments, &tickers);
+        self.instruments = instruments
+            .into_iter()
+            .map(|i| (i.symbol.clone(), i))
+            .collect();
+        info!("🔎 Asset universe: {} symbols ({})", universe.len(), universe.join(", "));
 
-        if !confluence.is_valid {
-            return Ok(());
+        let mut h1_data_map = HashMap::new();
+        for symbol in &universe {
+            match self.exchange.get_market_data(symbol, Timeframe::H1, 200).await {
+                Ok(data) => {
+                    h1_data_map.insert(symbol.clone(), data);
+                }
+                Err(e) => warn!("Skipping {} in ranking: {}", symbol, e),
+            }
         }
 
-        info!("🎯 Potential setup on {} - Confluence: {}/100", symbol, confluence.total_score);
+        let tickers: HashMap<String, Ticker> = tickers
+            .into_iter()
+            .map(|t| (t.symbol.clone(), t))
+            .collect();
+        let ranking = self.asset_ranker.rank_assets(&h1_data_map, &tickers).await;
 
-        // TODO: Implement full trade entry logic
-        // - Identify swing high/low
-        // - Calculate Fibonacci zones
-        // - Validate entry with SmartEntryManager
-        // - Calculate position size with AdaptiveRiskManager
-        // - Place orders via exchange
-        // - Send alerts
+        info!("Asset rankings:");
+        for asset in &ranking {
+            info!("  {} - Score: {:.1} (vol {:.2}, trend {:.2}, liq {:.2}, spread {:.2})",
+                asset.symbol,
+                asset.total_score,
+                asset.volatility_score,
+                asset.trend_strength,
+                asset.liquidity_score,
+                asset.spread_cost
+            );
+        }
+
+        self.asset_ranker.set_ranking(ranking, chrono::Utc::now());
+        info!("✅ Active assets today: {}", self.asset_ranker.active_symbols().join(", "));
 
         Ok(())
     }
 
-    async fn manage_open_positions(&mut self) -> Result<()> {
-        // TODO: Implement position management
-        // - Update trailing stops
-        // - Check TP levels
-        // - Monitor for exit conditions
+    // Runs intents in order; fills go back to the owning strategy and any
+    // intents they produce are queued behind the current ones
+    async fn execute_intents(&mut self, intents: Vec<(usize, Intent)>) -> Result<()> {
+        let mut queue: VecDeque<(usize, Intent)> = intents.into();
+        self.portfolio.sync_open(self.position_exposures());
+
+        while let Some((index, intent)) = queue.pop_front() {
+            let fill = match intent {
+                Intent::Enter(entry) => self.open_position(index, entry).await?,
+                Intent::MoveStop { symbol, stop_loss, reason } => {
+                    self.move_stop(&symbol, stop_loss, &reason);
+                    None
+                }
+                Intent::Exit { symbol, price, size_percent, reason } => {
+                    self.reduce_position(&symbol, price, size_percent, &reason).await?
+                }
+            };
+
+            if let Some(fill) = fill {
+                // Later entries in the cycle are checked against this fill
+                self.portfolio.sync_open(self.position_exposures());
+                let follow_up = self.strategies[index].on_fill(&fill);
+                queue.extend(follow_up.into_iter().map(|i| (index, i)));
+            }
+        }
+
         Ok(())
     }
 
+    async fn open_position(&mut self, index: usize, entry: EntryIntent) -> Result<Option<Fill>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let strategy_name = self.strategies[index].settings().name.clone();
+
+        if let Some(reason) = self.daily_loss.blocked() {
+            info!("⏸️  {} entry from {} skipped - {}", entry.symbol, strategy_name, reason);
+            return Ok(None);
+        }
+
+        if !self.news_calendar.is_safe_to_trade(chrono::Utc::now()) {
+            info!("⏸️  {} entry from {} skipped - high-impact news ahead", entry.symbol, strategy_name);
+            return Ok(None);
+        }
+
+        // Weekend window (unless enabled), holidays and no-trade windows
+        if let Some(reason) = self.session_calendar.state(chrono::Utc::now()).no_trade {
+            info!("⏸️  {} entry from {} skipped - {}", entry.symbol, strategy_name, reason);
+            return Ok(None);
+        }
+
+        if let Some(block) = self.volatility_guard.blocked(&entry.symbol, now) {
+            info!("⏸️  {} entry from {} skipped - {}", entry.symbol, strategy_name, block.reason);
+            return Ok(None);
+        }
+
+        if let Some(cascade) = self.liquidation_tracker.blocked(&entry.symbol, now) {
+            info!("⏸️  {} entry from {} skipped - {}", entry.symbol, strategy_name, cascade.reason);
+            return Ok(None);
+        }
+
+        // Expected value gate once the entry's score bin has enough history;
+        // entries without a confluence score are not calibrated
+        if let (Some(min_r), true) = (self.config.min_expected_r, entry.confluence_score > 0) {
+            if let Some(expected_r) = self.calibration.expected_r(entry.confluence_score) {
+                if expected_r < min_r {
+                    info!("⏸️  {} entry from {} skipped - score {} expects {:+.2}R (min {:+.2}R)",
+                        entry.symbol, strategy_name, entry.confluence_score, expected_r, min_r);
+                    return Ok(None);
+                }
+            }
+        }
+
+        let risk_distance = (entry.entry_price - entry.stop_loss).abs();
+        if risk_distance.is_zero() {
+            warn!("Rejected {} entry from {}: stop equals entry", entry.symbol, strategy_name);
+            return Ok(None);
+        }
+
+        // Alts against BTC are skipped or shrunk, high-beta alts scaled down
+        let mut leader_factor = 1.0;
+        if let Some(leader) = &self.btc_leader {
+            match leader.evaluate(&entry.symbol, entry.direction) {
+                LeaderVerdict::Veto { reason } => {
+                    info!("⏸️  {} entry from {} skipped - {}", entry.symbol, strategy_name, reason);
+                    return Ok(None);
+                }
+                LeaderVerdict::Reduce { factor, reason } => {
+                    info!("🪙 {} entry from {} at {:.0}% risk - {}", entry.symbol, strategy_name, factor * 100.0, reason);
+                    leader_factor = factor;
+                }
+                LeaderVerdict::Allow => {}
+            }
+
+            let beta_factor = leader.beta_risk_factor(&entry.symbol);
+            if beta_factor < 1.0 {
+                info!("🪙 {} beta {:.2} to BTC, risk scaled to {:.0}%",
+                    entry.symbol,
+                    leader.beta(&entry.symbol).unwrap_or(1.0),
+                    beta_factor * 100.0
+                );
+            }
+            leader_factor *= beta_factor;
+        }
+
+        // Adaptive risk, capped by the strategy's own budget
+        let drawdown = Decimal::try_from(self.metrics_calculator.calculate().current_drawdown).unwrap_or_default();
+        let budget = Decimal::try_from(self.strategies[index].settings().risk_budget_percent).unwrap_or_default();
+        let risk_percent = (self.risk_manager
+            .calculate_risk_percent(self.current_balance, self.initial_balance, drawdown)
+            .min(budget) * Decimal::try_from(leader_factor).unwrap_or(Decimal::ONE))
+            .round_dp(4);
+
+        // Correlated positions share one risk cap
+        let exposures: Vec<Exposure> = self.positions
+            .iter()
+            .map(|(symbol, position)| Exposure {
+                symbol: symbol.clone(),
+                direction: position.direction,
+                risk_percent: position.info.risk_percent,
+            })
+            .collect();
+        let risk_percent = match self.correlation_matrix.check_exposure(
+            &entry.symbol,
+            entry.direction,
+            risk_percent.to_f64().unwrap_or(0.0),
+            &exposures,
+        ) {
+            ExposureVerdict::Allow => risk_percent,
+            ExposureVerdict::Reduce { factor, reason } => {
+                info!("🔗 {} entry from {} at {:.0}% risk - {}", entry.symbol, strategy_name, factor * 100.0, reason);
+                (risk_percent * Decimal::try_from(factor).unwrap_or(Decimal::ONE)).round_dp(4)
+            }
+            ExposureVerdict::Reject { reason } => {
+                info!("⏸️  {} entry from {} skipped - {}", entry.symbol, strategy_name, reason);
+                return Ok(None);
+            }
+        };
+
+        // Fees, slippage and the instrument's lot rules come out of the risk
+        let sizing = self.position_sizer(&entry.symbol).await
+            .size(risk_percent, self.current_balance, entry.entry_price, entry.stop_loss);
+        for adjustment in &sizing.adjustments {
+            info!("📐 {} size {}", entry.symbol, adjustment);
+        }
+        if !sizing.is_tradeable() {
+            warn!("Rejected {} entry from {}: size rounds to zero", entry.symbol, strategy_name);
+            return Ok(None);
+        }
+
+        // Portfolio limits: entries that could fit later wait in the queue
+        let candidate = PositionExposure {
+            symbol: entry.symbol.clone(),
+            direction: entry.direction,
+            notional: sizing.notional.to_f64().unwrap_or(0.0),
+            risk_at_stop: sizing.loss_at_stop.to_f64().unwrap_or(0.0),
+        };
+        let equity = (self.current_balance + self.daily_loss.unrealized()).to_f64().unwrap_or(0.0);
+        match self.portfolio.check(&candidate, equity) {
+            PortfolioDecision::Accept => {}
+            PortfolioDecision::Queue { reason } if self.config.entry_queue_ms() > 0 => {
+                self.queue_entry(index, entry, &strategy_name, &reason, now, equity);
+                return Ok(None);
+            }
+            PortfolioDecision::Queue { reason } | PortfolioDecision::Reject { reason } => {
+                info!("⏸️  {} entry from {} skipped - {} ({})", entry.symbol, strategy_name, reason, self.portfolio.summary(equity));
+                self.entry_queue.retain(|q| q.entry.symbol != entry.symbol);
+                return Ok(None);
+            }
+        }
+
+        // Firm rules: stop required, per-trade cap, and room left for a full stop-out
+        let order = ProposedOrder {
+            symbol: entry.symbol.clone(),
+            entry_price: entry.entry_price.to_f64().unwrap_or(0.0),
+            stop_loss: entry.stop_loss.to_f64(),
+            risk_percent: risk_percent.to_f64().unwrap_or(0.0),
+            loss_at_stop: sizing.loss_at_stop.to_f64().unwrap_or(0.0),
+        };
+        if let Err(violation) = self.challenge_rules.check_order(&self.account_state(), &order, now) {
+            warn!("⛔ {} entry from {} rejected - {}", entry.symbol, strategy_name, violation.reason);
+            self.record_violation(&violation);
+            return Ok(None);
+        }
+
+        let quantity = sizing.quantity;
+        info!("📐 {} {} units, ${:.0} notional at {:.2}x, ${:.2} at stop ({:.0}% of budget, fees ${:.2}, slippage ${:.2})",
+            entry.symbol,
+            quantity,
+            sizing.notional,
+            sizing.leverage,
+            sizing.loss_at_stop,
+            sizing.budget_used_percent(),
+            sizing.fees,
+            sizing.slippage
+        );
+
+        let side = match entry.direction {
+            TrendDirection::Long => OrderSide::Buy,
+            _ => OrderSide::Sell,
+        };
+        let order = self.exchange
+            .place_order(&entry.symbol, side, quantity.to_f64().unwrap_or(0.0), None)
+            .await?;
+
+        info!("📈 {} opened {:?} {} {} @ {} (SL {}, risk {}%) - {}",
+            strategy_name,
+            entry.direction,
+            quantity,
+            entry.symbol,
+            entry.entry_price,
+            entry.stop_loss,
+            risk_percent,
+            entry.reason
+        );
+
+        let info = TradeInfo {
+            symbol: entry.symbol.clone(),
+            direction: format!("{:?}", entry.direction),
+            entry_price: entry.entry_price.to_f64().unwrap_or(0.0),
+            stop_loss: entry.stop_loss.to_f64().unwrap_or(0.0),
+            tp1: entry.targets.tp1.price.to_f64().unwrap_or(0.0),
+            tp2: entry.targets.tp2.price.to_f64().unwrap_or(0.0),
+            tp3: entry.targets.tp3.price.to_f64().unwrap_or(0.0),
+            risk_percent: risk_percent.to_f64().unwrap_or(0.0),
+            confluence_score: entry.confluence_score,
+            size: quantity.to_f64().unwrap_or(0.0),
+        };
+
+        if let Some(alerter) = &self.alerter {
+            alerter.send_trade_opened(&info).await.ok();
+        }
+        self.record_trading_day(TradeEvent::Opened, info.risk_percent).await;
+
+        self.positions.insert(entry.symbol.clone(), OpenPosition {
+            strategy: index,
+            direction: entry.direction,
+            entry_price: entry.entry_price,
+            risk_amount: risk_distance * quantity,
+            initial_quantity: quantity,
+            quantity,
+            stop_loss: entry.stop_loss,
+            opened_at: now,
+            realized_pnl: Decimal::ZERO,
+            info,
+        });
+        self.portfolio.commit(candidate);
+
+        Ok(Some(Fill {
+            symbol: entry.symbol.clone(),
+            order_id: order.id,
+            kind: FillKind::Open,
+            direction: entry.direction,
+            price: entry.entry_price,
+            quantity,
+            timestamp: now,
+            entry: Some(entry),
+        }))
+    }
+
+    // Current exposure of open positions; risk is what the stop would lose
+    // from entry, 0 once the stop is at or past breakeven
+    fn position_exposures(&self) -> Vec<PositionExposure> {
+        self.positions
+            .iter()
+            .map(|(symbol, position)| {
+                let sign = if position.direction == TrendDirection::Short { -Decimal::ONE } else { Decimal::ONE };
+                let risk = ((position.entry_price - position.stop_loss) * position.quantity * sign).max(Decimal::ZERO);
+                PositionExposure {
+                    symbol: symbol.clone(),
+                    direction: position.direction,
+                    notional: (position.entry_price * position.quantity).to_f64().unwrap_or(0.0),
+                    risk_at_stop: risk.to_f64().unwrap_or(0.0),
+                }
+            })
+            .collect()
+    }
+
+    // Keeps one entry per symbol; a newer intent replaces the queued one but
+    // not its place or its wait
+    fn queue_entry(&mut self, index: usize, entry: EntryIntent, strategy_name: &str, reason: &str, now: i64, equity: f64) {
+        if let Some(queued) = self.entry_queue.iter_mut().find(|q| q.entry.symbol == entry.symbol) {
+            queued.strategy = index;
+            queued.entry = entry;
+            return;
+        }
+
+        info!("⏳ {} entry from {} queued - {} ({}, {} queued)",
+            entry.symbol,
+            strategy_name,
+            reason,
+            self.portfolio.summary(equity),
+            self.entry_queue.len() + 1
+        );
+        self.entry_queue.push_back(QueuedEntry {
+            strategy: index,
+            entry,
+            queued_at: now,
+        });
+    }
+
+    // Queued entries to retry this cycle. Expired ones are dropped, and so
+    // are those whose price has moved half the stop distance away from the
+    // planned entry or through the stop.
+    fn queued_entries(&mut self, market_data: &HashMap<String, MultiTimeframeData>, now: i64) -> Vec<(usize, Intent)> {
+        let ttl = self.config.entry_queue_ms();
+        self.entry_queue.retain(|q| {
+            let entry = &q.entry;
+            if now - q.queued_at > ttl {
+                info!("⌛ Queued {} entry expired after {} min", entry.symbol, (now - q.queued_at) / 60_000);
+                return false;
+            }
+
+            let Some(price) = market_data.get(&entry.symbol).and_then(|d| d.aligned(Timeframe::M5)).map(|d| d.close()) else {
+                return true;
+            };
+            let stop_distance = (entry.entry_price - entry.stop_loss).abs();
+            let through_stop = match entry.direction {
+                TrendDirection::Short => price >= entry.stop_loss,
+                _ => price <= entry.stop_loss,
+            };
+            if through_stop || (price - entry.entry_price).abs() * Decimal::TWO > stop_distance {
+                info!("⌛ Queued {} entry dropped - price {} moved away from entry {}", entry.symbol, price, entry.entry_price);
+                return false;
+            }
+            true
+        });
+
+        self.entry_queue
+            .iter()
+            .map(|q| (q.strategy, Intent::Enter(q.entry.clone())))
+            .collect()
+    }
+
+    // Sizer with the symbol's lot rules, fetched once; defaults without them
+    async fn position_sizer(&mut self, symbol: &str) -> PositionSizer {
+        if !self.instruments.contains_key(symbol) {
+            match self.exchange.get_instruments().await {
+                Ok(instruments) => self.instruments = instruments
+                    .into_iter()
+                    .map(|i| (i.symbol.clone(), i))
+                    .collect(),
+                Err(e) => warn!("Could not fetch instrument rules, sizing {} without them: {}", symbol, e),
+            }
+        }
+
+        let mut sizer = PositionSizer::new()
+            .with_max_leverage(self.config.max_leverage.unwrap_or(position_sizer::DEFAULT_MAX_LEVERAGE))
+            .with_taker_fee(self.config.taker_fee_percent.map_or(position_sizer::DEFAULT_TAKER_FEE, |f| f / 100.0))
+            .with_slippage_bps(self.config.slippage_bps.unwrap_or(position_sizer::DEFAULT_SLIPPAGE_BPS));
+        if let Some(instrument) = self.instruments.get(symbol) {
+            sizer = sizer.for_instrument(instrument);
+        }
+        sizer
+    }
+
+    // Stops only ever move in the position's favour
+    fn move_stop(&mut self, symbol: &str, stop_loss: Decimal, reason: &str) {
+        let Some(position) = self.positions.get_mut(symbol) else {
+            return;
+        };
+
+        let tighter = match position.direction {
+            TrendDirection::Long => stop_loss > position.stop_loss,
+            TrendDirection::Short => stop_loss < position.stop_loss,
+            TrendDirection::Neutral => false,
+        };
+
+        if tighter {
+            info!("🛡️  {} stop {} -> {} ({})", symbol, position.stop_loss, stop_loss, reason);
+            position.stop_loss = stop_loss;
+        }
+    }
+
+    async fn reduce_position(
+        &mut self,
+        symbol: &str,
+        price: Decimal,
+        size_percent: Decimal,
+        reason: &str,
+    ) -> Result<Option<Fill>> {
+        if !self.positions.contains_key(symbol) {
+            return Ok(None);
+        }
+        let sizer = self.position_sizer(symbol).await;
+        let Some(position) = self.positions.get(symbol) else {
+            return Ok(None);
+        };
+
+        // Rounded down so a partial close never exceeds its share or the venue step
+        let quantity = sizer
+            .round_down(position.initial_quantity * size_percent / Decimal::from(100))
+            .min(position.quantity);
+        if quantity <= Decimal::ZERO {
+            return Ok(None);
+        }
+
+        let (side, sign) = match position.direction {
+            TrendDirection::Long => (OrderSide::Sell, Decimal::ONE),
+            _ => (OrderSide::Buy, -Decimal::ONE),
+        };
+        let order = self.exchange
+            .place_order(symbol, side, quantity.to_f64().unwrap_or(0.0), None)
+            .await?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let pnl = (price - position.entry_price) * quantity * sign;
+
+        let Some(position) = self.positions.get_mut(symbol) else {
+            return Ok(None);
+        };
+        position.quantity -= quantity;
+        position.realized_pnl += pnl;
+        let direction = position.direction;
+        self.daily_loss.record_realized(pnl);
+
+        info!("📉 Closed {} of {} @ {} ({}) P&L ${:.2}", quantity, symbol, price, reason, pnl);
+
+        let kind = if position.quantity.is_zero() {
+            if let Some(position) = self.positions.remove(symbol) {
+                self.close_trade(symbol, position, price, reason, now).await;
+            }
+            FillKind::Close
+        } else {
+            FillKind::Reduce
+        };
+
+        Ok(Some(Fill {
+            symbol: symbol.to_string(),
+            order_id: order.id,
+            kind,
+            direction,
+            price,
+            quantity,
+            timestamp: now,
+            entry: None,
+        }))
+    }
+
+    async fn close_trade(&mut self, symbol: &str, position: OpenPosition, exit_price: Decimal, reason: &str, now: i64) {
+        let pnl = position.realized_pnl;
+
+        self.risk_manager.record_trade(TradeResult {
+            is_win: pnl > Decimal::ZERO,
+            pnl,
+            timestamp: now,
+        });
+        self.metrics_calculator.add_trade(CompletedTrade {
+            symbol: symbol.to_string(),
+            pnl: pnl.to_f64().unwrap_or(0.0),
+            entry_time: position.opened_at,
+            exit_time: now,
+            direction: position.info.direction.clone(),
+        });
+
+        info!("🏁 {} trade closed, total P&L ${:.2}", symbol, pnl);
+        self.record_trading_day(TradeEvent::Closed, position.info.risk_percent).await;
+
+        if position.info.confluence_score > 0 && !position.risk_amount.is_zero() {
+            self.record_score_outcome(symbol, &position, now);
+        }
+
+        if let Some(alerter) = &self.alerter {
+            alerter.send_trade_closed(
+                &position.info,
+                exit_price.to_f64().unwrap_or(0.0),
+                pnl.to_f64().unwrap_or(0.0),
+                reason
+            ).await.ok();
+        }
+
+        self.send_balance_notification().await;
+    }
+
+    async fn record_trading_day(&mut self, event: TradeEvent, risk_percent: f64) {
+        let day = match self.trading_days.record(event, chrono::Utc::now(), risk_percent) {
+            Ok(Some(day)) => day,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Could not save the trading day count: {}", e);
+                return;
+            }
+        };
+
+        let count = self.trading_days.count();
+        let min_days = self.challenge_rules.limits.min_trading_days;
+        info!("📆 New valid trading day {} ({}/{})", day, count, min_days);
+        if let Some(alerter) = &self.alerter {
+            alerter.send_alert(
+                &format!("Nuevo día de trading válido {}: {}/{} días", day, count, min_days),
+                crate::monitoring::AlertLevel::Info
+            ).await.ok();
+        }
+    }
+
+    fn record_score_outcome(&mut self, symbol: &str, position: &OpenPosition, now: i64) {
+        let outcome = ScoredOutcome {
+            symbol: symbol.to_string(),
+            strategy: self.strategies[position.strategy].settings().name.clone(),
+            direction: position.direction,
+            score: position.info.confluence_score,
+            r_multiple: (position.realized_pnl / position.risk_amount).to_f64().unwrap_or(0.0),
+            closed_at: now,
+        };
+
+        if let Err(e) = self.score_journal.append(&outcome) {
+            warn!("Could not write the confluence journal: {}", e);
+        }
+        self.score_outcomes.push(outcome);
+        self.calibration = ScoreCalibration::fit(&self.score_outcomes, self.calibration.method);
+    }
+
+    async fn report_regime_change(&self, change: &RegimeChange) {
+        let reading = &change.reading;
+        info!("🌡️  {} regime {:?} -> {:?} (ADX {:.1}, ATR pct {:.0}, BB pct {:.0}, volume {:.2}x)",
+            change.symbol,
+            change.from,
+            change.to,
+            reading.adx,
+            reading.atr_percentile,
+            reading.bandwidth_percentile,
+            reading.volume_ratio
+        );
+
+        // First reading of a symbol is only logged
+        let Some(from) = change.from else {
+            return;
+        };
+
+        if let Some(alerter) = &self.alerter {
+            let level = if change.to.is_hostile() {
+                crate::monitoring::AlertLevel::Warning
+            } else {
+                crate::monitoring::AlertLevel::Info
+            };
+
+            alerter.send_alert(
+                &format!("Cambio de régimen en {}: {:?} → {:?} (ADX {:.1})", change.symbol, from, change.to, reading.adx),
+                level
+            ).await.ok();
+        }
+    }
+
+    async fn report_volatility_block(&self, block: &VolatilityBlock) {
+        warn!("🌪️  {} entries blocked for {} min: {}",
+            block.symbol,
+            (block.until - block.triggered_at) / 60_000,
+            block.reason
+        );
+
+        if let Some(alerter) = &self.alerter {
+            alerter.send_alert(
+                &format!("Volatilidad extrema en {}: {}. Sin nuevas entradas por {} min",
+                    block.symbol,
+                    block.reason,
+                    (block.until - block.triggered_at) / 60_000
+                ),
+                crate::monitoring::AlertLevel::Warning
+            ).await.ok();
+        }
+    }
+
+    // Keeps the liquidation feed on the symbols being traded and moves what
+    // it received into the tracker
+    async fn sync_liquidation_stream(&mut self, symbols: &[String]) {
+        if !self.liquidation_stream_enabled {
+            return;
+        }
+
+        let stale = self.liquidation_stream
+            .as_ref()
+            .is_none_or(|s| s.symbols != symbols || s.is_finished());
+        if stale {
+            match self.exchange.subscribe_liquidations(symbols).await {
+                Ok(stream) => self.liquidation_stream = Some(stream),
+                Err(e) => {
+                    warn!("Liquidation stream unavailable, cascade detection disabled: {}", e);
+                    self.liquidation_stream_enabled = false;
+                    return;
+                }
+            }
+        }
+
+        if let Some(stream) = &mut self.liquidation_stream {
+            for liquidation in stream.drain() {
+                self.liquidation_tracker.record(liquidation);
+            }
+        }
+    }
+
+    async fn report_liquidation_cascade(&self, cascade: &LiquidationCascade) {
+        warn!("💥 {} entries blocked for {} min: {}",
+            cascade.symbol,
+            (cascade.until - cascade.triggered_at) / 60_000,
+            cascade.reason
+        );
+
+        if let Some(alerter) = &self.alerter {
+            alerter.send_alert(
+                &format!("Cascada de liquidaciones en {}: {}. Sin nuevas entradas por {} min",
+                    cascade.symbol,
+                    cascade.reason,
+                    (cascade.until - cascade.triggered_at) / 60_000
+                ),
+                crate::monitoring::AlertLevel::Warning
+            ).await.ok();
+        }
+    }
+
+    // Marks open positions to the latest M5 close, rolls the trading day and
+    // reacts to warnings and the daily limit
+    async fn update_daily_loss(&mut self, market_data: &HashMap<String, MultiTimeframeData>) -> Result<()> {
+        let now = chrono::Utc::now();
+        let mut prices = HashMap::new();
+        let mut unrealized = Decimal::ZERO;
+        for (symbol, position) in &self.positions {
+            let Some(price) = market_data.get(symbol).and_then(|d| d.aligned(Timeframe::M5)).map(|d| d.close()) else {
+                continue;
+            };
+            let sign = if position.direction == TrendDirection::Short { -Decimal::ONE } else { Decimal::ONE };
+            unrealized += (price - position.entry_price) * position.quantity * sign;
+            prices.insert(symbol.clone(), price);
+        }
+
+        for event in self.daily_loss.update(now, self.current_balance, unrealized) {
+            match event {
+                DailyLossEvent::NewDay(snapshot) => {
+                    info!("📅 Trading day {} started with equity ${:.2}", snapshot.day, snapshot.start_equity);
+                }
+                DailyLossEvent::Warning { level, loss_percent } => {
+                    warn!("⚠️  Daily loss {:.2}% - {:.0}% of the {:.2}% limit used",
+                        loss_percent, level, self.config.max_daily_loss_percent);
+                    if let Some(alerter) = &self.alerter {
+                        alerter.send_alert(
+                            &format!("Pérdida diaria {:.2}%: {:.0}% del límite de {:.2}%",
+                                loss_percent, level, self.config.max_daily_loss_percent),
+                            crate::monitoring::AlertLevel::Warning
+                        ).await.ok();
+                    }
+                }
+                DailyLossEvent::LimitHit { loss_percent, flatten } => {
+                    let resume = self.daily_loss.next_reset(now);
+                    warn!("🛑 Daily loss limit hit ({:.2}%), no new entries until {}", loss_percent, resume.format("%Y-%m-%d %H:%M UTC"));
+                    if let Some(alerter) = &self.alerter {
+                        alerter.send_alert(
+                            &format!("Límite de pérdida diaria alcanzado ({:.2}%). Sin nuevas entradas hasta {}{}",
+                                loss_percent,
+                                resume.format("%Y-%m-%d %H:%M UTC"),
+                                if flatten { ". Cerrando posiciones" } else { "" }
+                            ),
+                            crate::monitoring::AlertLevel::Critical
+                        ).await.ok();
+                    }
+
+                    if flatten {
+                        let exits = self.positions
+                            .iter()
+                            .filter_map(|(symbol, position)| {
+                                Some((position.strategy, Intent::Exit {
+                                    symbol: symbol.clone(),
+                                    price: *prices.get(symbol)?,
+                                    size_percent: Decimal::from(100),
+                                    reason: "Daily loss limit".to_string(),
+                                }))
+                            })
+                            .collect();
+                        self.execute_intents(exits).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Stops are enforced here on the latest M5 close for every strategy,
+    // after pulling them in while the volatility guard blocks the symbol
+    async fn manage_open_positions(&mut self, market_data: &HashMap<String, MultiTimeframeData>) -> Result<()> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut stopped = Vec::new();
+
+        for (symbol, position) in self.positions.iter_mut() {
+            let Some(data) = market_data.get(symbol) else {
+                continue;
+            };
+            let Some(m5) = data.aligned(Timeframe::M5) else {
+                continue;
+            };
+            let price = m5.close();
+            let atr = data.aligned(Timeframe::M15).map(|d| d.atr(14)).unwrap_or_default();
+
+            if let Some(stop) = self.volatility_guard.tightened_stop(symbol, position.direction, price, position.stop_loss, atr, now) {
+                info!("🛡️  {} stop tightened {} -> {} (extreme volatility)", symbol, position.stop_loss, stop);
+                position.stop_loss = stop;
+            }
+
+            let hit = match position.direction {
+                TrendDirection::Long => price <= position.stop_loss,
+                TrendDirection::Short => price >= position.stop_loss,
+                TrendDirection::Neutral => false,
+            };
+            if hit {
+                stopped.push((position.strategy, Intent::Exit {
+                    symbol: symbol.clone(),
+                    price,
+                    size_percent: Decimal::from(100),
+                    reason: format!("Stop loss {}", position.stop_loss),
+                }));
+            }
+        }
+
+        self.execute_intents(stopped).await
+    }
+
     fn update_balance(&mut self, account: &AccountBalance) {
         self.current_balance = Decimal::try_from(account.total_balance_usdt).unwrap();
 
@@ -238,14 +1259,15 @@ impl TradingBot {
             let balance_info = crate::monitoring::BalanceInfo {
                 initial: self.initial_balance.to_string().parse().unwrap_or(0.0),
                 current: self.current_balance.to_string().parse().unwrap_or(0.0),
-                target: self.initial_balance.to_string().parse::<f64>().unwrap_or(0.0) * 1.10,
+                target: self.initial_balance.to_string().parse::<f64>().unwrap_or(0.0)
+                    * (1.0 + self.challenge_rules.limits.profit_target_percent.unwrap_or(0.0) / 100.0),
                 pnl: (self.current_balance - self.initial_balance).to_string().parse().unwrap_or(0.0),
                 pnl_pct: ((self.current_balance - self.initial_balance) / self.initial_balance * Decimal::from(100)).to_string().parse().unwrap_or(0.0),
                 drawdown: metrics.current_drawdown,
                 total_trades: metrics.total_trades,
                 win_rate: metrics.win_rate * 100.0,
-                valid_days: self.valid_trading_days,
-                min_days: self.config.min_trading_days,
+                valid_days: self.trading_days.count(),
+                min_days: self.challenge_rules.limits.min_trading_days,
             };
 
             if let Err(e) = alerter.send_balance_update(&balance_info).await {
@@ -254,45 +1276,79 @@ impl TradingBot {
         }
     }
 
-    fn is_safe_to_trade(&self) -> bool {
-        // Check news calendar
-        let now = chrono::Utc::now();
-        if !self.news_calendar.is_safe_to_trade(now) {
-            warn!("⛔ Trading blocked due to upcoming high-impact news");
-            return false;
+    fn has_reached_target(&self) -> bool {
+        self.challenge_rules.target_reached(&self.account_state())
+    }
+
+    fn account_state(&self) -> AccountState {
+        let balance = self.current_balance.to_f64().unwrap_or(0.0);
+        // Equity already carries the unrealized P&L, so the loss still to come
+        // is entry-to-stop plus what the positions are up now
+        let stop_pnl: Decimal = self.positions
+            .values()
+            .map(|p| {
+                let sign = if p.direction == TrendDirection::Short { -Decimal::ONE } else { Decimal::ONE };
+                (p.stop_loss - p.entry_price) * p.quantity * sign
+            })
+            .sum();
+        let open_risk_at_stop = (self.daily_loss.unrealized() - stop_pnl).max(Decimal::ZERO);
+
+        AccountState {
+            initial_balance: self.initial_balance.to_f64().unwrap_or(0.0),
+            equity: balance + self.daily_loss.unrealized().to_f64().unwrap_or(0.0),
+            daily_loss_percent: self.daily_loss.loss_percent(),
+            day_start_equity: self.daily_loss
+                .snapshot()
+                .and_then(|s| s.start_equity.to_f64())
+                .unwrap_or(balance),
+            open_risk_at_stop: open_risk_at_stop.to_f64().unwrap_or(0.0),
+            valid_trading_days: self.trading_days.count(),
         }
+    }
 
-        // Check if it's weekend (if weekend trading is disabled)
-        if !self.config.weekend_trading_enabled {
-            let weekday = now.weekday();
-            if weekday == chrono::Weekday::Sat || weekday == chrono::Weekday::Sun {
-                return false;
+    // Live check of the firm's loss limits and the distance left to each
+    async fn monitor_challenge_rules(&mut self) {
+        let account = self.account_state();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        info!("📏 {}", self.challenge_rules
+            .status(&account)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(", "));
+
+        for violation in self.challenge_rules.monitor(&account, now) {
+            error!("🚨 Challenge rule breached: {}", violation.reason);
+            self.record_violation(&violation);
+            if let Some(alerter) = &self.alerter {
+                alerter.send_alert(
+                    &format!("Regla del challenge incumplida: {}", violation.reason),
+                    crate::monitoring::AlertLevel::Critical
+                ).await.ok();
             }
         }
-
-        true
     }
 
-    fn has_reached_target(&self) -> bool {
-        let profit = self.current_balance - self.initial_balance;
-        let target = self.initial_balance * Decimal::try_from(self.config.target_profit_percent / 100.0).unwrap();
-
-        profit >= target && self.valid_trading_days >= self.config.min_trading_days
+    fn record_violation(&self, violation: &RuleViolation) {
+        if let Err(e) = self.violation_log.append(violation) {
+            warn!("Could not write the violation log: {}", e);
+        }
     }
 
     fn should_stop_trading(&self) -> bool {
         let metrics = self.metrics_calculator.calculate();
 
-        // Kill-switch condition 1: Daily loss exceeds 1.0%
-        // TODO: Track daily P&L
+        // Kill-switch condition 1: the daily loss limit only pauses entries
+        // until the next day boundary, see `update_daily_loss`
 
-        // Kill-switch condition 2: Total drawdown exceeds 8.0%
-        if metrics.max_drawdown > 8.0 {
+        // Kill-switch condition 2: Total drawdown exceeds the bot's own limit
+        if metrics.max_drawdown > self.config.max_total_dd_percent {
             return true;
         }
 
-        // Kill-switch condition 3: Consecutive losses reach 4
-        if metrics.consecutive_losses >= 4 {
+        // Kill-switch condition 3: Consecutive losses reach the limit (default 4)
+        if metrics.consecutive_losses >= self.config.max_consecutive_losses.unwrap_or(4) {
             return true;
         }
 
@@ -304,6 +1360,164 @@ impl TradingBot {
     }
 
     pub fn get_metrics(&self) -> PerformanceMetrics {
-        self.metrics_calculator.calculate()
+        PerformanceMetrics {
+            valid_trading_days: self.trading_days.count() as usize,
+            ..self.metrics_calculator.calculate()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::NaiveTime;
+    use std::sync::Mutex;
+    use crate::exchange::{Order, OrderStatus, OrderType};
+    use crate::intelligence::session_calendar::NoTradeWindow;
+    use crate::types::{Candle, MarketData};
+
+    // Every symbol trades flat at `price`; orders fill immediately
+    struct FlatExchange {
+        price: Decimal,
+        orders: Mutex<Vec<(String, OrderSide, f64)>>,
+    }
+
+    #[async_trait]
+    impl ExchangeConnector for FlatExchange {
+        async fn get_market_data(&self, symbol: &str, timeframe: Timeframe, limit: usize) -> Result<MarketData> {
+            let step = timeframe.to_millis();
+            let last_closed = chrono::Utc::now().timestamp_millis() / step * step - step;
+            let candles = (0..limit as i64)
+                .map(|i| {
+                    let timestamp = last_closed - (limit as i64 - 1 - i) * step;
+                    Candle::new(timestamp, self.price, self.price, self.price, self.price, Decimal::ONE)
+                })
+                .collect();
+            Ok(MarketData {
+                symbol: symbol.to_string(),
+                candles,
+                timeframe,
+            })
+        }
+
+        async fn place_order(&self, symbol: &str, side: OrderSide, quantity: f64, price: Option<f64>) -> Result<Order> {
+            self.orders.lock().unwrap().push((symbol.to_string(), side, quantity));
+            Ok(Order {
+                id: "1".to_string(),
+                symbol: symbol.to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity,
+                price,
+                status: OrderStatus::Filled,
+                filled_quantity: quantity,
+                timestamp: 0,
+            })
+        }
+
+        async fn cancel_order(&self, _symbol: &str, _order_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_account_balance(&self) -> Result<AccountBalance> {
+            Ok(AccountBalance {
+                total_balance_usdt: 10_000.0,
+                available_balance_usdt: 10_000.0,
+                positions: Vec::new(),
+            })
+        }
+
+        async fn get_open_orders(&self, _symbol: &str) -> Result<Vec<Order>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_instruments(&self) -> Result<Vec<Instrument>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_tickers(&self) -> Result<Vec<Ticker>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_order_book(&self, _symbol: &str, _depth: usize) -> Result<OrderBook> {
+            anyhow::bail!("no order book")
+        }
+    }
+
+    fn config(data_dir: &std::path::Path) -> Config {
+        serde_json::from_value(serde_json::json!({
+            "challenge_mode": "one-step",
+            "initial_capital": 10_000.0,
+            "target_profit_percent": 10.0,
+            "min_trading_days": 10,
+            "risk_per_trade_base": 1.0,
+            "risk_per_trade_max": 1.5,
+            "risk_per_trade_min": 0.5,
+            "max_daily_loss_percent": 5.0,
+            "max_total_dd_percent": 10.0,
+            "min_confluence_score": 70,
+            "enable_dynamic_asset_selection": false,
+            "enable_news_filter": false,
+            "enable_atr_tp": true,
+            "enable_liquidation_stream": false,
+            "exchange_api_key": "",
+            "exchange_api_secret": "",
+            "exchange_testnet": true,
+            "enable_alerts": false,
+            "dashboard_port": 0,
+            "data_dir": data_dir.to_string_lossy(),
+            "log_level": "info",
+            "weekend_trading_enabled": false,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn open_position_is_stopped_out_inside_a_no_trade_window() {
+        let data_dir = std::env::temp_dir().join(format!("bot_no_trade_{}", std::process::id()));
+        let exchange = Arc::new(FlatExchange {
+            price: Decimal::from(90),
+            orders: Mutex::new(Vec::new()),
+        });
+        let mut bot = TradingBot::new(config(&data_dir), exchange.clone()).unwrap();
+
+        // The whole UTC day is closed to new entries
+        bot.session_calendar = SessionCalendar::new().with_no_trade_windows(vec![NoTradeWindow {
+            weekday: None,
+            start: NaiveTime::MIN,
+            end: NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+        }]);
+        assert!(bot.session_calendar.state(chrono::Utc::now()).no_trade.is_some());
+
+        bot.positions.insert("SOLUSDT".to_string(), OpenPosition {
+            strategy: 0,
+            direction: TrendDirection::Long,
+            entry_price: Decimal::from(100),
+            risk_amount: Decimal::from(5),
+            initial_quantity: Decimal::ONE,
+            quantity: Decimal::ONE,
+            stop_loss: Decimal::from(95),
+            opened_at: 0,
+            realized_pnl: Decimal::ZERO,
+            info: TradeInfo {
+                symbol: "SOLUSDT".to_string(),
+                direction: "Long".to_string(),
+                entry_price: 100.0,
+                stop_loss: 95.0,
+                tp1: 105.0,
+                tp2: 110.0,
+                tp3: 115.0,
+                risk_percent: 0.05,
+                confluence_score: 0,
+                size: 1.0,
+            },
+        });
+
+        bot.trading_cycle().await.unwrap();
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        assert!(bot.positions.is_empty());
+        assert_eq!(*exchange.orders.lock().unwrap(), vec![("SOLUSDT".to_string(), OrderSide::Sell, 1.0)]);
     }
 }
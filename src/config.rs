@@ -1,13 +1,30 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::intelligence::confluence_registry::parse_weights;
+use crate::intelligence::session_calendar::{self, SessionCalendar};
+use crate::intelligence::CalibrationMethod;
+use crate::intelligence::setup_rules::{self, RuleSetup};
+use crate::risk_v2::daily_loss::{self, DailyLossLedger, DayBoundary};
+use crate::risk_v2::{ChallengePhase, ChallengeRules, CorrelationMatrix, RuleLimits, ValidDayRule};
+use crate::risk_v2::correlation_matrix;
+use crate::risk_v2::portfolio::{self, PortfolioLimits};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     // Challenge
     pub challenge_mode: String,
+    // one-step, two-step-1, two-step-2 or funded; sets the firm's limits.
+    // Unset keeps one-step limits with the target and days below.
+    pub challenge_phase: Option<String>,
     pub initial_capital: f64,
     pub target_profit_percent: f64,
     pub min_trading_days: u32,
+    // What makes a UTC day count: "opened" or "opened_or_closed" (default),
+    // for trades risking at least this percent (default any)
+    pub valid_day_rule: Option<String>,
+    pub valid_day_min_risk_percent: Option<f64>,
 
     // Risk Management
     pub risk_per_trade_base: f64,
@@ -15,12 +32,71 @@ pub struct Config {
     pub risk_per_trade_min: f64,
     pub max_daily_loss_percent: f64,
     pub max_total_dd_percent: f64,
+    // Losing trades in a row that stop the bot (default 4)
+    pub max_consecutive_losses: Option<usize>,
+    // Start of the prop firm's trading day (default 00:00 UTC), alerts at
+    // these shares of the daily limit (default "50,80") and whether hitting
+    // the limit also closes open positions (default off)
+    pub daily_reset_time: Option<String>,
+    pub daily_reset_timezone: Option<String>,
+    pub daily_loss_warnings: Option<String>,
+    pub flatten_on_daily_loss: Option<bool>,
+    // Position sizing: leverage cap (default 10x), taker fee per side in
+    // percent (default 0.055) and expected slippage per fill (default 2 bps)
+    pub max_leverage: Option<f64>,
+    pub taker_fee_percent: Option<f64>,
+    pub slippage_bps: Option<f64>,
+    // Correlation of log returns between ranked symbols: "pearson" (default)
+    // or "spearman", over this many bars (default 100) of this timeframe
+    // (default 1h), refreshed every N minutes (default 60). Positions at or
+    // above the threshold (default 0.7) share a risk cap (default 1.5%).
+    pub correlation_method: Option<String>,
+    pub correlation_window: Option<usize>,
+    pub correlation_timeframe: Option<String>,
+    pub correlation_refresh_minutes: Option<u32>,
+    pub correlation_threshold: Option<f64>,
+    pub max_correlated_risk_percent: Option<f64>,
+    // Open positions at once (default 1), their combined loss at the stops
+    // (default 2%) and net notional on one side (default 300% of equity).
+    // Entries over a limit wait this many minutes for room (default 15,
+    // 0 rejects them right away).
+    pub max_concurrent_positions: Option<usize>,
+    pub max_total_risk_percent: Option<f64>,
+    pub max_net_exposure_percent: Option<f64>,
+    pub entry_queue_minutes: Option<u32>,
 
     // Strategy
     pub min_confluence_score: u8,
     pub enable_dynamic_asset_selection: bool,
+    // Universe filter for dynamic selection (defaults $50M and 15 symbols)
+    pub min_universe_turnover_usd: Option<f64>,
+    pub max_universe_size: Option<usize>,
+    // Mean-reversion strategy for ranging markets (default off until validated)
+    pub enable_range_reversion: Option<bool>,
     pub enable_news_filter: bool,
     pub enable_atr_tp: bool,
+    // Overrides for confluence signal weights, e.g. "breakout=25,session=5"
+    pub confluence_weights: Option<String>,
+    // Opposing directional points that veto a setup (default 20)
+    pub confluence_conflict_threshold: Option<u8>,
+    // TOML or YAML file of declarative setups scored as extra signals
+    pub setup_rules_file: Option<String>,
+    // Score -> win rate / expected R fit on the outcome journal: "binned" or
+    // "isotonic" (default), and the expected R entries need once calibrated
+    pub calibration_method: Option<String>,
+    pub min_expected_r: Option<f64>,
+    // Entry block after extreme volatility, in minutes (default 120)
+    pub volatility_cooldown_minutes: Option<u32>,
+    // Pull stops of open positions closer while the block is active
+    pub volatility_tighten_stops: Option<bool>,
+    // Bybit liquidation feed (default on), the notional one side must lose
+    // in 5 min to count as a cascade (default $1M) and the entry block after
+    // it, in minutes (default 30)
+    pub enable_liquidation_stream: Option<bool>,
+    pub liquidation_cascade_min_usd: Option<f64>,
+    pub liquidation_cooldown_minutes: Option<u32>,
+    // Veto or shrink alt entries that go against BTC (default on)
+    pub enable_btc_leader_filter: Option<bool>,
 
     // Exchange
     pub exchange_api_key: String,
@@ -34,10 +110,16 @@ pub struct Config {
 
     // Monitoring
     pub dashboard_port: u16,
+    // Where journals and state are kept (default "data")
+    pub data_dir: Option<String>,
     pub log_level: String,
 
     // Trading Session
     pub weekend_trading_enabled: bool,
+    // UTC dates without new entries, e.g. "2026-12-25,2027-01-01"
+    pub session_holidays: Option<String>,
+    // Extra UTC no-trade windows, e.g. "Fri 21:00-23:59,13:25-13:35"
+    pub session_no_trade_windows: Option<String>,
 }
 
 impl Config {
@@ -63,6 +145,168 @@ impl Config {
             anyhow::bail!("initial_capital must be > 0");
         }
 
+        if self.max_leverage.is_some_and(|l| l <= 0.0) {
+            anyhow::bail!("max_leverage must be > 0");
+        }
+
+        if self.taker_fee_percent.is_some_and(|f| f < 0.0) || self.slippage_bps.is_some_and(|s| s < 0.0) {
+            anyhow::bail!("taker_fee_percent and slippage_bps must be >= 0");
+        }
+
+        if self.confluence_conflict_threshold.is_some_and(|t| t > 100) {
+            anyhow::bail!("confluence_conflict_threshold must be <= 100");
+        }
+
+        self.confluence_weight_overrides()?;
+        self.session_calendar()?;
+        self.calibration_method()?;
+        self.setup_rules()?;
+        self.daily_loss_ledger()?;
+        self.valid_day_rule()?;
+        self.correlation_matrix()?;
+        self.portfolio_limits()?;
+
+        // The bot's own limits must sit inside the firm's
+        let limits = self.challenge_rules()?.limits;
+        if self.max_daily_loss_percent > limits.max_daily_loss_percent {
+            anyhow::bail!("max_daily_loss_percent must be <= the firm's {}%", limits.max_daily_loss_percent);
+        }
+        if self.max_total_dd_percent > limits.max_drawdown_percent {
+            anyhow::bail!("max_total_dd_percent must be <= the firm's {}%", limits.max_drawdown_percent);
+        }
+        if self.risk_per_trade_max > limits.max_risk_per_trade_percent {
+            anyhow::bail!("risk_per_trade_max must be <= the firm's {}% per trade", limits.max_risk_per_trade_percent);
+        }
+
         Ok(())
     }
+
+    pub fn confluence_weight_overrides(&self) -> Result<HashMap<String, u8>> {
+        match &self.confluence_weights {
+            Some(spec) => parse_weights(spec),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    pub fn session_calendar(&self) -> Result<SessionCalendar> {
+        let holidays = match &self.session_holidays {
+            Some(spec) => session_calendar::parse_holidays(spec)?,
+            None => Vec::new(),
+        };
+        let windows = match &self.session_no_trade_windows {
+            Some(spec) => session_calendar::parse_no_trade_windows(spec)?,
+            None => Vec::new(),
+        };
+
+        Ok(SessionCalendar::new()
+            .with_weekend_trading(self.weekend_trading_enabled)
+            .with_holidays(holidays)
+            .with_no_trade_windows(windows))
+    }
+
+    pub fn calibration_method(&self) -> Result<CalibrationMethod> {
+        match &self.calibration_method {
+            Some(method) => method.parse(),
+            None => Ok(CalibrationMethod::Isotonic),
+        }
+    }
+
+    pub fn setup_rules(&self) -> Result<Vec<RuleSetup>> {
+        match &self.setup_rules_file {
+            Some(path) => setup_rules::load_setup_rules(path),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn daily_loss_ledger(&self) -> Result<DailyLossLedger> {
+        let boundary = DayBoundary::parse(
+            self.daily_reset_timezone.as_deref().unwrap_or("UTC"),
+            self.daily_reset_time.as_deref().unwrap_or("00:00"),
+        )?;
+        let warnings = match &self.daily_loss_warnings {
+            Some(spec) => daily_loss::parse_warning_levels(spec)?,
+            None => daily_loss::DEFAULT_WARNING_LEVELS.to_vec(),
+        };
+
+        Ok(DailyLossLedger::new(self.max_daily_loss_percent)
+            .with_boundary(boundary)
+            .with_warning_levels(warnings)
+            .with_flatten_on_limit(self.flatten_on_daily_loss.unwrap_or(false)))
+    }
+
+    pub fn challenge_rules(&self) -> Result<ChallengeRules> {
+        match &self.challenge_phase {
+            Some(phase) => Ok(ChallengeRules::new(phase.parse()?)),
+            None => {
+                let limits = RuleLimits {
+                    profit_target_percent: Some(self.target_profit_percent),
+                    min_trading_days: self.min_trading_days,
+                    ..RuleLimits::for_phase(ChallengePhase::OneStep)
+                };
+                Ok(ChallengeRules::with_limits(ChallengePhase::OneStep, limits))
+            }
+        }
+    }
+
+    pub fn valid_day_rule(&self) -> Result<ValidDayRule> {
+        match &self.valid_day_rule {
+            Some(rule) => rule.parse(),
+            None => Ok(ValidDayRule::OpenedOrClosed),
+        }
+    }
+
+    pub fn correlation_matrix(&self) -> Result<CorrelationMatrix> {
+        let method = match &self.correlation_method {
+            Some(method) => method.parse()?,
+            None => correlation_matrix::CorrelationMethod::Pearson,
+        };
+        let timeframe = match &self.correlation_timeframe {
+            Some(timeframe) => timeframe.parse()?,
+            None => correlation_matrix::DEFAULT_TIMEFRAME,
+        };
+        let window = self.correlation_window.unwrap_or(correlation_matrix::DEFAULT_WINDOW);
+        if window < 30 {
+            anyhow::bail!("correlation_window must be >= 30 bars");
+        }
+        let threshold = self.correlation_threshold.unwrap_or(correlation_matrix::DEFAULT_THRESHOLD);
+        if !(0.0..=1.0).contains(&threshold) {
+            anyhow::bail!("correlation_threshold must be between 0 and 1");
+        }
+
+        Ok(CorrelationMatrix::new()
+            .with_method(method)
+            .with_window(window, timeframe)
+            .with_refresh_interval(self.correlation_refresh_minutes.map_or(correlation_matrix::DEFAULT_REFRESH_MS, |m| m as i64 * 60_000))
+            .with_exposure_limit(
+                threshold,
+                self.max_correlated_risk_percent.unwrap_or(correlation_matrix::DEFAULT_MAX_CORRELATED_RISK),
+            ))
+    }
+
+    pub fn portfolio_limits(&self) -> Result<PortfolioLimits> {
+        let limits = PortfolioLimits {
+            max_positions: self.max_concurrent_positions.unwrap_or(portfolio::DEFAULT_MAX_POSITIONS),
+            max_total_risk_percent: self.max_total_risk_percent.unwrap_or(portfolio::DEFAULT_MAX_TOTAL_RISK),
+            max_net_exposure_percent: self.max_net_exposure_percent.unwrap_or(portfolio::DEFAULT_MAX_NET_EXPOSURE),
+        };
+        if limits.max_positions == 0 {
+            anyhow::bail!("max_concurrent_positions must be >= 1");
+        }
+        // Otherwise a trade at full risk could never be taken
+        if limits.max_total_risk_percent < self.risk_per_trade_max {
+            anyhow::bail!("max_total_risk_percent must be >= risk_per_trade_max");
+        }
+        if limits.max_net_exposure_percent <= 0.0 {
+            anyhow::bail!("max_net_exposure_percent must be > 0");
+        }
+        Ok(limits)
+    }
+
+    pub fn entry_queue_ms(&self) -> i64 {
+        self.entry_queue_minutes.map_or(portfolio::DEFAULT_ENTRY_QUEUE_MS, |m| m as i64 * 60_000)
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        PathBuf::from(self.data_dir.as_deref().unwrap_or("data"))
+    }
 }
@@ -1,13 +1,15 @@
 use super::*;
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 
 pub struct BinanceConnector {
     client: Client,
+    #[allow(dead_code)] // Needed once signed endpoints are implemented
     api_key: String,
+    #[allow(dead_code)]
     api_secret: String,
     testnet: bool,
 }
@@ -30,23 +32,53 @@ impl BinanceConnector {
         }
     }
 
-    fn timeframe_to_interval(&self, timeframe: Timeframe) -> &str {
-        match timeframe {
-            Timeframe::M1 => "1m",
-            Timeframe::M5 => "5m",
-            Timeframe::M15 => "15m",
-            Timeframe::M30 => "30m",
-            Timeframe::H1 => "1h",
-            Timeframe::H4 => "4h",
-            Timeframe::D1 => "1d",
+    fn timeframe_to_interval(&self, timeframe: Timeframe) -> Option<&'static str> {
+        match timeframe.to_minutes() {
+            1 => Some("1m"),
+            3 => Some("3m"),
+            5 => Some("5m"),
+            15 => Some("15m"),
+            30 => Some("30m"),
+            60 => Some("1h"),
+            120 => Some("2h"),
+            240 => Some("4h"),
+            360 => Some("6h"),
+            480 => Some("8h"),
+            720 => Some("12h"),
+            1440 => Some("1d"),
+            4320 => Some("3d"),
+            10080 => Some("1w"),
+            _ => None,
         }
     }
 }
 
+const NATIVE_TIMEFRAMES: [Timeframe; 14] = [
+    Timeframe::M1,
+    Timeframe::Custom(3),
+    Timeframe::M5,
+    Timeframe::M15,
+    Timeframe::M30,
+    Timeframe::H1,
+    Timeframe::Custom(120),
+    Timeframe::H4,
+    Timeframe::Custom(360),
+    Timeframe::Custom(480),
+    Timeframe::Custom(720),
+    Timeframe::D1,
+    Timeframe::Custom(4320),
+    Timeframe::Custom(10080),
+];
+
+const MAX_KLINE_LIMIT: usize = 1000;
+const MAX_DEPTH_LIMIT: usize = 5000;
+
 #[async_trait]
 impl ExchangeConnector for BinanceConnector {
     async fn get_market_data(&self, symbol: &str, timeframe: Timeframe, limit: usize) -> Result<MarketData> {
-        let interval = self.timeframe_to_interval(timeframe);
+        let Some(interval) = self.timeframe_to_interval(timeframe) else {
+            return fetch_resampled(self, &NATIVE_TIMEFRAMES, MAX_KLINE_LIMIT, symbol, timeframe, limit).await;
+        };
         let url = format!("{}/klines", self.base_url());
 
         let response = self.client
@@ -54,7 +86,7 @@ impl ExchangeConnector for BinanceConnector {
             .query(&[
                 ("symbol", symbol),
                 ("interval", interval),
-                ("limit", &limit.to_string()),
+                ("limit", &limit.min(MAX_KLINE_LIMIT).to_string()),
             ])
             .send()
             .await?
@@ -70,6 +102,10 @@ impl ExchangeConnector for BinanceConnector {
                 low: Decimal::from_str(&k.3).unwrap_or(Decimal::ZERO),
                 close: Decimal::from_str(&k.4).unwrap_or(Decimal::ZERO),
                 volume: Decimal::from_str(&k.5).unwrap_or(Decimal::ZERO),
+                quote_volume: Decimal::from_str(&k.7).ok(),
+                trade_count: Some(k.8),
+                taker_buy_base_volume: Decimal::from_str(&k.9).ok(),
+                taker_buy_quote_volume: Decimal::from_str(&k.10).ok(),
             })
             .collect();
 
@@ -117,6 +153,82 @@ impl ExchangeConnector for BinanceConnector {
         // TODO: Implement open orders retrieval
         Ok(Vec::new())
     }
+
+    // Spot API, so every instrument is reported as Spot
+    async fn get_instruments(&self) -> Result<Vec<Instrument>> {
+        let url = format!("{}/exchangeInfo", self.base_url());
+        let response = self.client
+            .get(&url)
+            .send()
+            .await?
+            .json::<BinanceExchangeInfo>()
+            .await?;
+
+        Ok(response.symbols
+            .into_iter()
+            .map(|s| {
+                let filter = |kind: &str| s.filters.iter().find(|f| f.filter_type == kind);
+                let parse = |value: &Option<String>| value.as_deref().and_then(|v| v.parse().ok()).unwrap_or(0.0);
+
+                Instrument {
+                    kind: InstrumentKind::Spot,
+                    is_trading: s.status == "TRADING",
+                    tick_size: filter("PRICE_FILTER").map(|f| parse(&f.tick_size)).unwrap_or(0.0),
+                    contract_size: 1.0,
+                    qty_step: filter("LOT_SIZE").map(|f| parse(&f.step_size)).unwrap_or(0.0),
+                    min_order_qty: filter("LOT_SIZE").map(|f| parse(&f.min_qty)).unwrap_or(0.0),
+                    min_notional: filter("NOTIONAL")
+                        .or_else(|| filter("MIN_NOTIONAL"))
+                        .map(|f| parse(&f.min_notional))
+                        .unwrap_or(0.0),
+                    symbol: s.symbol.clone(),
+                    base_asset: s.base_asset.clone(),
+                    quote_asset: s.quote_asset.clone(),
+                }
+            })
+            .collect())
+    }
+
+    async fn get_tickers(&self) -> Result<Vec<Ticker>> {
+        let url = format!("{}/ticker/24hr", self.base_url());
+        let response = self.client
+            .get(&url)
+            .send()
+            .await?
+            .json::<Vec<BinanceTicker>>()
+            .await?;
+
+        Ok(response
+            .into_iter()
+            .map(|t| Ticker {
+                last_price: t.last_price.parse().unwrap_or(0.0),
+                bid_price: t.bid_price.parse().unwrap_or(0.0),
+                ask_price: t.ask_price.parse().unwrap_or(0.0),
+                volume_24h: t.volume.parse().unwrap_or(0.0),
+                turnover_24h: t.quote_volume.parse().unwrap_or(0.0),
+                symbol: t.symbol,
+            })
+            .collect())
+    }
+
+    async fn get_order_book(&self, symbol: &str, depth: usize) -> Result<OrderBook> {
+        let url = format!("{}/depth", self.base_url());
+        let response = self.client
+            .get(&url)
+            .query(&[("symbol", symbol), ("limit", &depth.clamp(1, MAX_DEPTH_LIMIT).to_string())])
+            .send()
+            .await?
+            .json::<BinanceDepth>()
+            .await?;
+
+        // The depth endpoint carries no timestamp
+        Ok(OrderBook {
+            symbol: symbol.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            bids: parse_book_levels(&response.bids)?,
+            asks: parse_book_levels(&response.asks)?,
+        })
+    }
 }
 
 // Binance API response types
@@ -128,10 +240,55 @@ struct BinanceKline(
     String,   // Low
     String,   // Close
     String,   // Volume
+    #[allow(dead_code)]
     i64,      // Close time
     String,   // Quote asset volume
     u64,      // Number of trades
     String,   // Taker buy base asset volume
     String,   // Taker buy quote asset volume
+    #[allow(dead_code)]
     String,   // Ignore
 );
+
+#[derive(Debug, Deserialize)]
+struct BinanceExchangeInfo {
+    symbols: Vec<BinanceSymbol>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceSymbol {
+    symbol: String,
+    status: String,
+    base_asset: String,
+    quote_asset: String,
+    filters: Vec<BinanceFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceFilter {
+    filter_type: String,
+    tick_size: Option<String>,
+    step_size: Option<String>,
+    min_qty: Option<String>,
+    min_notional: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceTicker {
+    symbol: String,
+    last_price: String,
+    bid_price: String,
+    ask_price: String,
+    volume: String,
+    quote_volume: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceDepth {
+    // [price, quantity] pairs, best first
+    bids: Vec<(String, String)>,
+    asks: Vec<(String, String)>,
+}
@@ -1,13 +1,19 @@
 use super::*;
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
+use futures_util::{SinkExt, StreamExt};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use rust_decimal::Decimal;
 use std::str::FromStr;
+use tokio::time::{interval, sleep, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use crate::types::{Liquidation, TrendDirection};
 
 pub struct BybitConnector {
     client: Client,
+    #[allow(dead_code)] // Needed once signed endpoints are implemented
     api_key: String,
+    #[allow(dead_code)]
     api_secret: String,
     testnet: bool,
 }
@@ -30,23 +36,61 @@ impl BybitConnector {
         }
     }
 
-    fn timeframe_to_interval(&self, timeframe: Timeframe) -> &str {
-        match timeframe {
-            Timeframe::M1 => "1",
-            Timeframe::M5 => "5",
-            Timeframe::M15 => "15",
-            Timeframe::M30 => "30",
-            Timeframe::H1 => "60",
-            Timeframe::H4 => "240",
-            Timeframe::D1 => "D",
+    fn public_stream_url(&self) -> &str {
+        if self.testnet {
+            "wss://stream-testnet.bybit.com/v5/public/linear"
+        } else {
+            "wss://stream.bybit.com/v5/public/linear"
+        }
+    }
+
+    fn timeframe_to_interval(&self, timeframe: Timeframe) -> Option<&'static str> {
+        match timeframe.to_minutes() {
+            1 => Some("1"),
+            3 => Some("3"),
+            5 => Some("5"),
+            15 => Some("15"),
+            30 => Some("30"),
+            60 => Some("60"),
+            120 => Some("120"),
+            240 => Some("240"),
+            360 => Some("360"),
+            720 => Some("720"),
+            1440 => Some("D"),
+            10080 => Some("W"),
+            _ => None,
         }
     }
 }
 
+const NATIVE_TIMEFRAMES: [Timeframe; 12] = [
+    Timeframe::M1,
+    Timeframe::Custom(3),
+    Timeframe::M5,
+    Timeframe::M15,
+    Timeframe::M30,
+    Timeframe::H1,
+    Timeframe::Custom(120),
+    Timeframe::H4,
+    Timeframe::Custom(360),
+    Timeframe::Custom(720),
+    Timeframe::D1,
+    Timeframe::Custom(10080),
+];
+
+const MAX_KLINE_LIMIT: usize = 1000;
+const MAX_ORDERBOOK_LIMIT: usize = 500;
+// Topics per subscribe request and the ping Bybit expects every 20s
+const MAX_TOPICS_PER_SUBSCRIBE: usize = 10;
+const STREAM_PING_SECS: u64 = 20;
+const MAX_RECONNECT_DELAY_SECS: u64 = 60;
+
 #[async_trait]
 impl ExchangeConnector for BybitConnector {
     async fn get_market_data(&self, symbol: &str, timeframe: Timeframe, limit: usize) -> Result<MarketData> {
-        let interval = self.timeframe_to_interval(timeframe);
+        let Some(interval) = self.timeframe_to_interval(timeframe) else {
+            return fetch_resampled(self, &NATIVE_TIMEFRAMES, MAX_KLINE_LIMIT, symbol, timeframe, limit).await;
+        };
         let url = format!("{}/v5/market/kline", self.base_url());
 
         tracing::info!("Fetching {} {} candles from Bybit...", symbol, interval);
@@ -57,7 +101,7 @@ impl ExchangeConnector for BybitConnector {
                 ("category", "linear"), // USDT perpetuals
                 ("symbol", symbol),
                 ("interval", interval),
-                ("limit", &limit.to_string()),
+                ("limit", &limit.min(MAX_KLINE_LIMIT).to_string()),
             ])
             .send()
             .await?;
@@ -76,18 +120,24 @@ impl ExchangeConnector for BybitConnector {
             return Err(anyhow!("Bybit API returned error: {}", api_response.ret_msg));
         }
 
-        let candles: Vec<Candle> = api_response.result.list
+        let mut candles: Vec<Candle> = api_response.result.list
             .into_iter()
             .map(|k| Candle {
-                timestamp: k.0.parse::<i64>().unwrap_or(0),
-                open: Decimal::from_str(&k.1).unwrap_or(Decimal::ZERO),
-                high: Decimal::from_str(&k.2).unwrap_or(Decimal::ZERO),
-                low: Decimal::from_str(&k.3).unwrap_or(Decimal::ZERO),
-                close: Decimal::from_str(&k.4).unwrap_or(Decimal::ZERO),
-                volume: Decimal::from_str(&k.5).unwrap_or(Decimal::ZERO),
+                quote_volume: Decimal::from_str(&k.6).ok(),
+                ..Candle::new(
+                    k.0.parse::<i64>().unwrap_or(0),
+                    Decimal::from_str(&k.1).unwrap_or(Decimal::ZERO),
+                    Decimal::from_str(&k.2).unwrap_or(Decimal::ZERO),
+                    Decimal::from_str(&k.3).unwrap_or(Decimal::ZERO),
+                    Decimal::from_str(&k.4).unwrap_or(Decimal::ZERO),
+                    Decimal::from_str(&k.5).unwrap_or(Decimal::ZERO),
+                )
             })
             .collect();
 
+        // Bybit returns newest first, indicators expect oldest first
+        candles.sort_by_key(|c| c.timestamp);
+
         tracing::info!("Fetched {} candles for {}", candles.len(), symbol);
 
         Ok(MarketData {
@@ -128,7 +178,7 @@ impl ExchangeConnector for BybitConnector {
     }
 
     async fn get_account_balance(&self) -> Result<AccountBalance> {
-        let url = format!("{}/v5/account/wallet-balance", self.base_url());
+        let _url = format!("{}/v5/account/wallet-balance", self.base_url());
 
         // TODO: Implement proper authentication
         // For now, return demo balance
@@ -145,6 +195,184 @@ impl ExchangeConnector for BybitConnector {
         // TODO: Implement open orders retrieval
         Ok(Vec::new())
     }
+
+    async fn get_instruments(&self) -> Result<Vec<Instrument>> {
+        let url = format!("{}/v5/market/instruments-info", self.base_url());
+        let mut instruments = Vec::new();
+        let mut cursor = String::new();
+
+        // Paginated, up to 1000 instruments per page
+        loop {
+            let mut query = vec![("category", "linear"), ("limit", "1000")];
+            if !cursor.is_empty() {
+                query.push(("cursor", cursor.as_str()));
+            }
+
+            let response: BybitInstrumentsResult = self.get_json(&url, &query).await?;
+            instruments.extend(response.list.into_iter().map(|i| Instrument {
+                kind: if i.contract_type == "LinearPerpetual" {
+                    InstrumentKind::LinearPerpetual
+                } else {
+                    InstrumentKind::Other
+                },
+                is_trading: i.status == "Trading",
+                tick_size: i.price_filter.tick_size.parse().unwrap_or(0.0),
+                // USDT linear contracts are quoted in the base coin
+                contract_size: 1.0,
+                qty_step: i.lot_size_filter.qty_step.parse().unwrap_or(0.0),
+                min_order_qty: i.lot_size_filter.min_order_qty.parse().unwrap_or(0.0),
+                min_notional: i.lot_size_filter.min_notional_value
+                    .as_deref()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0.0),
+                symbol: i.symbol,
+                base_asset: i.base_coin,
+                quote_asset: i.quote_coin,
+            }));
+
+            match response.next_page_cursor {
+                Some(next) if !next.is_empty() => cursor = next,
+                _ => break,
+            }
+        }
+
+        tracing::info!("Fetched {} linear instruments from Bybit", instruments.len());
+        Ok(instruments)
+    }
+
+    async fn get_tickers(&self) -> Result<Vec<Ticker>> {
+        let url = format!("{}/v5/market/tickers", self.base_url());
+        let response: BybitTickersResult = self.get_json(&url, &[("category", "linear")]).await?;
+
+        Ok(response.list
+            .into_iter()
+            .map(|t| Ticker {
+                last_price: t.last_price.parse().unwrap_or(0.0),
+                bid_price: t.bid1_price.parse().unwrap_or(0.0),
+                ask_price: t.ask1_price.parse().unwrap_or(0.0),
+                volume_24h: t.volume24h.parse().unwrap_or(0.0),
+                turnover_24h: t.turnover24h.parse().unwrap_or(0.0),
+                symbol: t.symbol,
+            })
+            .collect())
+    }
+
+    async fn get_order_book(&self, symbol: &str, depth: usize) -> Result<OrderBook> {
+        let url = format!("{}/v5/market/orderbook", self.base_url());
+        // Linear contracts serve 1-500 levels
+        let limit = depth.clamp(1, MAX_ORDERBOOK_LIMIT).to_string();
+        let response: BybitOrderBookResult = self
+            .get_json(&url, &[("category", "linear"), ("symbol", symbol), ("limit", limit.as_str())])
+            .await?;
+
+        Ok(OrderBook {
+            symbol: response.s,
+            timestamp: response.ts,
+            bids: parse_book_levels(&response.b)?,
+            asks: parse_book_levels(&response.a)?,
+        })
+    }
+
+    async fn subscribe_liquidations(&self, symbols: &[String]) -> Result<LiquidationStream> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let url = self.public_stream_url().to_string();
+        let topics: Vec<String> = symbols.iter().map(|s| format!("allLiquidation.{}", s)).collect();
+
+        let task = tokio::spawn(async move {
+            let mut delay = 1;
+            loop {
+                match stream_liquidations(&url, &topics, &sender).await {
+                    // Receiver dropped, nobody is listening anymore
+                    Ok(()) => return,
+                    Err(e) => tracing::warn!("Liquidation stream dropped: {}, reconnecting in {}s", e, delay),
+                }
+                sleep(Duration::from_secs(delay)).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY_SECS);
+            }
+        });
+
+        Ok(LiquidationStream::new(symbols.to_vec(), receiver, task))
+    }
+}
+
+// One WebSocket session; returns Ok only once the receiver is gone
+async fn stream_liquidations(
+    url: &str,
+    topics: &[String],
+    sender: &tokio::sync::mpsc::UnboundedSender<Liquidation>,
+) -> Result<()> {
+    let (socket, _) = connect_async(url).await?;
+    let (mut write, mut read) = socket.split();
+
+    for chunk in topics.chunks(MAX_TOPICS_PER_SUBSCRIBE) {
+        let request = serde_json::json!({ "op": "subscribe", "args": chunk });
+        write.send(Message::Text(request.to_string())).await?;
+    }
+    tracing::info!("Subscribed to {} Bybit liquidation topics", topics.len());
+
+    let mut ping = interval(Duration::from_secs(STREAM_PING_SECS));
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                write.send(Message::Text(r#"{"op":"ping"}"#.to_string())).await?;
+            }
+            message = read.next() => {
+                let Some(message) = message else {
+                    bail!("connection closed");
+                };
+
+                match message? {
+                    Message::Text(text) => {
+                        let Ok(update) = serde_json::from_str::<BybitStreamMessage>(&text) else {
+                            continue;
+                        };
+                        if update.success == Some(false) {
+                            tracing::warn!("Bybit stream request rejected: {}", update.ret_msg.unwrap_or_default());
+                        }
+
+                        for item in update.data.unwrap_or_default() {
+                            let liquidation = Liquidation {
+                                timestamp: item.t,
+                                side: if item.side == "Buy" { TrendDirection::Long } else { TrendDirection::Short },
+                                price: Decimal::from_str(&item.p)?,
+                                quantity: Decimal::from_str(&item.v)?,
+                                symbol: item.s,
+                            };
+                            if sender.send(liquidation).is_err() {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    Message::Ping(payload) => write.send(Message::Pong(payload)).await?,
+                    Message::Close(_) => bail!("closed by server"),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl BybitConnector {
+    // GET a public v5 endpoint and unwrap its `result`
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str, query: &[(&str, &str)]) -> Result<T> {
+        let response = self.client.get(url).query(query).send().await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(anyhow!("Bybit API error: {} - {}", status, text));
+        }
+
+        let api_response: BybitEnvelope<T> = serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Failed to parse Bybit response: {} - Response: {}", e, text))?;
+
+        if api_response.ret_code != 0 {
+            return Err(anyhow!("Bybit API returned error: {}", api_response.ret_msg));
+        }
+
+        api_response.result.ok_or_else(|| anyhow!("Bybit response without result"))
+    }
 }
 
 // Bybit API response types (v5 API)
@@ -157,6 +385,7 @@ struct BybitKlineResponse {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct BybitKlineResult {
     category: String,
     symbol: String,
@@ -173,3 +402,90 @@ struct BybitKline(
     String, // Volume
     String, // Turnover
 );
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitEnvelope<T> {
+    ret_code: i32,
+    ret_msg: String,
+    result: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitInstrumentsResult {
+    list: Vec<BybitInstrument>,
+    next_page_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitInstrument {
+    symbol: String,
+    contract_type: String,
+    status: String,
+    base_coin: String,
+    quote_coin: String,
+    price_filter: BybitPriceFilter,
+    lot_size_filter: BybitLotSizeFilter,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitPriceFilter {
+    tick_size: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitLotSizeFilter {
+    qty_step: String,
+    min_order_qty: String,
+    min_notional_value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitTickersResult {
+    list: Vec<BybitTicker>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitTicker {
+    symbol: String,
+    last_price: String,
+    bid1_price: String,
+    ask1_price: String,
+    volume24h: String,
+    turnover24h: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitOrderBookResult {
+    s: String,
+    // [price, size] pairs, best first
+    b: Vec<(String, String)>,
+    a: Vec<(String, String)>,
+    ts: i64,
+}
+
+// Subscription acks, pongs and topic pushes share one envelope
+#[derive(Debug, Deserialize)]
+struct BybitStreamMessage {
+    success: Option<bool>,
+    ret_msg: Option<String>,
+    data: Option<Vec<BybitLiquidation>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitLiquidation {
+    #[serde(rename = "T")]
+    t: i64,
+    s: String,
+    // Side of the liquidated position: Buy means a long was liquidated
+    #[serde(rename = "S")]
+    side: String,
+    v: String,
+    p: String,
+}
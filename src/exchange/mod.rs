@@ -4,17 +4,134 @@ pub mod bybit;
 pub use binance::BinanceConnector;
 pub use bybit::BybitConnector;
 
-use anyhow::Result;
-use crate::types::{MarketData, Candle, Timeframe};
+use anyhow::{bail, Result};
+use crate::resample;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use crate::types::{BookLevel, MarketData, MultiTimeframeData, Candle, Liquidation, OrderBook, Timeframe};
 use async_trait::async_trait;
 
 #[async_trait]
 pub trait ExchangeConnector: Send + Sync {
     async fn get_market_data(&self, symbol: &str, timeframe: Timeframe, limit: usize) -> Result<MarketData>;
+
+    // Fetch every timeframe for a symbol against a single evaluation time
+    async fn get_multi_timeframe_data(
+        &self,
+        symbol: &str,
+        timeframes: &[Timeframe],
+        limit: usize,
+    ) -> Result<MultiTimeframeData> {
+        let mut data = MultiTimeframeData::new(
+            symbol.to_string(),
+            chrono::Utc::now().timestamp_millis(),
+        );
+
+        for timeframe in timeframes {
+            data.insert(self.get_market_data(symbol, *timeframe, limit).await?);
+        }
+
+        Ok(data)
+    }
+
     async fn place_order(&self, symbol: &str, side: OrderSide, quantity: f64, price: Option<f64>) -> Result<Order>;
     async fn cancel_order(&self, symbol: &str, order_id: &str) -> Result<()>;
     async fn get_account_balance(&self) -> Result<AccountBalance>;
     async fn get_open_orders(&self, symbol: &str) -> Result<Vec<Order>>;
+
+    // Instrument metadata and 24h tickers for universe discovery
+    async fn get_instruments(&self) -> Result<Vec<Instrument>>;
+    async fn get_tickers(&self) -> Result<Vec<Ticker>>;
+
+    // Snapshot of the best `depth` levels per side
+    async fn get_order_book(&self, symbol: &str, depth: usize) -> Result<OrderBook>;
+
+    // Live liquidations of `symbols` until the returned stream is dropped
+    async fn subscribe_liquidations(&self, _symbols: &[String]) -> Result<LiquidationStream> {
+        bail!("liquidation stream not supported by this exchange")
+    }
+}
+
+// Receiving end of a background liquidation feed; dropping it stops the feed
+pub struct LiquidationStream {
+    pub symbols: Vec<String>,
+    receiver: mpsc::UnboundedReceiver<Liquidation>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl LiquidationStream {
+    pub fn new(
+        symbols: Vec<String>,
+        receiver: mpsc::UnboundedReceiver<Liquidation>,
+        task: tokio::task::JoinHandle<()>,
+    ) -> Self {
+        Self { symbols, receiver, task }
+    }
+
+    // Everything received since the last call
+    pub fn drain(&mut self) -> Vec<Liquidation> {
+        let mut liquidations = Vec::new();
+        while let Ok(liquidation) = self.receiver.try_recv() {
+            liquidations.push(liquidation);
+        }
+        liquidations
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for LiquidationStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// Fallback for intervals a venue does not offer natively: fetch the largest
+// native interval that divides `timeframe` and resample it
+pub(crate) async fn fetch_resampled<C: ExchangeConnector + ?Sized>(
+    connector: &C,
+    native: &[Timeframe],
+    max_limit: usize,
+    symbol: &str,
+    timeframe: Timeframe,
+    limit: usize,
+) -> Result<MarketData> {
+    let source = resample::best_source_timeframe(timeframe, native).ok_or_else(|| {
+        anyhow::anyhow!("no native interval to build {}m bars from", timeframe.to_minutes())
+    })?;
+
+    let source_limit = resample::source_limit(timeframe, source, limit).min(max_limit);
+    tracing::debug!(
+        "Resampling {} {}m bars from {} x {}m",
+        symbol,
+        timeframe.to_minutes(),
+        source_limit,
+        source.to_minutes()
+    );
+
+    let data = connector.get_market_data(symbol, source, source_limit).await?;
+    let mut resampled = resample::resample_market_data(&data, timeframe)?;
+
+    let excess = resampled.candles.len().saturating_sub(limit);
+    resampled.candles.drain(..excess);
+
+    Ok(resampled)
+}
+
+// [price, quantity] string pairs as both venues send them
+pub(crate) fn parse_book_levels(levels: &[(String, String)]) -> Result<Vec<BookLevel>> {
+    levels
+        .iter()
+        .map(|(price, quantity)| {
+            Ok(BookLevel {
+                price: Decimal::from_str(price)?,
+                quantity: Decimal::from_str(quantity)?,
+            })
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -69,3 +186,48 @@ pub struct Position {
     pub unrealized_pnl: f64,
     pub side: OrderSide,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstrumentKind {
+    LinearPerpetual,
+    Spot,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct Instrument {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub kind: InstrumentKind,
+    // Open for trading right now
+    pub is_trading: bool,
+    pub tick_size: f64,
+    // Order quantities are in contracts of `contract_size` base units
+    pub contract_size: f64,
+    pub qty_step: f64,
+    pub min_order_qty: f64,
+    // Smallest order value in the quote asset, 0 when the venue has none
+    pub min_notional: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ticker {
+    pub symbol: String,
+    pub last_price: f64,
+    pub bid_price: f64,
+    pub ask_price: f64,
+    // Base asset volume and quote (USDT) turnover over the last 24h
+    pub volume_24h: f64,
+    pub turnover_24h: f64,
+}
+
+impl Ticker {
+    pub fn spread_bps(&self) -> Option<f64> {
+        let mid = (self.bid_price + self.ask_price) / 2.0;
+        if self.bid_price <= 0.0 || self.ask_price < self.bid_price {
+            return None;
+        }
+        Some((self.ask_price - self.bid_price) / mid * 10_000.0)
+    }
+}
@@ -2,6 +2,7 @@ use rust_decimal::Decimal;
 use crate::types::TrendDirection;
 
 pub struct DynamicTPManager {
+    #[allow(dead_code)]
     trailing_enabled: bool,
 }
 
@@ -2,6 +2,6 @@ pub mod smart_entry;
 pub mod dynamic_tp;
 pub mod news_calendar;
 
-pub use smart_entry::{SmartEntryManager, EntrySignal, FibZone};
+pub use smart_entry::{SmartEntryManager, EntrySignal, FibZone, M5Confirmation};
 pub use dynamic_tp::{DynamicTPManager, TakeProfitLevels, TPLevel, TrailingStop};
 pub use news_calendar::{NewsCalendar, NewsEvent, ImpactLevel};
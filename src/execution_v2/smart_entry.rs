@@ -1,6 +1,7 @@
 use rust_decimal::Decimal;
-use crate::types::{MarketData, TrendDirection};
-use crate::intelligence::ConfluenceScorer;
+use rust_decimal::prelude::ToPrimitive;
+use crate::types::{MultiTimeframeData, Timeframe, TrendDirection};
+use crate::intelligence::{ConfluenceScorer, CandlePatternRecognizer, PatternMatch};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FibZone {
@@ -10,14 +11,25 @@ pub enum FibZone {
     Invalid,
 }
 
+// M5 confirmation rules from the strategy spec
+const MAX_REJECTION_BODY_RATIO: f64 = 0.30;
+const MAX_RETEST_CANDLES: usize = 3;
+const AVERAGE_RANGE_PERIOD: usize = 20;
+
 pub struct SmartEntryManager {
     confluence_scorer: ConfluenceScorer,
+    pattern_recognizer: CandlePatternRecognizer,
 }
 
 impl SmartEntryManager {
     pub fn new(min_confluence: u8) -> Self {
+        Self::with_scorer(ConfluenceScorer::new(min_confluence))
+    }
+
+    pub fn with_scorer(confluence_scorer: ConfluenceScorer) -> Self {
         Self {
-            confluence_scorer: ConfluenceScorer::new(min_confluence),
+            confluence_scorer,
+            pattern_recognizer: CandlePatternRecognizer::new(),
         }
     }
 
@@ -67,12 +79,115 @@ impl SmartEntryManager {
         }
     }
 
-    fn check_m5_confirmation(&self, _data: &MarketData, _direction: TrendDirection) -> bool {
-        // TODO: Implement M5 confirmation logic
-        false
+    // Full entry check: confluence on the signal timeframe, Fibonacci zone of
+    // the retest and M5 confirmation, all against the same aligned data set
+    pub async fn validate_entry(
+        &self,
+        data: &MultiTimeframeData,
+        signal_timeframe: Timeframe,
+        swing_high: Decimal,
+        swing_low: Decimal,
+        direction: TrendDirection,
+    ) -> Option<EntrySignal> {
+        let confluence = self.confluence_scorer.calculate_score(data, signal_timeframe).await;
+        // Confluence has to back the side we are about to trade
+        if !confluence.is_valid || confluence.direction != direction {
+            return None;
+        }
+
+        let price = data.aligned(Timeframe::M5)?.close();
+        let fib_zone = self.calculate_fib_zone(price, swing_high, swing_low, direction);
+
+        // Marginal zone only with high confluence
+        let zone_ok = match fib_zone {
+            FibZone::Premium | FibZone::Standard => true,
+            FibZone::Marginal => confluence.total_score >= 85,
+            FibZone::Invalid => false,
+        };
+
+        if !zone_ok || !self.check_m5_confirmation(data, direction) {
+            return None;
+        }
+
+        Some(EntrySignal {
+            price,
+            fib_zone,
+            confluence_score: confluence.total_score,
+            direction,
+        })
+    }
+
+    pub fn check_m5_confirmation(&self, data: &MultiTimeframeData, direction: TrendDirection) -> bool {
+        self.evaluate_m5_confirmation(data, direction).confirmed
+    }
+
+    pub fn evaluate_m5_confirmation(&self, data: &MultiTimeframeData, direction: TrendDirection) -> M5Confirmation {
+        let mut confirmation = M5Confirmation::default();
+
+        let Some(data) = data.aligned(Timeframe::M5) else {
+            return confirmation;
+        };
+
+        if direction == TrendDirection::Neutral || data.candles.len() < AVERAGE_RANGE_PERIOD {
+            return confirmation;
+        }
+
+        let average_range = data.average_range(AVERAGE_RANGE_PERIOD);
+        let Some(candle) = data.last_candle() else {
+            return confirmation;
+        };
+
+        if average_range.is_zero() {
+            return confirmation;
+        }
+
+        // 1. Rejection candle smaller than 30% of the average range
+        let body_ratio = (candle.body() / average_range).to_f64().unwrap_or(1.0);
+        confirmation.body_ratio = body_ratio;
+
+        // 2. Close in favour of the trend
+        let closes_with_trend = match direction {
+            TrendDirection::Long => candle.is_bullish(),
+            TrendDirection::Short => candle.is_bearish(),
+            TrendDirection::Neutral => false,
+        };
+
+        // 3. No more than 3 candles in the retest (avoid drifting sideways)
+        let retest_candles = data.candles[..data.candles.len() - 1]
+            .iter()
+            .rev()
+            .take_while(|c| match direction {
+                TrendDirection::Long => c.is_bearish(),
+                TrendDirection::Short => c.is_bullish(),
+                TrendDirection::Neutral => false,
+            })
+            .count();
+        confirmation.retest_candles = retest_candles;
+
+        // The rejection itself has to be recognisable on the candle
+        confirmation.patterns = self.pattern_recognizer
+            .detect(&data.candles)
+            .into_iter()
+            .filter(|m| m.direction == direction)
+            .collect();
+
+        confirmation.confirmed = body_ratio < MAX_REJECTION_BODY_RATIO
+            && closes_with_trend
+            && retest_candles <= MAX_RETEST_CANDLES
+            && !confirmation.patterns.is_empty();
+
+        confirmation
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct M5Confirmation {
+    pub confirmed: bool,
+    pub body_ratio: f64,
+    pub retest_candles: usize,
+    pub patterns: Vec<PatternMatch>,
+}
+
 #[derive(Debug)]
 pub struct EntrySignal {
     pub price: Decimal,
@@ -80,3 +195,58 @@ pub struct EntrySignal {
     pub confluence_score: u8,
     pub direction: TrendDirection,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Candle, MarketData};
+
+    fn candle(index: usize, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        let dec = |value: f64| Decimal::try_from(value).unwrap();
+        Candle::new(index as i64 * 300_000, dec(open), dec(high), dec(low), dec(close), dec(100.0))
+    }
+
+    // 21 candles ranging 99-101, `retest` bearish candles, then `last`
+    fn m5_data(retest: usize, last: (f64, f64, f64, f64)) -> MultiTimeframeData {
+        let mut candles: Vec<Candle> = (0..21)
+            .map(|i| {
+                let (open, close) = if i % 2 == 0 { (100.0, 100.5) } else { (100.5, 100.0) };
+                candle(i, open, 101.0, 99.0, close)
+            })
+            .collect();
+        for i in 0..retest {
+            candles.push(candle(21 + i, 100.5, 100.7, 99.8, 100.0));
+        }
+        let (open, high, low, close) = last;
+        candles.push(candle(21 + retest, open, high, low, close));
+
+        let mut data = MultiTimeframeData::new("BTCUSDT".to_string(), i64::MAX);
+        data.insert(MarketData {
+            symbol: "BTCUSDT".to_string(),
+            candles,
+            timeframe: Timeframe::M5,
+        });
+        data
+    }
+
+    #[test]
+    fn m5_confirmation_needs_small_rejection_closing_with_trend() {
+        let manager = SmartEntryManager::new(70);
+        let rejection = (99.9, 100.25, 98.5, 100.2);
+
+        let confirmed = manager.evaluate_m5_confirmation(&m5_data(2, rejection), TrendDirection::Long);
+        assert!(confirmed.confirmed, "{:?}", confirmed);
+        assert_eq!(confirmed.retest_candles, 2);
+        assert!(confirmed.body_ratio < MAX_REJECTION_BODY_RATIO);
+        assert!(!confirmed.patterns.is_empty());
+
+        // Same wick closing down is against a long
+        let against = manager.evaluate_m5_confirmation(&m5_data(2, (100.2, 100.25, 98.5, 99.9)), TrendDirection::Long);
+        assert!(!against.confirmed);
+
+        // Drifting retest of four candles
+        let drifting = manager.evaluate_m5_confirmation(&m5_data(4, rejection), TrendDirection::Long);
+        assert_eq!(drifting.retest_candles, 4);
+        assert!(!drifting.confirmed);
+    }
+}
@@ -1,18 +1,35 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use std::collections::HashMap;
-use crate::types::MarketData;
+use crate::exchange::{Instrument, InstrumentKind, Ticker};
+use crate::types::{MarketData, to_f64};
+
+// Always monitored and exempt from the score threshold
+pub const CORE_SYMBOLS: [&str; 2] = ["BTCUSDT", "ETHUSDT"];
+pub const DEFAULT_MIN_TURNOVER_USD: f64 = 50_000_000.0;
+pub const DEFAULT_MAX_UNIVERSE: usize = 15;
+// Other assets need this score to become active, at most MAX_ACTIVE at once
+const MIN_ACTIVE_SCORE: f64 = 75.0;
+const MAX_ACTIVE: usize = 2;
 
 #[derive(Debug, Clone)]
 pub struct AssetScore {
     pub symbol: String,
+    // Factor scores in [0, 1], ranked against the rest of the universe
     pub volatility_score: f64,
     pub trend_strength: f64,
     pub liquidity_score: f64,
+    // Higher is cheaper
     pub spread_cost: f64,
     pub total_score: f64,
 }
 
 pub struct AssetRanker {
     weights: ScoreWeights,
+    min_turnover_usd: f64,
+    max_universe: usize,
+    // Latest daily ranking and the UTC day it was made on
+    ranking: Vec<AssetScore>,
+    ranked_on: Option<NaiveDate>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,8 +40,21 @@ struct ScoreWeights {
     spread: f64,
 }
 
+// Raw, un-normalized factors of one symbol
+struct AssetFactors {
+    symbol: String,
+    atr_percent: f64,
+    adx: f64,
+    turnover: f64,
+    spread_bps: f64,
+}
+
 impl AssetRanker {
     pub fn new() -> Self {
+        Self::with_universe_filter(DEFAULT_MIN_TURNOVER_USD, DEFAULT_MAX_UNIVERSE)
+    }
+
+    pub fn with_universe_filter(min_turnover_usd: f64, max_universe: usize) -> Self {
         Self {
             weights: ScoreWeights {
                 volatility: 0.4,
@@ -32,34 +62,76 @@ impl AssetRanker {
                 liquidity: 0.2,
                 spread: 0.1,
             },
+            min_turnover_usd,
+            max_universe,
+            ranking: Vec::new(),
+            ranked_on: None,
         }
     }
 
+    // Core symbols plus the most traded USDT perpetuals above the turnover
+    // floor, capped at `max_universe` symbols in total
+    pub fn build_universe(&self, instruments: &[Instrument], tickers: &[Ticker]) -> Vec<String> {
+        let turnover: HashMap<&str, f64> = tickers
+            .iter()
+            .map(|t| (t.symbol.as_str(), t.turnover_24h))
+            .collect();
+
+        let mut candidates: Vec<(&str, f64)> = instruments
+            .iter()
+            .filter(|i| i.kind == InstrumentKind::LinearPerpetual && i.is_trading && i.quote_asset == "USDT")
+            .filter(|i| !CORE_SYMBOLS.contains(&i.symbol.as_str()))
+            .filter_map(|i| turnover.get(i.symbol.as_str()).map(|t| (i.symbol.as_str(), *t)))
+            .filter(|(_, t)| *t >= self.min_turnover_usd)
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        CORE_SYMBOLS
+            .iter()
+            .copied()
+            .chain(candidates.into_iter().map(|(symbol, _)| symbol))
+            .take(self.max_universe.max(CORE_SYMBOLS.len()))
+            .map(str::to_string)
+            .collect()
+    }
+
+    // Scores every symbol with H1 data; factors are percentile ranks across
+    // the symbols passed in, so scores only compare within one universe
     pub async fn rank_assets(
         &self,
-        candidates: Vec<String>,
         market_data: &HashMap<String, MarketData>,
+        tickers: &HashMap<String, Ticker>,
     ) -> Vec<AssetScore> {
-        let mut scores = Vec::new();
+        let factors: Vec<AssetFactors> = market_data
+            .values()
+            .filter(|data| data.candles.len() > 14)
+            .map(|data| asset_factors(data, tickers.get(&data.symbol)))
+            .collect();
 
-        for symbol in candidates {
-            if let Some(data) = market_data.get(&symbol) {
-                let score = self.calculate_asset_score(&symbol, data);
-                scores.push(score);
-            }
-        }
+        let volatility = percentile_ranks(&factors.iter().map(|f| f.atr_percent).collect::<Vec<_>>());
+        let trend = percentile_ranks(&factors.iter().map(|f| f.adx).collect::<Vec<_>>());
+        let liquidity = percentile_ranks(&factors.iter().map(|f| f.turnover).collect::<Vec<_>>());
+        // Tighter spread ranks higher
+        let spread = percentile_ranks(&factors.iter().map(|f| -f.spread_bps).collect::<Vec<_>>());
+
+        let mut scores: Vec<AssetScore> = factors
+            .iter()
+            .enumerate()
+            .map(|(i, f)| self.calculate_asset_score(&f.symbol, volatility[i], trend[i], liquidity[i], spread[i]))
+            .collect();
 
-        scores.sort_by(|a, b| b.total_score.partial_cmp(&a.total_score).unwrap());
+        scores.sort_by(|a, b| b.total_score.total_cmp(&a.total_score));
         scores
     }
 
-    fn calculate_asset_score(&self, symbol: &str, _data: &MarketData) -> AssetScore {
-        // TODO: Implement actual scoring logic
-        let volatility_score = 0.5;
-        let trend_strength = 0.5;
-        let liquidity_score = 0.5;
-        let spread_cost = 0.5;
-
+    fn calculate_asset_score(
+        &self,
+        symbol: &str,
+        volatility_score: f64,
+        trend_strength: f64,
+        liquidity_score: f64,
+        spread_cost: f64,
+    ) -> AssetScore {
         let total_score =
             (volatility_score * self.weights.volatility) +
             (trend_strength * self.weights.trend) +
@@ -75,6 +147,45 @@ impl AssetRanker {
             total_score: total_score * 100.0,
         }
     }
+
+    // Core symbols in the ranking are always active. Alts above
+    // MIN_ACTIVE_SCORE only fill the slots left under MAX_ACTIVE, so with
+    // both core symbols ranked they never trade.
+    pub fn select_active(&self, ranking: &[AssetScore]) -> Vec<AssetScore> {
+        let is_core = |s: &&AssetScore| CORE_SYMBOLS.contains(&s.symbol.as_str());
+        let core = ranking.iter().filter(is_core);
+        let alts = ranking
+            .iter()
+            .filter(|s| !is_core(s) && s.total_score > MIN_ACTIVE_SCORE)
+            .take(MAX_ACTIVE.saturating_sub(core.clone().count()));
+
+        core.chain(alts).cloned().collect()
+    }
+
+    pub fn needs_rerank(&self, now: DateTime<Utc>) -> bool {
+        self.ranked_on != Some(now.date_naive())
+    }
+
+    pub fn set_ranking(&mut self, ranking: Vec<AssetScore>, now: DateTime<Utc>) {
+        self.ranking = ranking;
+        self.ranked_on = Some(now.date_naive());
+    }
+
+    pub fn ranking(&self) -> &[AssetScore] {
+        &self.ranking
+    }
+
+    // Symbols to trade today; the core symbols until a ranking exists
+    pub fn active_symbols(&self) -> Vec<String> {
+        if self.ranking.is_empty() {
+            return CORE_SYMBOLS.iter().map(|s| s.to_string()).collect();
+        }
+
+        self.select_active(&self.ranking)
+            .into_iter()
+            .map(|s| s.symbol)
+            .collect()
+    }
 }
 
 impl Default for AssetRanker {
@@ -82,3 +193,68 @@ impl Default for AssetRanker {
         Self::new()
     }
 }
+
+fn asset_factors(data: &MarketData, ticker: Option<&Ticker>) -> AssetFactors {
+    let close = to_f64(data.close());
+    let atr = to_f64(data.atr(14));
+
+    AssetFactors {
+        symbol: data.symbol.clone(),
+        atr_percent: if close > 0.0 { atr / close * 100.0 } else { 0.0 },
+        adx: data.adx(14),
+        turnover: ticker.map_or_else(|| data.volume_24h() * close, |t| t.turnover_24h),
+        spread_bps: ticker.and_then(|t| t.spread_bps()).unwrap_or_else(|| data.spread_bps()),
+    }
+}
+
+// Share of the other values below each value (ties count half), in [0, 1]
+fn percentile_ranks(values: &[f64]) -> Vec<f64> {
+    if values.len() < 2 {
+        return vec![1.0; values.len()];
+    }
+
+    let others = (values.len() - 1) as f64;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let below = values.iter().filter(|o| *o < v).count() as f64;
+            let ties = values.iter().enumerate().filter(|(j, o)| *j != i && *o == v).count() as f64;
+            (below + ties / 2.0) / others
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(symbol: &str, total_score: f64) -> AssetScore {
+        AssetScore {
+            symbol: symbol.to_string(),
+            volatility_score: 0.0,
+            trend_strength: 0.0,
+            liquidity_score: 0.0,
+            spread_cost: 0.0,
+            total_score,
+        }
+    }
+
+    #[test]
+    fn core_symbols_are_always_active_and_alts_fill_free_slots() {
+        let ranker = AssetRanker::new();
+        let active = |ranking: &[AssetScore]| -> Vec<String> {
+            ranker.select_active(ranking).into_iter().map(|s| s.symbol).collect()
+        };
+
+        let strong_alt = [score("SOLUSDT", 90.0), score("BTCUSDT", 60.0), score("ETHUSDT", 40.0)];
+        assert_eq!(active(&strong_alt), vec!["BTCUSDT", "ETHUSDT"]);
+
+        // ETH missing from the ranking (e.g. under the turnover floor)
+        let no_eth = [score("BNBUSDT", 74.0), score("SOLUSDT", 90.0), score("BTCUSDT", 60.0), score("XRPUSDT", f64::NAN)];
+        assert_eq!(active(&no_eth), vec!["BTCUSDT", "SOLUSDT"]);
+
+        let weak_alts = [score("BNBUSDT", 74.0), score("XRPUSDT", f64::NAN)];
+        assert!(active(&weak_alts).is_empty());
+    }
+}
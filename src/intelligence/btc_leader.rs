@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::intelligence::{VolatilityGuard, VolatilityTrigger};
+use crate::types::{paired_log_returns, MarketData, MultiTimeframeData, Timeframe, TrendDirection, to_f64};
+
+pub const LEADER_SYMBOL: &str = "BTCUSDT";
+// BTC trend on H1, momentum over the last hour of M15 bars
+const TREND_TIMEFRAME: Timeframe = Timeframe::H1;
+const MOMENTUM_TIMEFRAME: Timeframe = Timeframe::M15;
+const MOMENTUM_BARS: usize = 4;
+// BTC moving this much against an alt entry within the hour vetoes it
+const VETO_MOMENTUM_PERCENT: f64 = 1.0;
+// Risk kept when the alt entry only fights BTC's trend
+const AGAINST_TREND_RISK: f64 = 0.5;
+// Beta from H1 log returns; high-beta alts get proportionally less risk,
+// never less than MIN_BETA_RISK of it
+const BETA_LOOKBACK: usize = 100;
+const MIN_BETA_RISK: f64 = 0.5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderState {
+    pub trend: TrendDirection,
+    pub momentum_percent: f64,
+    pub volatility: Option<VolatilityTrigger>,
+    pub as_of: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeaderVerdict {
+    Allow,
+    // Keep the entry at `factor` of its risk
+    Reduce { factor: f64, reason: String },
+    Veto { reason: String },
+}
+
+// Cross-asset filter for alt entries: BTC leads, so alts trading against its
+// trend get less risk and those fighting a sharp BTC move or BTC extreme
+// volatility are skipped
+pub struct BtcLeaderFilter {
+    state: Option<LeaderState>,
+    betas: HashMap<String, f64>,
+    volatility_guard: VolatilityGuard,
+}
+
+impl BtcLeaderFilter {
+    pub fn new() -> Self {
+        Self {
+            state: None,
+            betas: HashMap::new(),
+            volatility_guard: VolatilityGuard::new(),
+        }
+    }
+
+    pub fn update(&mut self, btc: &MultiTimeframeData) {
+        let (Some(trend_data), Some(momentum_data)) = (btc.aligned(TREND_TIMEFRAME), btc.aligned(MOMENTUM_TIMEFRAME)) else {
+            return;
+        };
+
+        self.state = Some(LeaderState {
+            trend: trend_data.trend_direction(),
+            momentum_percent: momentum_percent(&momentum_data, MOMENTUM_BARS),
+            volatility: self.volatility_guard.check(&momentum_data),
+            as_of: btc.as_of,
+        });
+    }
+
+    pub fn state(&self) -> Option<&LeaderState> {
+        self.state.as_ref()
+    }
+
+    pub fn update_beta(&mut self, alt: &MultiTimeframeData, btc: &MultiTimeframeData) {
+        let (Some(alt_data), Some(btc_data)) = (alt.aligned(TREND_TIMEFRAME), btc.aligned(TREND_TIMEFRAME)) else {
+            return;
+        };
+
+        if let Some(beta) = rolling_beta(&alt_data, &btc_data, BETA_LOOKBACK) {
+            self.betas.insert(alt.symbol.clone(), beta);
+        }
+    }
+
+    pub fn beta(&self, symbol: &str) -> Option<f64> {
+        if symbol == LEADER_SYMBOL {
+            return Some(1.0);
+        }
+        self.betas.get(symbol).copied()
+    }
+
+    // Share of the normal risk for `symbol`: 1/beta for alts moving more than BTC
+    pub fn beta_risk_factor(&self, symbol: &str) -> f64 {
+        match self.beta(symbol) {
+            Some(beta) if beta > 1.0 => (1.0 / beta).max(MIN_BETA_RISK),
+            _ => 1.0,
+        }
+    }
+
+    pub fn evaluate(&self, symbol: &str, direction: TrendDirection) -> LeaderVerdict {
+        if symbol == LEADER_SYMBOL || direction == TrendDirection::Neutral {
+            return LeaderVerdict::Allow;
+        }
+        let Some(state) = &self.state else {
+            return LeaderVerdict::Allow;
+        };
+
+        if let Some(trigger) = &state.volatility {
+            return LeaderVerdict::Veto {
+                reason: format!("BTC in extreme volatility: {}", trigger.reason()),
+            };
+        }
+
+        let against_momentum = match direction {
+            TrendDirection::Long => -state.momentum_percent,
+            _ => state.momentum_percent,
+        };
+        if against_momentum >= VETO_MOMENTUM_PERCENT {
+            return LeaderVerdict::Veto {
+                reason: format!("BTC moved {:+.2}% in the last hour against a {:?} entry", state.momentum_percent, direction),
+            };
+        }
+
+        let opposite = match direction {
+            TrendDirection::Long => TrendDirection::Short,
+            _ => TrendDirection::Long,
+        };
+        if state.trend == opposite {
+            return LeaderVerdict::Reduce {
+                factor: AGAINST_TREND_RISK,
+                reason: format!("{:?} entry against the BTC H1 {:?} trend", direction, state.trend),
+            };
+        }
+
+        LeaderVerdict::Allow
+    }
+}
+
+impl Default for BtcLeaderFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Close-to-close change over the last `bars` bars
+fn momentum_percent(data: &MarketData, bars: usize) -> f64 {
+    let len = data.candles.len();
+    if len <= bars {
+        return 0.0;
+    }
+
+    let from = to_f64(data.candles[len - 1 - bars].close);
+    let to = to_f64(data.candles[len - 1].close);
+    if from > 0.0 { (to - from) / from * 100.0 } else { 0.0 }
+}
+
+// cov(alt, btc) / var(btc) of log returns over bars both series share
+pub fn rolling_beta(alt: &MarketData, btc: &MarketData, lookback: usize) -> Option<f64> {
+    let (alt_returns, btc_returns) = paired_log_returns(alt, btc, lookback)?;
+    let returns: Vec<(f64, f64)> = alt_returns.into_iter().zip(btc_returns).collect();
+
+    let n = returns.len() as f64;
+    let mean_alt = returns.iter().map(|r| r.0).sum::<f64>() / n;
+    let mean_btc = returns.iter().map(|r| r.1).sum::<f64>() / n;
+    let covariance = returns.iter().map(|r| (r.0 - mean_alt) * (r.1 - mean_btc)).sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r.1 - mean_btc).powi(2)).sum::<f64>() / n;
+
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Candle;
+    use rust_decimal::Decimal;
+
+    const H1: i64 = 60 * 60_000;
+
+    fn series(symbol: &str, closes: &[f64]) -> MarketData {
+        let dec = |value: f64| Decimal::try_from(value).unwrap();
+        let candles = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Candle::new(i as i64 * H1, dec(close), dec(close), dec(close), dec(close), dec(1.0)))
+            .collect();
+        MarketData {
+            symbol: symbol.to_string(),
+            candles,
+            timeframe: Timeframe::H1,
+        }
+    }
+
+    fn filter(trend: TrendDirection, momentum_percent: f64) -> BtcLeaderFilter {
+        let mut filter = BtcLeaderFilter::new();
+        filter.state = Some(LeaderState {
+            trend,
+            momentum_percent,
+            volatility: None,
+            as_of: 0,
+        });
+        filter
+    }
+
+    #[test]
+    fn btc_momentum_of_one_percent_vetoes_alts_against_it() {
+        let falling = filter(TrendDirection::Neutral, -1.0);
+        assert!(matches!(falling.evaluate("ETHUSDT", TrendDirection::Long), LeaderVerdict::Veto { .. }));
+        assert_eq!(falling.evaluate("ETHUSDT", TrendDirection::Short), LeaderVerdict::Allow);
+        assert_eq!(falling.evaluate(LEADER_SYMBOL, TrendDirection::Long), LeaderVerdict::Allow);
+
+        let dip = filter(TrendDirection::Neutral, -0.99);
+        assert_eq!(dip.evaluate("ETHUSDT", TrendDirection::Long), LeaderVerdict::Allow);
+
+        let mut volatile = filter(TrendDirection::Long, 0.0);
+        volatile.state.as_mut().unwrap().volatility = Some(VolatilityTrigger::FlashCrash {
+            from_price: 100.0,
+            to_price: 94.0,
+            drop_percent: 6.0,
+        });
+        assert!(matches!(volatile.evaluate("ETHUSDT", TrendDirection::Long), LeaderVerdict::Veto { .. }));
+    }
+
+    #[test]
+    fn entries_against_the_btc_trend_keep_half_the_risk() {
+        let bearish = filter(TrendDirection::Short, -0.5);
+        match bearish.evaluate("ETHUSDT", TrendDirection::Long) {
+            LeaderVerdict::Reduce { factor, .. } => assert_eq!(factor, AGAINST_TREND_RISK),
+            other => panic!("expected reduce, got {:?}", other),
+        }
+        assert_eq!(bearish.evaluate("ETHUSDT", TrendDirection::Short), LeaderVerdict::Allow);
+        assert_eq!(BtcLeaderFilter::new().evaluate("ETHUSDT", TrendDirection::Long), LeaderVerdict::Allow);
+    }
+
+    #[test]
+    fn beta_scales_risk_down_to_the_floor() {
+        let mut filter = BtcLeaderFilter::new();
+        for (symbol, beta) in [("A", 0.8), ("B", 1.25), ("C", 2.0), ("D", 4.0)] {
+            filter.betas.insert(symbol.to_string(), beta);
+        }
+
+        assert_eq!(filter.beta_risk_factor("A"), 1.0);
+        assert_eq!(filter.beta_risk_factor("B"), 0.8);
+        assert_eq!(filter.beta_risk_factor("C"), MIN_BETA_RISK);
+        assert_eq!(filter.beta_risk_factor("D"), MIN_BETA_RISK);
+        assert_eq!(filter.beta_risk_factor("unknown"), 1.0);
+        assert_eq!(filter.beta(LEADER_SYMBOL), Some(1.0));
+    }
+
+    #[test]
+    fn rolling_beta_of_a_doubled_return_series() {
+        // Alt log returns are exactly twice BTC's
+        let btc: Vec<f64> = (0..40).map(|i| 100.0 * (1.0 + 0.01 * ((i % 5) as f64 - 2.0))).collect();
+        let alt: Vec<f64> = btc.iter().map(|c| 100.0 * (c / 100.0).powi(2)).collect();
+
+        let beta = rolling_beta(&series("ALT", &alt), &series(LEADER_SYMBOL, &btc), BETA_LOOKBACK).unwrap();
+        assert!((beta - 2.0).abs() < 1e-9, "{}", beta);
+
+        // Under MIN_PAIRED_RETURNS shared returns
+        assert!(rolling_beta(&series("ALT", &alt[..30]), &series(LEADER_SYMBOL, &btc), BETA_LOOKBACK).is_none());
+
+        assert!((momentum_percent(&series(LEADER_SYMBOL, &[100.0, 50.0, 101.0, 102.0, 99.0]), 4) + 1.0).abs() < 1e-9);
+        assert_eq!(momentum_percent(&series(LEADER_SYMBOL, &[100.0, 101.0]), 4), 0.0);
+    }
+}
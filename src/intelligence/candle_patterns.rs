@@ -0,0 +1,340 @@
+use serde::{Deserialize, Serialize};
+use crate::types::{Candle, TrendDirection, to_f64};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CandlePattern {
+    PinBar,
+    BullishEngulfing,
+    BearishEngulfing,
+    InsideBar,
+    Hammer,
+    ShootingStar,
+    RejectionWick,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternMatch {
+    pub pattern: CandlePattern,
+    pub direction: TrendDirection,
+    pub index: usize,
+    // 0.0 - 1.0, how cleanly the candle fits the pattern
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CandlePatternRecognizer {
+    // Dominant wick must be at least this many times the body
+    min_wick_to_body: f64,
+    // Dominant wick must cover at least this fraction of the range
+    min_wick_to_range: f64,
+    // Candles used to decide the prior swing for hammer / shooting star
+    context_candles: usize,
+}
+
+impl CandlePatternRecognizer {
+    pub fn new() -> Self {
+        Self {
+            min_wick_to_body: 2.0,
+            min_wick_to_range: 0.5,
+            context_candles: 3,
+        }
+    }
+
+    // Patterns completed by the last candle of the sequence
+    pub fn detect(&self, candles: &[Candle]) -> Vec<PatternMatch> {
+        if candles.is_empty() {
+            return Vec::new();
+        }
+        self.detect_at(candles, candles.len() - 1)
+    }
+
+    pub fn detect_at(&self, candles: &[Candle], index: usize) -> Vec<PatternMatch> {
+        let mut matches = Vec::new();
+        let Some(candle) = candles.get(index) else {
+            return matches;
+        };
+
+        if candle.range().is_zero() {
+            return matches;
+        }
+
+        if let Some(m) = self.pin_bar(candle, index) {
+            matches.push(m);
+        }
+
+        if let Some(m) = self.rejection_wick(candle, index) {
+            matches.push(m);
+        }
+
+        if index > 0 {
+            let previous = &candles[index - 1];
+
+            if let Some(m) = self.engulfing(previous, candle, index) {
+                matches.push(m);
+            }
+
+            if let Some(m) = self.inside_bar(previous, candle, index) {
+                matches.push(m);
+            }
+        }
+
+        if let Some(m) = self.hammer_or_star(candles, index) {
+            matches.push(m);
+        }
+
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches
+    }
+
+    // Share of the candle range taken by the wick that rejects `direction`
+    // (lower wick for longs, upper wick for shorts)
+    pub fn rejection_wick_ratio(&self, candle: &Candle, direction: TrendDirection) -> f64 {
+        let range = to_f64(candle.range());
+        if range == 0.0 {
+            return 0.0;
+        }
+
+        let wick = match direction {
+            TrendDirection::Long => candle.lower_wick(),
+            TrendDirection::Short => candle.upper_wick(),
+            TrendDirection::Neutral => candle.lower_wick().max(candle.upper_wick()),
+        };
+
+        to_f64(wick) / range
+    }
+
+    fn pin_bar(&self, candle: &Candle, index: usize) -> Option<PatternMatch> {
+        let range = to_f64(candle.range());
+        let body = to_f64(candle.body());
+        let upper = to_f64(candle.upper_wick());
+        let lower = to_f64(candle.lower_wick());
+
+        // Nose of at least 2/3 of the range with the body in the opposite third
+        let (wick, opposite, direction) = if lower > upper {
+            (lower, upper, TrendDirection::Long)
+        } else {
+            (upper, lower, TrendDirection::Short)
+        };
+
+        if wick / range < 2.0 / 3.0 || body / range > 1.0 / 3.0 || opposite > wick / 3.0 {
+            return None;
+        }
+
+        Some(PatternMatch {
+            pattern: CandlePattern::PinBar,
+            direction,
+            index,
+            score: (wick / range).min(1.0),
+        })
+    }
+
+    fn rejection_wick(&self, candle: &Candle, index: usize) -> Option<PatternMatch> {
+        let bullish = self.rejection_wick_ratio(candle, TrendDirection::Long);
+        let bearish = self.rejection_wick_ratio(candle, TrendDirection::Short);
+
+        let (ratio, direction) = if bullish >= bearish {
+            (bullish, TrendDirection::Long)
+        } else {
+            (bearish, TrendDirection::Short)
+        };
+
+        if ratio < self.min_wick_to_range {
+            return None;
+        }
+
+        Some(PatternMatch {
+            pattern: CandlePattern::RejectionWick,
+            direction,
+            index,
+            score: ratio,
+        })
+    }
+
+    fn engulfing(&self, previous: &Candle, candle: &Candle, index: usize) -> Option<PatternMatch> {
+        let previous_body = previous.body();
+        if previous_body.is_zero() {
+            return None;
+        }
+
+        let body_top = candle.open.max(candle.close);
+        let body_bottom = candle.open.min(candle.close);
+        let previous_top = previous.open.max(previous.close);
+        let previous_bottom = previous.open.min(previous.close);
+
+        if body_top < previous_top || body_bottom > previous_bottom || candle.body() <= previous_body {
+            return None;
+        }
+
+        let (pattern, direction) = if previous.is_bearish() && candle.is_bullish() {
+            (CandlePattern::BullishEngulfing, TrendDirection::Long)
+        } else if previous.is_bullish() && candle.is_bearish() {
+            (CandlePattern::BearishEngulfing, TrendDirection::Short)
+        } else {
+            return None;
+        };
+
+        // Twice the previous body or more counts as a full-strength engulf
+        let ratio = to_f64(candle.body()) / to_f64(previous_body);
+
+        Some(PatternMatch {
+            pattern,
+            direction,
+            index,
+            score: (ratio / 2.0).min(1.0),
+        })
+    }
+
+    fn inside_bar(&self, previous: &Candle, candle: &Candle, index: usize) -> Option<PatternMatch> {
+        if candle.high > previous.high || candle.low < previous.low || previous.range().is_zero() {
+            return None;
+        }
+
+        let ratio = to_f64(candle.range()) / to_f64(previous.range());
+
+        Some(PatternMatch {
+            pattern: CandlePattern::InsideBar,
+            direction: TrendDirection::Neutral,
+            index,
+            score: (1.0 - ratio).max(0.0),
+        })
+    }
+
+    fn hammer_or_star(&self, candles: &[Candle], index: usize) -> Option<PatternMatch> {
+        if index < self.context_candles {
+            return None;
+        }
+
+        let candle = &candles[index];
+        let body = to_f64(candle.body());
+        let range = to_f64(candle.range());
+        let upper = to_f64(candle.upper_wick());
+        let lower = to_f64(candle.lower_wick());
+
+        // Hammers and stars only mean something after a move into them
+        let start = candles[index - self.context_candles].close;
+        let end = candles[index - 1].close;
+
+        let min_wick = (body * self.min_wick_to_body).max(range * self.min_wick_to_range);
+
+        if end < start && lower >= min_wick && upper <= body.max(range * 0.1) {
+            return Some(PatternMatch {
+                pattern: CandlePattern::Hammer,
+                direction: TrendDirection::Long,
+                index,
+                score: (lower / range).min(1.0),
+            });
+        }
+
+        if end > start && upper >= min_wick && lower <= body.max(range * 0.1) {
+            return Some(PatternMatch {
+                pattern: CandlePattern::ShootingStar,
+                direction: TrendDirection::Short,
+                index,
+                score: (upper / range).min(1.0),
+            });
+        }
+
+        None
+    }
+}
+
+impl Default for CandlePatternRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn candle(index: usize, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        let dec = |value: f64| Decimal::try_from(value).unwrap();
+        Candle::new(index as i64 * 300_000, dec(open), dec(high), dec(low), dec(close), dec(100.0))
+    }
+
+    fn has(matches: &[PatternMatch], pattern: CandlePattern, direction: TrendDirection) -> bool {
+        matches.iter().any(|m| m.pattern == pattern && m.direction == direction)
+    }
+
+    // Three closes falling or rising into the candle under test
+    fn after_move(closes: [f64; 3], last: Candle) -> Vec<Candle> {
+        let mut candles: Vec<Candle> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| candle(i, close + 0.2, close + 0.5, close - 0.5, close))
+            .collect();
+        candles.push(last);
+        candles
+    }
+
+    #[test]
+    fn pin_bar_needs_a_long_nose_and_small_body() {
+        let recognizer = CandlePatternRecognizer::new();
+
+        let pin = recognizer.detect(&[candle(0, 100.8, 101.1, 98.0, 101.0)]);
+        assert!(has(&pin, CandlePattern::PinBar, TrendDirection::Long));
+
+        let marubozu = recognizer.detect(&[candle(0, 98.0, 101.1, 97.9, 101.0)]);
+        assert!(!marubozu.iter().any(|m| m.pattern == CandlePattern::PinBar));
+    }
+
+    #[test]
+    fn engulfing_body_must_cover_the_previous_body() {
+        let recognizer = CandlePatternRecognizer::new();
+        let previous = candle(0, 101.0, 101.2, 99.8, 100.0);
+
+        let bullish = recognizer.detect(&[previous.clone(), candle(1, 99.8, 101.6, 99.7, 101.5)]);
+        assert!(has(&bullish, CandlePattern::BullishEngulfing, TrendDirection::Long));
+
+        let bearish = recognizer.detect(&[candle(0, 100.0, 101.2, 99.8, 101.0), candle(1, 101.2, 101.3, 99.5, 99.6)]);
+        assert!(has(&bearish, CandlePattern::BearishEngulfing, TrendDirection::Short));
+
+        let smaller = recognizer.detect(&[previous, candle(1, 100.2, 100.9, 100.1, 100.8)]);
+        assert!(!smaller.iter().any(|m| m.pattern == CandlePattern::BullishEngulfing));
+    }
+
+    #[test]
+    fn inside_bar_stays_within_the_previous_range() {
+        let recognizer = CandlePatternRecognizer::new();
+        let mother = candle(0, 99.0, 102.0, 98.0, 101.0);
+
+        let inside = recognizer.detect(&[mother.clone(), candle(1, 100.0, 101.0, 99.0, 100.5)]);
+        assert!(has(&inside, CandlePattern::InsideBar, TrendDirection::Neutral));
+
+        let outside = recognizer.detect(&[mother, candle(1, 100.0, 103.0, 99.0, 100.5)]);
+        assert!(!outside.iter().any(|m| m.pattern == CandlePattern::InsideBar));
+    }
+
+    #[test]
+    fn hammer_and_shooting_star_depend_on_the_prior_move() {
+        let recognizer = CandlePatternRecognizer::new();
+        let hammer = candle(3, 102.5, 102.85, 100.5, 102.8);
+        let star = candle(3, 103.8, 106.0, 103.45, 103.5);
+
+        let after_fall = recognizer.detect(&after_move([105.0, 104.0, 103.0], hammer.clone()));
+        assert!(has(&after_fall, CandlePattern::Hammer, TrendDirection::Long));
+
+        let after_rise = recognizer.detect(&after_move([101.0, 102.0, 103.0], hammer));
+        assert!(!after_rise.iter().any(|m| m.pattern == CandlePattern::Hammer || m.pattern == CandlePattern::ShootingStar));
+
+        let star_after_rise = recognizer.detect(&after_move([101.0, 102.0, 103.0], star.clone()));
+        assert!(has(&star_after_rise, CandlePattern::ShootingStar, TrendDirection::Short));
+
+        let star_after_fall = recognizer.detect(&after_move([105.0, 104.0, 103.0], star));
+        assert!(!star_after_fall.iter().any(|m| m.pattern == CandlePattern::ShootingStar));
+    }
+
+    #[test]
+    fn rejection_wick_covers_half_the_range() {
+        let recognizer = CandlePatternRecognizer::new();
+
+        let wick = candle(0, 100.0, 100.6, 98.5, 100.5);
+        assert!((recognizer.rejection_wick_ratio(&wick, TrendDirection::Long) - 1.5 / 2.1).abs() < 1e-9);
+        assert!(has(&recognizer.detect(&[wick]), CandlePattern::RejectionWick, TrendDirection::Long));
+
+        let body = candle(0, 98.6, 100.6, 98.5, 100.5);
+        assert!(!recognizer.detect(&[body]).iter().any(|m| m.pattern == CandlePattern::RejectionWick));
+    }
+}
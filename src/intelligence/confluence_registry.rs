@@ -0,0 +1,216 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::types::{MarketData, MultiTimeframeData, Timeframe, TrendDirection};
+use crate::intelligence::confluence_scorer::{Breakout, ConfluenceSignal};
+
+// Everything a check gets to look at for one scoring pass
+pub struct ConfluenceContext<'a> {
+    pub data: &'a MultiTimeframeData,
+    pub timeframe: Timeframe,
+    // Closed candles of `timeframe`
+    pub market_data: &'a MarketData,
+    pub breakout: Option<Breakout>,
+    // Breakout direction, or the trend of `timeframe` when there is none
+    pub direction: TrendDirection,
+}
+
+pub trait ConfluenceCheck: Send + Sync {
+    // The returned signal's score must be `weight` when active, 0 otherwise
+    fn evaluate(&self, ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal;
+}
+
+// Plain functions can be registered as checks
+impl<F> ConfluenceCheck for F
+where
+    F: Fn(&ConfluenceContext, u8) -> ConfluenceSignal + Send + Sync,
+{
+    fn evaluate(&self, ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+        self(ctx, weight)
+    }
+}
+
+#[derive(Clone)]
+pub struct RegisteredCheck {
+    pub key: String,
+    pub weight: u8,
+    pub check: Arc<dyn ConfluenceCheck>,
+}
+
+// Ordered set of named checks and their weights. Weights of all registered
+// checks must add up to 100; a weight of 0 keeps a check evaluated and
+// logged without contributing to the score.
+#[derive(Clone, Default)]
+pub struct ConfluenceRegistry {
+    checks: Vec<RegisteredCheck>,
+}
+
+impl ConfluenceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, key: &str, weight: u8, check: Arc<dyn ConfluenceCheck>) -> Result<()> {
+        if self.checks.iter().any(|c| c.key == key) {
+            bail!("confluence check '{}' is already registered", key);
+        }
+
+        self.checks.push(RegisteredCheck {
+            key: key.to_string(),
+            weight,
+            check,
+        });
+        Ok(())
+    }
+
+    // Swap the implementation of a check, keeping its weight and position
+    pub fn replace(&mut self, key: &str, check: Arc<dyn ConfluenceCheck>) -> Result<()> {
+        match self.checks.iter_mut().find(|c| c.key == key) {
+            Some(registered) => {
+                registered.check = check;
+                Ok(())
+            }
+            None => bail!("unknown confluence check '{}'", key),
+        }
+    }
+
+    pub fn set_weight(&mut self, key: &str, weight: u8) -> Result<()> {
+        match self.checks.iter_mut().find(|c| c.key == key) {
+            Some(check) => {
+                check.weight = weight;
+                Ok(())
+            }
+            None => bail!("unknown confluence check '{}'", key),
+        }
+    }
+
+    pub fn apply_weights(&mut self, weights: &HashMap<String, u8>) -> Result<()> {
+        for (key, weight) in weights {
+            self.set_weight(key, *weight)?;
+        }
+        Ok(())
+    }
+
+    pub fn total_weight(&self) -> u32 {
+        self.checks.iter().map(|c| c.weight as u32).sum()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let total = self.total_weight();
+        if total != 100 {
+            let weights: Vec<String> = self.checks
+                .iter()
+                .map(|c| format!("{}={}", c.key, c.weight))
+                .collect();
+            bail!("confluence weights must add up to 100, got {} ({})", total, weights.join(", "));
+        }
+        Ok(())
+    }
+
+    pub fn checks(&self) -> &[RegisteredCheck] {
+        &self.checks
+    }
+
+    pub fn keys(&self) -> Vec<&str> {
+        self.checks.iter().map(|c| c.key.as_str()).collect()
+    }
+}
+
+// "breakout=20,retest=20,session=10" -> {breakout: 20, ...}
+pub fn parse_weights(spec: &str) -> Result<HashMap<String, u8>> {
+    let mut weights = HashMap::new();
+
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((key, value)) = entry.split_once('=') else {
+            bail!("invalid confluence weight '{}', expected name=weight", entry);
+        };
+
+        let weight: u8 = value.trim().parse()
+            .map_err(|_| anyhow::anyhow!("invalid weight for '{}': {}", key.trim(), value.trim()))?;
+
+        if weights.insert(key.trim().to_string(), weight).is_some() {
+            bail!("confluence weight for '{}' given twice", key.trim());
+        }
+    }
+
+    Ok(weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(reason: &'static str) -> Arc<dyn ConfluenceCheck> {
+        Arc::new(move |_: &ConfluenceContext, weight: u8| ConfluenceSignal::new("Test", weight, true, reason.to_string()))
+    }
+
+    fn registry() -> ConfluenceRegistry {
+        let mut registry = ConfluenceRegistry::new();
+        registry.register("breakout", 60, check("breakout")).unwrap();
+        registry.register("retest", 40, check("retest")).unwrap();
+        registry.register("book", 0, check("book")).unwrap();
+        registry
+    }
+
+    #[test]
+    fn register_rejects_duplicate_keys() {
+        let mut registry = registry();
+
+        assert!(registry.register("retest", 10, check("again")).is_err());
+        assert_eq!(registry.keys(), vec!["breakout", "retest", "book"]);
+        assert_eq!(registry.checks()[1].weight, 40);
+    }
+
+    #[test]
+    fn replace_keeps_weight_and_position() {
+        let mut registry = registry();
+        registry.replace("retest", check("replaced")).unwrap();
+
+        let data = MultiTimeframeData::new("BTCUSDT".to_string(), 0);
+        let market_data = MarketData::new("BTCUSDT".to_string(), Timeframe::M15);
+        let ctx = ConfluenceContext {
+            data: &data,
+            timeframe: Timeframe::M15,
+            market_data: &market_data,
+            breakout: None,
+            direction: TrendDirection::Long,
+        };
+
+        let retest = &registry.checks()[1];
+        assert_eq!((retest.key.as_str(), retest.weight), ("retest", 40));
+        assert_eq!(retest.check.evaluate(&ctx, retest.weight).reason, "replaced");
+        assert!(registry.replace("missing", check("missing")).is_err());
+    }
+
+    #[test]
+    fn weights_must_add_up_to_100() {
+        let mut registry = registry();
+        registry.validate().unwrap();
+
+        registry.set_weight("book", 5).unwrap();
+        let error = registry.validate().unwrap_err().to_string();
+        assert!(error.contains("got 105"), "{}", error);
+        assert!(error.contains("book=5"), "{}", error);
+
+        registry.apply_weights(&parse_weights("breakout=55").unwrap()).unwrap();
+        registry.validate().unwrap();
+        assert!(registry.set_weight("missing", 5).is_err());
+    }
+
+    #[test]
+    fn parse_weights_reads_name_weight_pairs() {
+        let weights = parse_weights(" breakout = 20, retest=30 ,").unwrap();
+        assert_eq!(weights.len(), 2);
+        assert_eq!((weights["breakout"], weights["retest"]), (20, 30));
+        assert!(parse_weights("").unwrap().is_empty());
+
+        assert!(parse_weights("breakout20").is_err());
+        assert!(parse_weights("breakout=high").is_err());
+        assert!(parse_weights("breakout=300").is_err());
+        assert!(parse_weights("breakout=20,breakout=30").is_err());
+
+        // Unknown keys parse but fail against the registry
+        let unknown = parse_weights("breakout=20,volume=80").unwrap();
+        assert!(registry().apply_weights(&unknown).is_err());
+    }
+}
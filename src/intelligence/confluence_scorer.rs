@@ -1,5 +1,26 @@
 use serde::{Deserialize, Serialize};
-use crate::types::{MarketData, Timeframe, TrendDirection};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use crate::types::{MarketData, MultiTimeframeData, Timeframe, TrendDirection, to_f64};
+use crate::intelligence::volume_profile::{self, KeyLevel, VolumeProfile, Vwap};
+use crate::intelligence::OrderFlow;
+use crate::intelligence::{liquidations, order_book};
+use crate::intelligence::session_calendar::SessionCalendar;
+use crate::intelligence::confluence_registry::{ConfluenceCheck, ConfluenceContext, ConfluenceRegistry};
+
+// Candles and bins used for the volume profile of the signal timeframe
+const PROFILE_LOOKBACK: usize = 100;
+const PROFILE_BINS: usize = 24;
+
+// Structure lookback and how recent a breakout must be to still count
+const STRUCTURE_LOOKBACK: usize = 20;
+const BREAKOUT_MAX_AGE: usize = 5;
+// Retest must come back within this fraction of ATR(14) of the level
+const RETEST_ATR_TOLERANCE: f64 = 0.25;
+const VOLUME_SPIKE_MULTIPLIER: f64 = 1.5;
+const MACD_DIVERGENCE_LOOKBACK: usize = 30;
+// Opposing directional points at which a setup counts as conflicted
+pub const DEFAULT_CONFLICT_THRESHOLD: u8 = 20;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfluenceSignal {
@@ -7,139 +28,818 @@ pub struct ConfluenceSignal {
     pub score: u8,
     pub weight: u8,
     pub is_active: bool,
+    // Side this signal votes for; Neutral signals back either side
+    pub direction: TrendDirection,
+    pub reason: String,
+    // Numbers the decision was based on, e.g. "rsi" -> 55.2
+    pub metrics: BTreeMap<String, f64>,
 }
 
-#[derive(Debug, Clone)]
+impl ConfluenceSignal {
+    pub fn new(name: &str, weight: u8, is_active: bool, reason: String) -> Self {
+        Self {
+            name: name.to_string(),
+            score: if is_active { weight } else { 0 },
+            weight,
+            is_active,
+            direction: TrendDirection::Neutral,
+            reason,
+            metrics: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_direction(mut self, direction: TrendDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_metric(mut self, key: &str, value: f64) -> Self {
+        self.metrics.insert(key.to_string(), value);
+        self
+    }
+}
+
+// Close beyond the prior structure high/low within the last few candles
+#[derive(Debug, Clone, Copy)]
+pub struct Breakout {
+    pub direction: TrendDirection,
+    pub level: f64,
+    // Index of the breakout candle in the signal timeframe series
+    pub index: usize,
+}
+
+#[derive(Clone)]
 pub struct ConfluenceScorer {
     min_score: u8,
+    conflict_threshold: u8,
+    registry: ConfluenceRegistry,
 }
 
 impl ConfluenceScorer {
     pub fn new(min_score: u8) -> Self {
-        Self { min_score }
+        Self::with_registry(min_score, Self::default_registry())
+    }
+
+    pub fn with_registry(min_score: u8, registry: ConfluenceRegistry) -> Self {
+        Self {
+            min_score,
+            conflict_threshold: DEFAULT_CONFLICT_THRESHOLD,
+            registry,
+        }
+    }
+
+    pub fn with_conflict_threshold(mut self, conflict_threshold: u8) -> Self {
+        self.conflict_threshold = conflict_threshold;
+        self
+    }
+
+    // The seven strategy signals with the spec's weights, plus order book
+    // and liquidation signals that are only logged until given a weight
+    pub fn default_registry() -> ConfluenceRegistry {
+        let mut registry = ConfluenceRegistry::new();
+
+        let builtin: [(&str, u8, Arc<dyn ConfluenceCheck>); 11] = [
+            // Primary Signals (40 pts)
+            ("breakout", 20, Arc::new(breakout_check)),
+            ("retest", 20, Arc::new(retest_check)),
+
+            // Secondary Signals (35 pts)
+            ("volume_spike", 10, Arc::new(volume_spike_check)),
+            ("rsi_zone", 10, Arc::new(rsi_zone_check)),
+            ("macd_divergence", 15, Arc::new(macd_divergence_check)),
+
+            // Time Confluences (25 pts)
+            ("mtf_alignment", 15, Arc::new(mtf_alignment_check)),
+            ("session", 10, Arc::new(SessionCalendar::new())),
+
+            // Order Book (0 pts)
+            ("book_imbalance", 0, Arc::new(order_book::book_imbalance_check)),
+            ("liquidity_walls", 0, Arc::new(order_book::liquidity_walls_check)),
+            ("spoofing", 0, Arc::new(order_book::spoofing_check)),
+            ("liquidations", 0, Arc::new(liquidations::liquidation_cascade_check(liquidations::DEFAULT_MIN_CASCADE_USD))),
+        ];
+
+        for (key, weight, check) in builtin {
+            registry
+                .register(key, weight, check)
+                .expect("built-in confluence keys are unique");
+        }
+
+        registry
+    }
+
+    pub fn registry(&self) -> &ConfluenceRegistry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut ConfluenceRegistry {
+        &mut self.registry
     }
 
     pub async fn calculate_score(
         &self,
-        market_data: &MarketData,
-        _timeframe: Timeframe,
+        data: &MultiTimeframeData,
+        timeframe: Timeframe,
     ) -> ConfluenceResult {
-        let mut signals = Vec::new();
+        // Signals only ever see closed bars of the requested timeframe
+        let market_data = &data
+            .aligned(timeframe)
+            .unwrap_or_else(|| MarketData::new(data.symbol.clone(), timeframe));
 
-        // Primary Signals (40 pts)
-        signals.push(self.check_breakout(market_data, 20));
-        signals.push(self.check_retest(market_data, 20));
+        let breakout = find_breakout(market_data);
+        let ctx = ConfluenceContext {
+            data,
+            timeframe,
+            market_data,
+            breakout,
+            direction: breakout
+                .map(|b| b.direction)
+                .unwrap_or_else(|| market_data.trend_direction()),
+        };
 
-        // Secondary Signals (35 pts)
-        signals.push(self.check_volume_spike(market_data, 10));
-        signals.push(self.check_rsi_zone(market_data, 10));
-        signals.push(self.check_macd_divergence(market_data, 15));
+        let signals: Vec<ConfluenceSignal> = self.registry
+            .checks()
+            .iter()
+            .map(|c| c.check.evaluate(&ctx, c.weight))
+            .collect();
 
-        // Time Confluences (25 pts)
-        signals.push(self.check_multi_timeframe_alignment(market_data, 15));
-        signals.push(self.check_trading_session(10));
+        let votes = |direction: TrendDirection| {
+            signals
+                .iter()
+                .filter(|s| s.is_active && s.direction == direction)
+                .map(|s| s.score as u32)
+                .sum::<u32>()
+                .min(100) as u8
+        };
+        let long_score = votes(TrendDirection::Long);
+        let short_score = votes(TrendDirection::Short);
+        let neutral_score = votes(TrendDirection::Neutral);
 
-        let total_score: u8 = signals
-            .iter()
-            .filter(|s| s.is_active)
-            .map(|s| s.score)
-            .sum();
+        // Directional votes pick the side, neutral ones add to whichever wins
+        let (direction, opposing_score) = match long_score.cmp(&short_score) {
+            std::cmp::Ordering::Greater => (TrendDirection::Long, short_score),
+            std::cmp::Ordering::Less => (TrendDirection::Short, long_score),
+            std::cmp::Ordering::Equal => (TrendDirection::Neutral, long_score),
+        };
+        let total_score = (long_score.max(short_score) as u32 + neutral_score as u32).min(100) as u8;
 
         ConfluenceResult {
             total_score,
+            long_score,
+            short_score,
+            neutral_score,
+            direction,
+            is_valid: direction != TrendDirection::Neutral
+                && total_score >= self.min_score
+                && opposing_score < self.conflict_threshold,
             signals,
-            is_valid: total_score >= self.min_score,
         }
     }
 
-    fn check_breakout(&self, _data: &MarketData, weight: u8) -> ConfluenceSignal {
-        // TODO: Implement breakout detection
-        let is_active = false;
+    // POC, value area edges and session VWAP of the recent candles
+    pub fn volume_levels(&self, data: &MarketData) -> Vec<KeyLevel> {
+        volume_levels(data)
+    }
 
-        ConfluenceSignal {
-            name: "Breakout Confirmado".to_string(),
-            score: if is_active { weight } else { 0 },
-            weight,
-            is_active,
-        }
+    // Volume level the current price is retesting, within a fraction of ATR
+    pub fn volume_level_retest(&self, data: &MarketData, atr_fraction: f64) -> Option<KeyLevel> {
+        volume_level_retest(data, atr_fraction)
     }
+}
 
-    fn check_retest(&self, _data: &MarketData, weight: u8) -> ConfluenceSignal {
-        // TODO: Implement retest detection
-        let is_active = false;
+fn volume_levels(data: &MarketData) -> Vec<KeyLevel> {
+    let start = data.candles.len().saturating_sub(PROFILE_LOOKBACK);
+    let candles = &data.candles[start..];
 
-        ConfluenceSignal {
-            name: "Retest Exitoso".to_string(),
-            score: if is_active { weight } else { 0 },
-            weight,
-            is_active,
-        }
+    let mut levels = Vec::new();
+    if let Some(profile) = VolumeProfile::build(candles, PROFILE_BINS) {
+        levels.extend(profile.key_levels());
+    }
+    if let Some(vwap) = Vwap::session(&data.candles) {
+        levels.extend(vwap.key_levels());
     }
 
-    fn check_volume_spike(&self, _data: &MarketData, weight: u8) -> ConfluenceSignal {
-        // TODO: Implement volume spike detection
-        let is_active = false;
+    levels
+}
 
-        ConfluenceSignal {
-            name: "Volume Spike".to_string(),
-            score: if is_active { weight } else { 0 },
-            weight,
-            is_active,
-        }
+fn volume_level_retest(data: &MarketData, atr_fraction: f64) -> Option<KeyLevel> {
+    let price = to_f64(data.close());
+    let atr = to_f64(data.atr(14));
+    if atr == 0.0 {
+        return None;
     }
 
-    fn check_rsi_zone(&self, _data: &MarketData, weight: u8) -> ConfluenceSignal {
-        // TODO: Implement RSI zone check
-        let is_active = false;
+    let levels = volume_levels(data);
+    volume_profile::nearest_level(&levels, price, atr * atr_fraction).cloned()
+}
 
-        ConfluenceSignal {
-            name: "RSI Zona Favorable".to_string(),
-            score: if is_active { weight } else { 0 },
-            weight,
-            is_active,
+// Built-in checks as registered in `default_registry`
+fn breakout_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    check_breakout(ctx.market_data, ctx.breakout, weight)
+}
+
+fn retest_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    check_retest(ctx.market_data, ctx.breakout, weight)
+}
+
+fn volume_spike_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    check_volume_spike(ctx.market_data, ctx.breakout, weight)
+}
+
+// RSI between 40 and 60 sits in both zones and backs either side
+fn rsi_zone_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    let long = check_rsi_zone(ctx.market_data, TrendDirection::Long, weight);
+    let short = check_rsi_zone(ctx.market_data, TrendDirection::Short, weight);
+
+    match (long.is_active, short.is_active) {
+        (true, true) => {
+            let rsi = long.metrics["rsi"];
+            ConfluenceSignal::new(long.name.as_str(), weight, true, format!("RSI {:.1} inside both the Long and Short zones", rsi))
+                .with_metric("rsi", rsi)
         }
+        (false, true) => short,
+        _ => long,
     }
+}
 
-    fn check_macd_divergence(&self, _data: &MarketData, weight: u8) -> ConfluenceSignal {
-        // TODO: Implement MACD divergence detection
-        let is_active = false;
+// Votes for whichever divergence is present; the setup direction wins a tie
+fn macd_divergence_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    let preferred = check_macd_divergence(ctx.market_data, ctx.direction, weight);
+    if preferred.is_active {
+        return preferred;
+    }
 
-        ConfluenceSignal {
-            name: "MACD Divergencia".to_string(),
-            score: if is_active { weight } else { 0 },
+    let other = match ctx.direction {
+        TrendDirection::Long => TrendDirection::Short,
+        _ => TrendDirection::Long,
+    };
+    let alternative = check_macd_divergence(ctx.market_data, other, weight);
+    if alternative.is_active { alternative } else { preferred }
+}
+
+fn mtf_alignment_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    check_multi_timeframe_alignment(ctx.data, weight)
+}
+
+fn check_breakout(data: &MarketData, breakout: Option<Breakout>, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Breakout Confirmado";
+
+    let Some(breakout) = breakout else {
+        return ConfluenceSignal::new(
+            NAME,
             weight,
-            is_active,
+            false,
+            format!("No close beyond the {}-candle structure in the last {} candles", STRUCTURE_LOOKBACK, BREAKOUT_MAX_AGE),
+        );
+    };
+
+    // Order flow must not be pushing against the break
+    let flow = OrderFlow::from_candles(&data.candles[..=breakout.index]);
+    let opposite = match breakout.direction {
+        TrendDirection::Long => TrendDirection::Short,
+        _ => TrendDirection::Long,
+    };
+    let flow_against = flow.confirms_breakout(opposite, 3);
+    let close = to_f64(data.candles[breakout.index].close);
+    let candles_ago = data.candles.len() - 1 - breakout.index;
+
+    let reason = if flow_against {
+        format!("{:?} break of {:.2} rejected by opposing order flow", breakout.direction, breakout.level)
+    } else {
+        format!("{:?} close at {:.2} beyond structure {:.2}, {} candles ago", breakout.direction, close, breakout.level, candles_ago)
+    };
+
+    ConfluenceSignal::new(NAME, weight, !flow_against, reason)
+        .with_direction(breakout.direction)
+        .with_metric("level", breakout.level)
+        .with_metric("close", close)
+        .with_metric("candles_ago", candles_ago as f64)
+        .with_metric("imbalance", flow.imbalance.last().copied().unwrap_or(0.0))
+        .with_metric("cvd_change", flow.cvd_change(3))
+}
+
+fn check_retest(data: &MarketData, breakout: Option<Breakout>, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Retest Exitoso";
+
+    let Some(breakout) = breakout else {
+        return ConfluenceSignal::new(NAME, weight, false, "No breakout to retest".to_string());
+    };
+
+    let atr = to_f64(data.atr(14));
+    let tolerance = atr * RETEST_ATR_TOLERANCE;
+    let after = &data.candles[breakout.index + 1..];
+    let close = to_f64(data.close());
+
+    if after.is_empty() || atr == 0.0 {
+        return ConfluenceSignal::new(NAME, weight, false, "Breakout candle is the latest candle, no pullback yet".to_string())
+            .with_direction(breakout.direction)
+            .with_metric("level", breakout.level);
+    }
+
+    // Deepest pullback towards the broken level, and whether it held
+    let (touched, held, extreme) = match breakout.direction {
+        TrendDirection::Long => {
+            let low = after.iter().map(|c| to_f64(c.low)).fold(f64::MAX, f64::min);
+            (low <= breakout.level + tolerance, close > breakout.level, low)
+        }
+        _ => {
+            let high = after.iter().map(|c| to_f64(c.high)).fold(f64::MIN, f64::max);
+            (high >= breakout.level - tolerance, close < breakout.level, high)
         }
+    };
+
+    let is_active = touched && held;
+    let mut reason = match (touched, held) {
+        (true, true) => format!("Pullback to {:.2} held the broken level {:.2}", extreme, breakout.level),
+        (true, false) => format!("Price closed back through the broken level {:.2}", breakout.level),
+        _ => format!("Pullback reached {:.2}, not within {:.2} of level {:.2}", extreme, tolerance, breakout.level),
+    };
+
+    let volume_level = volume_level_retest(data, RETEST_ATR_TOLERANCE * 2.0);
+    if let (true, Some(level)) = (is_active, &volume_level) {
+        reason.push_str(&format!(" at {} {:.2}", level.name, level.price));
     }
 
-    fn check_multi_timeframe_alignment(&self, _data: &MarketData, weight: u8) -> ConfluenceSignal {
-        // TODO: Implement multi-timeframe alignment check
-        let is_active = false;
+    let mut signal = ConfluenceSignal::new(NAME, weight, is_active, reason)
+        .with_direction(breakout.direction)
+        .with_metric("level", breakout.level)
+        .with_metric("pullback_extreme", extreme)
+        .with_metric("tolerance", tolerance)
+        .with_metric("close", close);
+    if let Some(level) = volume_level {
+        signal = signal.with_metric("volume_level", level.price);
+    }
+    signal
+}
 
-        ConfluenceSignal {
-            name: "Alineación Multi-TF".to_string(),
-            score: if is_active { weight } else { 0 },
-            weight,
-            is_active,
+fn check_volume_spike(data: &MarketData, breakout: Option<Breakout>, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Volume Spike";
+
+    // Spike measured on the breakout candle, else on the latest candle
+    let index = match breakout {
+        Some(b) => b.index,
+        None if !data.candles.is_empty() => data.candles.len() - 1,
+        None => return ConfluenceSignal::new(NAME, weight, false, "No candles".to_string()),
+    };
+
+    let start = index.saturating_sub(STRUCTURE_LOOKBACK);
+    let previous = &data.candles[start..index];
+    if previous.is_empty() {
+        return ConfluenceSignal::new(NAME, weight, false, "Not enough history for average volume".to_string());
+    }
+
+    let average = previous.iter().map(|c| to_f64(c.volume)).sum::<f64>() / previous.len() as f64;
+    let volume = to_f64(data.candles[index].volume);
+    let ratio = if average > 0.0 { volume / average } else { 0.0 };
+    let is_active = ratio > VOLUME_SPIKE_MULTIPLIER;
+
+    // Volume backs the side the spike candle moved towards
+    let direction = match breakout {
+        Some(b) => b.direction,
+        None if data.candles[index].is_bullish() => TrendDirection::Long,
+        None if data.candles[index].is_bearish() => TrendDirection::Short,
+        None => TrendDirection::Neutral,
+    };
+
+    let reason = format!(
+        "{} candle volume {:.1}x the {}-candle average (needs > {}x)",
+        if breakout.is_some() { "Breakout" } else { "Last" },
+        ratio,
+        previous.len(),
+        VOLUME_SPIKE_MULTIPLIER
+    );
+
+    ConfluenceSignal::new(NAME, weight, is_active, reason)
+        .with_direction(direction)
+        .with_metric("volume", volume)
+        .with_metric("average_volume", average)
+        .with_metric("ratio", ratio)
+}
+
+fn check_rsi_zone(data: &MarketData, direction: TrendDirection, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "RSI Zona Favorable";
+
+    let rsi = data.rsi(14);
+    let (is_active, zone) = match direction {
+        TrendDirection::Long => (rsi > 40.0 && rsi < 70.0, "40-70"),
+        TrendDirection::Short => (rsi > 30.0 && rsi < 60.0, "30-60"),
+        TrendDirection::Neutral => (false, "none"),
+    };
+
+    let reason = if direction == TrendDirection::Neutral {
+        format!("RSI {:.1} with no trade direction", rsi)
+    } else {
+        format!(
+            "RSI {:.1} {} the {:?} zone {}",
+            rsi,
+            if is_active { "inside" } else { "outside" },
+            direction,
+            zone
+        )
+    };
+
+    ConfluenceSignal::new(NAME, weight, is_active, reason)
+        .with_direction(direction)
+        .with_metric("rsi", rsi)
+}
+
+fn check_macd_divergence(data: &MarketData, direction: TrendDirection, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "MACD Divergencia";
+
+    if data.candles.len() < MACD_DIVERGENCE_LOOKBACK + 26 || direction == TrendDirection::Neutral {
+        return ConfluenceSignal::new(NAME, weight, false, "Not enough history or no trade direction".to_string());
+    }
+
+    // Compare the earlier and the later half of the lookback window
+    let macd = data.macd_series(12, 26, 9);
+    let start = data.candles.len() - MACD_DIVERGENCE_LOOKBACK;
+    let middle = start + MACD_DIVERGENCE_LOOKBACK / 2;
+    let end = data.candles.len();
+
+    let lows = |from: usize, to: usize| {
+        let price = data.candles[from..to].iter().map(|c| to_f64(c.low)).fold(f64::MAX, f64::min);
+        let line = macd[from..to].iter().map(|p| p.macd).fold(f64::MAX, f64::min);
+        (price, line)
+    };
+    let highs = |from: usize, to: usize| {
+        let price = data.candles[from..to].iter().map(|c| to_f64(c.high)).fold(f64::MIN, f64::max);
+        let line = macd[from..to].iter().map(|p| p.macd).fold(f64::MIN, f64::max);
+        (price, line)
+    };
+
+    let (is_active, earlier, later, kind) = match direction {
+        TrendDirection::Long => {
+            // Bullish: price lower low, MACD higher low
+            let (earlier, later) = (lows(start, middle), lows(middle, end));
+            (later.0 < earlier.0 && later.1 > earlier.1, earlier, later, "bullish")
+        }
+        _ => {
+            // Bearish: price higher high, MACD lower high
+            let (earlier, later) = (highs(start, middle), highs(middle, end));
+            (later.0 > earlier.0 && later.1 < earlier.1, earlier, later, "bearish")
         }
+    };
+
+    let reason = if is_active {
+        format!(
+            "Regular {} divergence: price {:.2} -> {:.2}, MACD {:.4} -> {:.4}",
+            kind, earlier.0, later.0, earlier.1, later.1
+        )
+    } else {
+        format!("No {} divergence over the last {} candles", kind, MACD_DIVERGENCE_LOOKBACK)
+    };
+
+    ConfluenceSignal::new(NAME, weight, is_active, reason)
+        .with_direction(direction)
+        .with_metric("price_earlier", earlier.0)
+        .with_metric("price_later", later.0)
+        .with_metric("macd_earlier", earlier.1)
+        .with_metric("macd_later", later.1)
+}
+
+fn check_multi_timeframe_alignment(data: &MultiTimeframeData, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Alineación Multi-TF";
+
+    // H1 + M15 + M5 must all point the same way
+    let timeframes = [Timeframe::H1, Timeframe::M15, Timeframe::M5];
+    let directions: Vec<TrendDirection> = timeframes
+        .iter()
+        .filter_map(|tf| data.aligned(*tf))
+        .map(|d| d.trend_direction())
+        .collect();
+
+    let is_active = directions.len() == timeframes.len()
+        && directions[0] != TrendDirection::Neutral
+        && directions.iter().all(|d| *d == directions[0]);
+
+    let reason = if directions.len() < timeframes.len() {
+        "Missing H1/M15/M5 data".to_string()
+    } else {
+        format!("H1 {:?}, M15 {:?}, M5 {:?}", directions[0], directions[1], directions[2])
+    };
+
+    let vote = if is_active { directions[0] } else { TrendDirection::Neutral };
+    let mut signal = ConfluenceSignal::new(NAME, weight, is_active, reason).with_direction(vote);
+    for (tf, direction) in timeframes.iter().zip(directions.iter()) {
+        signal = signal.with_metric(&format!("{:?}", tf), direction_value(*direction));
     }
+    signal
+}
 
-    fn check_trading_session(&self, weight: u8) -> ConfluenceSignal {
-        // TODO: Implement trading session check (London/NY overlap, etc.)
-        let is_active = false;
+// Latest close beyond the high/low of the STRUCTURE_LOOKBACK candles before it
+fn find_breakout(data: &MarketData) -> Option<Breakout> {
+    let len = data.candles.len();
+    if len < STRUCTURE_LOOKBACK + 1 {
+        return None;
+    }
 
-        ConfluenceSignal {
-            name: "Sesión Alta Liquidez".to_string(),
-            score: if is_active { weight } else { 0 },
-            weight,
-            is_active,
+    let first = len.saturating_sub(BREAKOUT_MAX_AGE).max(STRUCTURE_LOOKBACK);
+    for index in (first..len).rev() {
+        let window = &data.candles[index - STRUCTURE_LOOKBACK..index];
+        let high = window.iter().map(|c| to_f64(c.high)).fold(f64::MIN, f64::max);
+        let low = window.iter().map(|c| to_f64(c.low)).fold(f64::MAX, f64::min);
+        let close = to_f64(data.candles[index].close);
+
+        if close > high {
+            return Some(Breakout { direction: TrendDirection::Long, level: high, index });
+        }
+        if close < low {
+            return Some(Breakout { direction: TrendDirection::Short, level: low, index });
         }
     }
+
+    None
+}
+
+fn direction_value(direction: TrendDirection) -> f64 {
+    match direction {
+        TrendDirection::Long => 1.0,
+        TrendDirection::Short => -1.0,
+        TrendDirection::Neutral => 0.0,
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ConfluenceResult {
+    // Score of the resolved direction, neutral votes included
     pub total_score: u8,
+    // Points voted for each side by directional signals only
+    pub long_score: u8,
+    pub short_score: u8,
+    pub neutral_score: u8,
+    pub direction: TrendDirection,
     pub signals: Vec<ConfluenceSignal>,
     pub is_valid: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Candle;
+    use rust_decimal::Decimal;
+
+    // 2024-01-02 00:00 UTC, a Tuesday
+    const BASE: i64 = 1_704_153_600_000;
+    const M15: i64 = 15 * 60_000;
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::try_from(value).unwrap()
+    }
+
+    fn candle(index: usize, open: f64, high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle::new(BASE + index as i64 * M15, dec(open), dec(high), dec(low), dec(close), dec(volume))
+    }
+
+    fn market_data(candles: Vec<Candle>) -> MarketData {
+        MarketData {
+            symbol: "BTCUSDT".to_string(),
+            candles,
+            timeframe: Timeframe::M15,
+        }
+    }
+
+    // 40 candles chopping between 99.5 and 101
+    fn range_candles() -> Vec<Candle> {
+        (0..40)
+            .map(|i| {
+                let (open, close) = if i % 2 == 0 { (100.0, 100.5) } else { (100.5, 100.0) };
+                candle(i, open, 101.0, 99.5, close, 100.0)
+            })
+            .collect()
+    }
+
+    // Range followed by a high-volume close above 101
+    fn breakout_candles() -> Vec<Candle> {
+        let mut candles = range_candles();
+        candles.push(candle(40, 100.5, 103.0, 100.4, 102.8, 300.0));
+        candles
+    }
+
+    // Steady climb so every EMA stack points up
+    fn trending_candles(count: usize, step: f64, timeframe: Timeframe) -> MarketData {
+        let candles = (0..count)
+            .map(|i| {
+                let open = 100.0 + step * i as f64;
+                let close = open + step;
+                let (high, low) = (open.max(close) + 0.1, open.min(close) - 0.1);
+                Candle::new(BASE + i as i64 * timeframe.to_millis(), dec(open), dec(high), dec(low), dec(close), dec(100.0))
+            })
+            .collect();
+
+        MarketData {
+            symbol: "BTCUSDT".to_string(),
+            candles,
+            timeframe,
+        }
+    }
+
+    #[test]
+    fn breakout_detected_above_range_high() {
+        let data = market_data(breakout_candles());
+
+        let breakout = find_breakout(&data).expect("breakout");
+        assert_eq!(breakout.direction, TrendDirection::Long);
+        assert_eq!(breakout.level, 101.0);
+        assert_eq!(breakout.index, 40);
+
+        let signal = check_breakout(&data, Some(breakout), 20);
+        assert!(signal.is_active, "{}", signal.reason);
+        assert_eq!(signal.score, 20);
+        assert_eq!(signal.metrics["level"], 101.0);
+    }
+
+    #[test]
+    fn no_breakout_inside_range() {
+        let data = market_data(range_candles());
+
+        assert!(find_breakout(&data).is_none());
+
+        let signal = check_breakout(&data, None, 20);
+        assert!(!signal.is_active);
+        assert_eq!(signal.score, 0);
+    }
+
+    #[test]
+    fn retest_that_holds_the_level_is_active() {
+        let mut candles = breakout_candles();
+        candles.push(candle(41, 102.8, 103.0, 101.1, 102.2, 120.0));
+        let data = market_data(candles);
+
+        let signal = check_retest(&data, find_breakout(&data), 20);
+        assert!(signal.is_active, "{}", signal.reason);
+        assert_eq!(signal.metrics["pullback_extreme"], 101.1);
+    }
+
+    #[test]
+    fn retest_closing_back_inside_range_fails() {
+        let mut candles = breakout_candles();
+        candles.push(candle(41, 102.8, 103.0, 100.5, 100.8, 120.0));
+        let data = market_data(candles);
+
+        let signal = check_retest(&data, find_breakout(&data), 20);
+        assert!(!signal.is_active);
+        assert!(signal.reason.contains("closed back"));
+    }
+
+    #[test]
+    fn retest_needs_a_pullback() {
+        let data = market_data(breakout_candles());
+
+        let signal = check_retest(&data, find_breakout(&data), 20);
+        assert!(!signal.is_active);
+    }
+
+    #[test]
+    fn volume_spike_on_breakout_candle() {
+        let data = market_data(breakout_candles());
+
+        let signal = check_volume_spike(&data, find_breakout(&data), 10);
+        assert!(signal.is_active, "{}", signal.reason);
+        assert_eq!(signal.metrics["ratio"], 3.0);
+    }
+
+    #[test]
+    fn average_volume_is_not_a_spike() {
+        let data = market_data(range_candles());
+
+        let signal = check_volume_spike(&data, None, 10);
+        assert!(!signal.is_active);
+        assert_eq!(signal.metrics["ratio"], 1.0);
+    }
+
+    #[test]
+    fn rsi_zone_depends_on_direction() {
+        // +1 / -0.5 alternating gives RSI(14) of 66.7
+        let mut price = 100.0;
+        let candles: Vec<Candle> = (0..30)
+            .map(|i| {
+                let open = price;
+                price += if i % 2 == 0 { 1.0 } else { -0.5 };
+                candle(i, open, open.max(price) + 0.1, open.min(price) - 0.1, price, 100.0)
+            })
+            .collect();
+        let data = market_data(candles);
+
+        let long = check_rsi_zone(&data, TrendDirection::Long, 10);
+        assert!(long.is_active, "{}", long.reason);
+        assert!((long.metrics["rsi"] - 66.67).abs() < 0.1);
+
+        let short = check_rsi_zone(&data, TrendDirection::Short, 10);
+        assert!(!short.is_active);
+
+        let neutral = check_rsi_zone(&data, TrendDirection::Neutral, 10);
+        assert!(!neutral.is_active);
+    }
+
+    #[test]
+    fn bullish_macd_divergence_on_lower_low() {
+        let mut closes: Vec<f64> = vec![100.0; 30];
+        // Sharp sell-off into the first half of the window
+        closes.extend((1..=15).map(|i| 100.0 - 2.0 * i as f64));
+        // Bounce, then a slow grind to a marginally lower low
+        closes.extend([77.0, 78.0, 77.5, 77.0, 76.0, 75.0, 74.0, 73.0, 72.5, 72.0, 71.5, 71.0, 70.5, 70.0, 69.6]);
+
+        let candles = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| {
+                let open = if i == 0 { close } else { closes[i - 1] };
+                candle(i, open, open.max(close) + 0.2, open.min(close) - 0.2, close, 100.0)
+            })
+            .collect();
+        let data = market_data(candles);
+
+        let long = check_macd_divergence(&data, TrendDirection::Long, 15);
+        assert!(long.is_active, "{}", long.reason);
+        assert!(long.metrics["price_later"] < long.metrics["price_earlier"]);
+        assert!(long.metrics["macd_later"] > long.metrics["macd_earlier"]);
+
+        let short = check_macd_divergence(&data, TrendDirection::Short, 15);
+        assert!(!short.is_active);
+    }
+
+    #[test]
+    fn multi_timeframe_alignment_requires_all_three() {
+        let mut data = MultiTimeframeData::new("BTCUSDT".to_string(), i64::MAX);
+        data.insert(trending_candles(60, 0.5, Timeframe::H1));
+        data.insert(trending_candles(60, 0.5, Timeframe::M15));
+        data.insert(trending_candles(60, 0.5, Timeframe::M5));
+
+        let aligned = check_multi_timeframe_alignment(&data, 15);
+        assert!(aligned.is_active, "{}", aligned.reason);
+
+        data.insert(trending_candles(60, -0.5, Timeframe::M5));
+        let conflicting = check_multi_timeframe_alignment(&data, 15);
+        assert!(!conflicting.is_active);
+        assert_eq!(conflicting.metrics["M5"], -1.0);
+    }
+
+    #[test]
+    fn default_weights_add_up_to_100() {
+        let mut registry = ConfluenceScorer::default_registry();
+        assert!(registry.validate().is_ok());
+
+        registry.set_weight("session", 20).unwrap();
+        assert!(registry.validate().is_err());
+        assert!(registry.set_weight("unknown", 5).is_err());
+    }
+
+    #[tokio::test]
+    async fn total_score_sums_active_signal_weights() {
+        let scorer = ConfluenceScorer::new(70);
+        let mut candles = breakout_candles();
+        candles.push(candle(41, 102.8, 103.0, 101.1, 102.2, 120.0));
+
+        // Evaluated at 14:00 UTC once the retest candle has closed
+        let mut data = MultiTimeframeData::new("BTCUSDT".to_string(), BASE + 14 * 3_600_000);
+        data.insert(market_data(candles));
+
+        let result = scorer.calculate_score(&data, Timeframe::M15).await;
+        let expected: u8 = result.signals
+            .iter()
+            .filter(|s| s.is_active && s.direction != TrendDirection::Short)
+            .map(|s| s.weight)
+            .sum();
+
+        assert_eq!(result.direction, TrendDirection::Long);
+        assert_eq!(result.short_score, 0);
+        assert_eq!(result.total_score, expected);
+        assert_eq!(result.is_valid, result.total_score >= 70);
+        for name in ["Breakout Confirmado", "Retest Exitoso", "Volume Spike", "Sesión Alta Liquidez"] {
+            let signal = result.signals.iter().find(|s| s.name == name).unwrap();
+            assert!(signal.is_active, "{}: {}", name, signal.reason);
+        }
+    }
+
+    fn vote(direction: TrendDirection) -> Arc<dyn ConfluenceCheck> {
+        Arc::new(move |_: &ConfluenceContext, weight: u8| {
+            ConfluenceSignal::new("vote", weight, true, String::new()).with_direction(direction)
+        })
+    }
+
+    #[tokio::test]
+    async fn opposing_votes_above_threshold_block_the_setup() {
+        let mut registry = ConfluenceRegistry::new();
+        registry.register("long", 60, vote(TrendDirection::Long)).unwrap();
+        registry.register("short", 30, vote(TrendDirection::Short)).unwrap();
+        registry.register("neutral", 10, vote(TrendDirection::Neutral)).unwrap();
+        let data = MultiTimeframeData::new("BTCUSDT".to_string(), BASE);
+
+        let scorer = ConfluenceScorer::with_registry(70, registry.clone());
+        let result = scorer.calculate_score(&data, Timeframe::M15).await;
+        assert_eq!(result.direction, TrendDirection::Long);
+        assert_eq!((result.long_score, result.short_score, result.neutral_score), (60, 30, 10));
+        assert_eq!(result.total_score, 70);
+        assert!(!result.is_valid);
+
+        let tolerant = ConfluenceScorer::with_registry(70, registry.clone()).with_conflict_threshold(40);
+        assert!(tolerant.calculate_score(&data, Timeframe::M15).await.is_valid);
+
+        // A tie leaves no direction to trade
+        registry.set_weight("short", 60).unwrap();
+        let tied = ConfluenceScorer::with_registry(0, registry).with_conflict_threshold(100);
+        let result = tied.calculate_score(&data, Timeframe::M15).await;
+        assert_eq!(result.direction, TrendDirection::Neutral);
+        assert!(!result.is_valid);
+    }
+}
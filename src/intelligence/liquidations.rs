@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use crate::types::{Liquidation, TrendDirection};
+use crate::intelligence::confluence_registry::{ConfluenceCheck, ConfluenceContext};
+use crate::intelligence::confluence_scorer::ConfluenceSignal;
+
+// Rolling window a cascade is measured over and the history its baseline
+// comes from
+const CASCADE_WINDOW_MS: i64 = 5 * 60 * 1000;
+const HISTORY_MS: i64 = 60 * 60 * 1000;
+// A cascade is one side liquidated at this multiple of its usual pace...
+const CASCADE_BASELINE_MULTIPLE: f64 = 5.0;
+// ...making up most of the window, and above an absolute floor
+const CASCADE_MIN_SHARE: f64 = 0.7;
+pub const DEFAULT_MIN_CASCADE_USD: f64 = 1_000_000.0;
+pub const DEFAULT_COOLDOWN_MS: i64 = 30 * 60 * 1000;
+
+// Liquidated notional per side in the latest window, and the average per
+// window over the rest of the history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LiquidationWindow {
+    pub long_notional: f64,
+    pub short_notional: f64,
+    pub long_baseline: f64,
+    pub short_baseline: f64,
+    pub count: usize,
+}
+
+impl LiquidationWindow {
+    pub fn from_liquidations(liquidations: &[Liquidation], now: i64) -> Self {
+        let window_start = now - CASCADE_WINDOW_MS;
+        let history_start = now - HISTORY_MS;
+        let baseline_windows = ((HISTORY_MS - CASCADE_WINDOW_MS) / CASCADE_WINDOW_MS) as f64;
+
+        let mut window = Self::default();
+        for liquidation in liquidations.iter().filter(|l| l.timestamp > history_start && l.timestamp <= now) {
+            let notional = liquidation.notional();
+            let recent = liquidation.timestamp > window_start;
+
+            match (liquidation.side, recent) {
+                (TrendDirection::Long, true) => window.long_notional += notional,
+                (TrendDirection::Short, true) => window.short_notional += notional,
+                (TrendDirection::Long, false) => window.long_baseline += notional / baseline_windows,
+                (TrendDirection::Short, false) => window.short_baseline += notional / baseline_windows,
+                (TrendDirection::Neutral, _) => {}
+            }
+            if recent {
+                window.count += 1;
+            }
+        }
+
+        window
+    }
+
+    pub fn total(&self) -> f64 {
+        self.long_notional + self.short_notional
+    }
+
+    // Side being liquidated in a cascade, if any
+    pub fn cascade_side(&self, min_notional: f64) -> Option<TrendDirection> {
+        [
+            (TrendDirection::Long, self.long_notional, self.long_baseline),
+            (TrendDirection::Short, self.short_notional, self.short_baseline),
+        ]
+        .into_iter()
+        .find(|(_, notional, baseline)| {
+            *notional >= min_notional
+                && *notional >= baseline * CASCADE_BASELINE_MULTIPLE
+                && *notional >= self.total() * CASCADE_MIN_SHARE
+        })
+        .map(|(side, _, _)| side)
+    }
+
+    pub fn notional(&self, side: TrendDirection) -> (f64, f64) {
+        match side {
+            TrendDirection::Long => (self.long_notional, self.long_baseline),
+            _ => (self.short_notional, self.short_baseline),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationCascade {
+    pub symbol: String,
+    // Side of the positions being liquidated
+    pub side: TrendDirection,
+    pub notional: f64,
+    pub baseline: f64,
+    pub reason: String,
+    pub triggered_at: i64,
+    // Entries stay blocked until this time, pushed back while the cascade lasts
+    pub until: i64,
+}
+
+// Per-symbol rolling liquidation history fed by the exchange stream, and
+// the entry block that follows a cascade
+pub struct LiquidationTracker {
+    min_cascade_usd: f64,
+    cooldown_ms: i64,
+    liquidations: HashMap<String, VecDeque<Liquidation>>,
+    cascades: HashMap<String, LiquidationCascade>,
+}
+
+impl LiquidationTracker {
+    pub fn new() -> Self {
+        Self::with_thresholds(DEFAULT_MIN_CASCADE_USD, DEFAULT_COOLDOWN_MS)
+    }
+
+    pub fn with_thresholds(min_cascade_usd: f64, cooldown_ms: i64) -> Self {
+        Self {
+            min_cascade_usd,
+            cooldown_ms,
+            liquidations: HashMap::new(),
+            cascades: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, liquidation: Liquidation) {
+        self.liquidations
+            .entry(liquidation.symbol.clone())
+            .or_default()
+            .push_back(liquidation);
+    }
+
+    // Liquidations of the last hour, oldest first
+    pub fn recent(&self, symbol: &str) -> Vec<Liquidation> {
+        let mut recent: Vec<Liquidation> = self.liquidations
+            .get(symbol)
+            .map(|l| l.iter().cloned().collect())
+            .unwrap_or_default();
+        recent.sort_by_key(|l| l.timestamp);
+        recent
+    }
+
+    pub fn window(&self, symbol: &str, now: i64) -> LiquidationWindow {
+        LiquidationWindow::from_liquidations(&self.recent(symbol), now)
+    }
+
+    // Drops old liquidations and returns the cascade only when the symbol was
+    // not blocked before, so the caller can log and alert once per episode
+    pub fn update(&mut self, symbol: &str, now: i64) -> Option<LiquidationCascade> {
+        if let Some(liquidations) = self.liquidations.get_mut(symbol) {
+            liquidations.retain(|l| l.timestamp > now - HISTORY_MS);
+        }
+
+        let window = self.window(symbol, now);
+        let side = window.cascade_side(self.min_cascade_usd)?;
+        let (notional, baseline) = window.notional(side);
+        let until = now + self.cooldown_ms;
+
+        if let Some(cascade) = self.cascades.get_mut(symbol) {
+            if cascade.until > now {
+                cascade.until = cascade.until.max(until);
+                return None;
+            }
+        }
+
+        let cascade = LiquidationCascade {
+            symbol: symbol.to_string(),
+            side,
+            notional,
+            baseline,
+            reason: cascade_reason(side, notional, baseline),
+            triggered_at: now,
+            until,
+        };
+        self.cascades.insert(symbol.to_string(), cascade.clone());
+        Some(cascade)
+    }
+
+    pub fn blocked(&self, symbol: &str, now: i64) -> Option<&LiquidationCascade> {
+        self.cascades.get(symbol).filter(|c| c.until > now)
+    }
+}
+
+impl Default for LiquidationTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Registered at weight 0 like the order book checks. A cascade flushes one
+// side with forced market orders, so the vote goes against the flush: a long
+// liquidation cascade backs longs and conflicts with a short setup chasing it.
+// Takes the same threshold as the tracker's entry block so both agree on
+// what a cascade is.
+pub fn liquidation_cascade_check(min_cascade_usd: f64) -> impl ConfluenceCheck {
+    move |ctx: &ConfluenceContext, weight: u8| cascade_signal(ctx, weight, min_cascade_usd)
+}
+
+fn cascade_signal(ctx: &ConfluenceContext, weight: u8, min_cascade_usd: f64) -> ConfluenceSignal {
+    const NAME: &str = "Cascada de Liquidaciones";
+
+    let window = LiquidationWindow::from_liquidations(ctx.data.liquidations(), ctx.data.as_of);
+    let signal = match window.cascade_side(min_cascade_usd) {
+        Some(side) => {
+            let (notional, baseline) = window.notional(side);
+            ConfluenceSignal::new(NAME, weight, true, cascade_reason(side, notional, baseline))
+                .with_direction(side)
+        }
+        None => ConfluenceSignal::new(
+            NAME,
+            weight,
+            false,
+            format!(
+                "No cascade: {} liquidations, longs ${:.0} / shorts ${:.0} in the last {} min",
+                window.count,
+                window.long_notional,
+                window.short_notional,
+                CASCADE_WINDOW_MS / 60_000
+            ),
+        ),
+    };
+
+    signal
+        .with_metric("long_liquidated", window.long_notional)
+        .with_metric("short_liquidated", window.short_notional)
+}
+
+fn cascade_reason(side: TrendDirection, notional: f64, baseline: f64) -> String {
+    let pace = if baseline > 0.0 {
+        format!("{:.1}x the usual pace", notional / baseline)
+    } else {
+        "no liquidations before it".to_string()
+    };
+
+    format!(
+        "{:?} liquidation cascade: ${:.0} in {} min, {}",
+        side,
+        notional,
+        CASCADE_WINDOW_MS / 60_000,
+        pace
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    const MINUTE: i64 = 60 * 1000;
+    const NOW: i64 = 10 * HISTORY_MS;
+
+    fn liquidation(side: TrendDirection, timestamp: i64, usd: i64) -> Liquidation {
+        Liquidation {
+            symbol: "BTCUSDT".to_string(),
+            timestamp,
+            side,
+            price: Decimal::from(usd),
+            quantity: Decimal::ONE,
+        }
+    }
+
+    #[test]
+    fn window_splits_recent_liquidations_from_the_baseline() {
+        let liquidations = [
+            liquidation(TrendDirection::Long, NOW - HISTORY_MS, 9_000_000),
+            liquidation(TrendDirection::Long, NOW - 30 * MINUTE, 1_100_000),
+            liquidation(TrendDirection::Short, NOW - 10 * MINUTE, 2_200_000),
+            liquidation(TrendDirection::Short, NOW - CASCADE_WINDOW_MS, 1_100_000),
+            liquidation(TrendDirection::Short, NOW - 2 * MINUTE, 100_000),
+            liquidation(TrendDirection::Long, NOW - MINUTE, 600_000),
+            liquidation(TrendDirection::Long, NOW + 1, 9_000_000),
+        ];
+        let window = LiquidationWindow::from_liquidations(&liquidations, NOW);
+
+        // Eleven 5 min windows make up the rest of the hour
+        assert_eq!(window.long_notional, 600_000.0);
+        assert_eq!(window.short_notional, 100_000.0);
+        assert_eq!(window.long_baseline, 100_000.0);
+        assert_eq!(window.short_baseline, 300_000.0);
+        assert_eq!(window.count, 2);
+    }
+
+    #[test]
+    fn cascade_needs_the_floor_the_baseline_multiple_and_the_share() {
+        let window = LiquidationWindow {
+            long_notional: 1_200_000.0,
+            short_notional: 300_000.0,
+            long_baseline: 200_000.0,
+            short_baseline: 0.0,
+            count: 10,
+        };
+        assert_eq!(window.cascade_side(1_000_000.0), Some(TrendDirection::Long));
+        assert_eq!(window.cascade_side(2_000_000.0), None);
+
+        // 4x its usual pace
+        assert_eq!(LiquidationWindow { long_baseline: 300_000.0, ..window }.cascade_side(1_000_000.0), None);
+        // 67% of the window
+        assert_eq!(LiquidationWindow { short_notional: 600_000.0, ..window }.cascade_side(1_000_000.0), None);
+
+        let shorts = LiquidationWindow {
+            long_notional: 0.0,
+            short_notional: 1_500_000.0,
+            long_baseline: 0.0,
+            short_baseline: 0.0,
+            count: 3,
+        };
+        assert_eq!(shorts.cascade_side(1_000_000.0), Some(TrendDirection::Short));
+    }
+
+    #[test]
+    fn tracker_reports_each_episode_once_and_blocks_until_the_cooldown() {
+        let cooldown = 30 * MINUTE;
+        let mut tracker = LiquidationTracker::with_thresholds(1_000_000.0, cooldown);
+
+        tracker.record(liquidation(TrendDirection::Long, NOW, 2_000_000));
+        let cascade = tracker.update("BTCUSDT", NOW).unwrap();
+        assert_eq!(cascade.side, TrendDirection::Long);
+        assert_eq!(cascade.until, NOW + cooldown);
+        assert!(tracker.blocked("ETHUSDT", NOW).is_none());
+
+        // Still cascading: no new event, the block is pushed back
+        tracker.record(liquidation(TrendDirection::Long, NOW + 4 * MINUTE, 2_000_000));
+        assert!(tracker.update("BTCUSDT", NOW + 4 * MINUTE).is_none());
+        assert_eq!(tracker.blocked("BTCUSDT", NOW + 31 * MINUTE).unwrap().until, NOW + 34 * MINUTE);
+
+        // Quiet after the cooldown
+        assert!(tracker.update("BTCUSDT", NOW + 40 * MINUTE).is_none());
+        assert!(tracker.blocked("BTCUSDT", NOW + 40 * MINUTE).is_none());
+
+        // A later flush is a new episode
+        tracker.record(liquidation(TrendDirection::Long, NOW + 45 * MINUTE, 2_000_000));
+        let again = tracker.update("BTCUSDT", NOW + 45 * MINUTE).unwrap();
+        assert_eq!(again.triggered_at, NOW + 45 * MINUTE);
+        assert!(tracker.blocked("BTCUSDT", NOW + 46 * MINUTE).is_some());
+    }
+}
@@ -1,6 +1,29 @@
-use crate::types::MarketData;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use crate::types::{MarketData, to_f64};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Trending above ADX 25, and stays trending until ADX drops below 20
+const ADX_TRENDING_ENTER: f64 = 25.0;
+const ADX_TRENDING_EXIT: f64 = 20.0;
+// ATR / Bollinger bandwidth percentile against their own recent history
+const VOLATILE_PERCENTILE_ENTER: f64 = 90.0;
+const VOLATILE_PERCENTILE_EXIT: f64 = 75.0;
+const PERCENTILE_LOOKBACK: usize = 100;
+// Recent volume against its longer average
+const VOLUME_FAST_PERIOD: usize = 10;
+const VOLUME_SLOW_PERIOD: usize = 50;
+const LOW_VOLUME_ENTER: f64 = 0.5;
+const LOW_VOLUME_EXIT: f64 = 0.7;
+// Spread limit of the strategy is 0.05%
+const MAX_SPREAD_BPS_ENTER: f64 = 5.0;
+const MAX_SPREAD_BPS_EXIT: f64 = 4.0;
+
+// Changes kept for logging and reports
+const HISTORY_LIMIT: usize = 200;
+const DEFAULT_MIN_DWELL_MS: i64 = 4 * 60 * 60 * 1000;
+const DEFAULT_CONFIRM_BARS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MarketRegime {
     Trending,
     Ranging,
@@ -8,21 +31,200 @@ pub enum MarketRegime {
     LowLiquidity,
 }
 
-pub struct MarketRegimeDetector;
+impl MarketRegime {
+    // Regimes we stand aside in regardless of strategy
+    pub fn is_hostile(&self) -> bool {
+        matches!(self, MarketRegime::Volatile | MarketRegime::LowLiquidity)
+    }
+}
+
+// Raw classification of one bar and the inputs behind it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeReading {
+    pub regime: MarketRegime,
+    // Close time of the bar the reading was taken on
+    pub timestamp: i64,
+    pub adx: f64,
+    pub atr_percentile: f64,
+    pub bandwidth_percentile: f64,
+    pub volume_ratio: f64,
+    pub spread_bps: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeChange {
+    pub symbol: String,
+    // None for the first reading of a symbol
+    pub from: Option<MarketRegime>,
+    pub to: MarketRegime,
+    pub timestamp: i64,
+    pub reading: RegimeReading,
+}
+
+struct RegimeState {
+    current: MarketRegime,
+    since: i64,
+    last_bar: i64,
+    // Regime seen on consecutive bars that differs from `current`
+    candidate: Option<(MarketRegime, usize)>,
+}
+
+// Classifies each symbol's regime and only switches once a new regime has
+// held for `confirm_bars` closed bars and the current one has lasted
+// `min_dwell_ms`. Switches into Volatile/LowLiquidity skip the dwell time.
+pub struct MarketRegimeDetector {
+    min_dwell_ms: i64,
+    confirm_bars: usize,
+    states: HashMap<String, RegimeState>,
+    history: VecDeque<RegimeChange>,
+}
 
 impl MarketRegimeDetector {
     pub fn new() -> Self {
-        Self
+        Self::with_dwell(DEFAULT_MIN_DWELL_MS, DEFAULT_CONFIRM_BARS)
+    }
+
+    pub fn with_dwell(min_dwell_ms: i64, confirm_bars: usize) -> Self {
+        Self {
+            min_dwell_ms,
+            confirm_bars: confirm_bars.max(1),
+            states: HashMap::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    // Confirmed regime of the symbol, or a fresh classification if the
+    // detector has not tracked it yet
+    pub fn detect_regime(&self, data: &MarketData) -> MarketRegime {
+        match self.states.get(&data.symbol) {
+            Some(state) => state.current,
+            None => self.classify(data, None, None).regime,
+        }
     }
 
-    pub fn detect_regime(&self, _data: &MarketData) -> MarketRegime {
-        // TODO: Implement regime detection using ADX, ATR, etc.
-        MarketRegime::Ranging
+    pub fn current(&self, symbol: &str) -> Option<MarketRegime> {
+        self.states.get(symbol).map(|s| s.current)
+    }
+
+    // Oldest first
+    pub fn history(&self) -> impl Iterator<Item = &RegimeChange> {
+        self.history.iter()
     }
 
     pub fn is_safe_to_trade(&self, regime: MarketRegime) -> bool {
         matches!(regime, MarketRegime::Trending)
     }
+
+    // Feed the latest closed bars of a symbol. Each bar is only counted once,
+    // so calling this every cycle on the same data is harmless.
+    pub fn update(&mut self, data: &MarketData, spread_bps: Option<f64>) -> Option<RegimeChange> {
+        let bar_time = data.last_candle()?.close_time(data.timeframe);
+
+        let Some(state) = self.states.get(&data.symbol) else {
+            let reading = self.classify(data, spread_bps, None);
+            self.states.insert(data.symbol.clone(), RegimeState {
+                current: reading.regime,
+                since: bar_time,
+                last_bar: bar_time,
+                candidate: None,
+            });
+            return Some(self.record(&data.symbol, None, reading));
+        };
+
+        if bar_time <= state.last_bar {
+            return None;
+        }
+
+        let reading = self.classify(data, spread_bps, Some(state.current));
+        let (min_dwell_ms, confirm_bars) = (self.min_dwell_ms, self.confirm_bars);
+        let state = self.states.get_mut(&data.symbol)?;
+        state.last_bar = bar_time;
+
+        if reading.regime == state.current {
+            state.candidate = None;
+            return None;
+        }
+
+        let count = match state.candidate {
+            Some((regime, count)) if regime == reading.regime => count + 1,
+            _ => 1,
+        };
+        state.candidate = Some((reading.regime, count));
+
+        let dwelled = bar_time - state.since >= min_dwell_ms || reading.regime.is_hostile();
+        if count < confirm_bars || !dwelled {
+            return None;
+        }
+
+        let from = state.current;
+        state.current = reading.regime;
+        state.since = bar_time;
+        state.candidate = None;
+
+        Some(self.record(&data.symbol, Some(from), reading))
+    }
+
+    // Thresholds loosen in favour of `current` so borderline readings keep it
+    pub fn classify(&self, data: &MarketData, spread_bps: Option<f64>, current: Option<MarketRegime>) -> RegimeReading {
+        let is_current = |regime: MarketRegime| current == Some(regime);
+
+        let adx = data.adx(14);
+        let atr_percentile = last_percentile(&data.atr_series(14));
+        let bandwidth_percentile = last_percentile(&data.bollinger_bandwidth_series(20, 2.0));
+        let volume_ratio = volume_ratio(data);
+
+        let (min_volume, max_spread) = if is_current(MarketRegime::LowLiquidity) {
+            (LOW_VOLUME_EXIT, MAX_SPREAD_BPS_EXIT)
+        } else {
+            (LOW_VOLUME_ENTER, MAX_SPREAD_BPS_ENTER)
+        };
+        let volatile_percentile = if is_current(MarketRegime::Volatile) {
+            VOLATILE_PERCENTILE_EXIT
+        } else {
+            VOLATILE_PERCENTILE_ENTER
+        };
+        let trending_adx = if is_current(MarketRegime::Trending) {
+            ADX_TRENDING_EXIT
+        } else {
+            ADX_TRENDING_ENTER
+        };
+
+        let regime = if volume_ratio < min_volume || spread_bps.is_some_and(|s| s > max_spread) {
+            MarketRegime::LowLiquidity
+        } else if atr_percentile > volatile_percentile || bandwidth_percentile > volatile_percentile {
+            MarketRegime::Volatile
+        } else if adx > trending_adx {
+            MarketRegime::Trending
+        } else {
+            MarketRegime::Ranging
+        };
+
+        RegimeReading {
+            regime,
+            timestamp: data.last_candle().map(|c| c.close_time(data.timeframe)).unwrap_or(0),
+            adx,
+            atr_percentile,
+            bandwidth_percentile,
+            volume_ratio,
+            spread_bps,
+        }
+    }
+
+    fn record(&mut self, symbol: &str, from: Option<MarketRegime>, reading: RegimeReading) -> RegimeChange {
+        let change = RegimeChange {
+            symbol: symbol.to_string(),
+            from,
+            to: reading.regime,
+            timestamp: reading.timestamp,
+            reading,
+        };
+
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(change.clone());
+        change
+    }
 }
 
 impl Default for MarketRegimeDetector {
@@ -30,3 +232,102 @@ impl Default for MarketRegimeDetector {
         Self::new()
     }
 }
+
+// Share of the last PERCENTILE_LOOKBACK values below the latest one, 0-100
+fn last_percentile(series: &[f64]) -> f64 {
+    let start = series.len().saturating_sub(PERCENTILE_LOOKBACK);
+    let window = &series[start..];
+    let Some((&last, previous)) = window.split_last() else {
+        return 0.0;
+    };
+    if previous.is_empty() {
+        return 0.0;
+    }
+
+    previous.iter().filter(|v| **v < last).count() as f64 / previous.len() as f64 * 100.0
+}
+
+fn volume_ratio(data: &MarketData) -> f64 {
+    if data.candles.len() < VOLUME_SLOW_PERIOD {
+        return 1.0;
+    }
+
+    let slow = to_f64(data.volume_sma(VOLUME_SLOW_PERIOD));
+    let fast = to_f64(data.volume_sma(VOLUME_FAST_PERIOD));
+    if slow == 0.0 { 1.0 } else { fast / slow }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Candle, Timeframe};
+    use rust_decimal::Decimal;
+
+    const M15: i64 = 15 * 60_000;
+
+    // Flat chop: constant range, volume and no directional movement
+    fn ranging(count: usize) -> MarketData {
+        let dec = |value: f64| Decimal::try_from(value).unwrap();
+        let candles = (0..count)
+            .map(|i| {
+                let (open, close) = if i % 2 == 0 { (100.0, 100.5) } else { (100.5, 100.0) };
+                Candle::new(i as i64 * M15, dec(open), dec(101.0), dec(99.5), dec(close), dec(100.0))
+            })
+            .collect();
+        MarketData {
+            symbol: "ETHUSDT".to_string(),
+            candles,
+            timeframe: Timeframe::M15,
+        }
+    }
+
+    #[test]
+    fn spread_thresholds_have_hysteresis() {
+        let detector = MarketRegimeDetector::new();
+        let data = ranging(120);
+        let classify = |spread: f64, current| detector.classify(&data, Some(spread), current).regime;
+
+        assert_eq!(classify(5.0, None), MarketRegime::Ranging);
+        assert_eq!(classify(5.01, None), MarketRegime::LowLiquidity);
+        // Once illiquid it takes a spread back under 4 bps to leave
+        assert_eq!(classify(4.01, Some(MarketRegime::LowLiquidity)), MarketRegime::LowLiquidity);
+        assert_eq!(classify(4.0, Some(MarketRegime::LowLiquidity)), MarketRegime::Ranging);
+    }
+
+    #[test]
+    fn switches_need_confirmation_and_dwell_unless_hostile() {
+        let mut detector = MarketRegimeDetector::with_dwell(60 * 60_000, 2);
+
+        let first = detector.update(&ranging(120), None).unwrap();
+        assert_eq!((first.from, first.to), (None, MarketRegime::Ranging));
+        assert!(detector.update(&ranging(120), Some(6.0)).is_none(), "same bar counted twice");
+
+        // Hostile regimes switch after the confirmation bars, without dwell
+        assert!(detector.update(&ranging(121), Some(6.0)).is_none());
+        let change = detector.update(&ranging(122), Some(6.0)).unwrap();
+        assert_eq!((change.from, change.to), (Some(MarketRegime::Ranging), MarketRegime::LowLiquidity));
+
+        // Leaving needs 2 confirming bars and an hour in LowLiquidity
+        assert!(detector.update(&ranging(123), Some(4.5)).is_none());
+        assert!(detector.update(&ranging(124), None).is_none());
+        assert!(detector.update(&ranging(125), None).is_none());
+        assert_eq!(detector.current("ETHUSDT"), Some(MarketRegime::LowLiquidity));
+        let change = detector.update(&ranging(126), None).unwrap();
+        assert_eq!(change.to, MarketRegime::Ranging);
+        assert_eq!(detector.history().count(), 3);
+    }
+
+    #[test]
+    fn percentile_counts_strictly_lower_values() {
+        assert_eq!(last_percentile(&[1.0, 2.0, 3.0, 4.0]), 100.0);
+        assert_eq!(last_percentile(&[4.0, 3.0, 2.0, 1.0]), 0.0);
+        assert_eq!(last_percentile(&[1.0, 2.0, 2.0, 3.0, 2.0]), 25.0);
+        assert_eq!(last_percentile(&[5.0]), 0.0);
+
+        // Only the last PERCENTILE_LOOKBACK values count
+        let mut series = vec![0.0; 50];
+        series.extend(vec![10.0; PERCENTILE_LOOKBACK - 1]);
+        series.push(5.0);
+        assert_eq!(last_percentile(&series), 0.0);
+    }
+}
@@ -1,7 +1,29 @@
 pub mod confluence_scorer;
+pub mod confluence_registry;
 pub mod asset_ranker;
 pub mod market_regime;
+pub mod candle_patterns;
+pub mod volume_profile;
+pub mod order_flow;
+pub mod volatility_guard;
+pub mod order_book;
+pub mod liquidations;
+pub mod session_calendar;
+pub mod btc_leader;
+pub mod score_calibration;
+pub mod setup_rules;
 
-pub use confluence_scorer::{ConfluenceScorer, ConfluenceResult, ConfluenceSignal};
+pub use confluence_scorer::{ConfluenceScorer, ConfluenceResult, ConfluenceSignal, Breakout};
+pub use confluence_registry::{ConfluenceCheck, ConfluenceContext, ConfluenceRegistry};
 pub use asset_ranker::{AssetRanker, AssetScore};
-pub use market_regime::MarketRegimeDetector;
+pub use market_regime::{MarketRegimeDetector, MarketRegime, RegimeChange, RegimeReading};
+pub use candle_patterns::{CandlePatternRecognizer, CandlePattern, PatternMatch};
+pub use volume_profile::{VolumeProfile, VolumeBin, Vwap, KeyLevel};
+pub use order_flow::OrderFlow;
+pub use volatility_guard::{VolatilityGuard, VolatilityBlock, VolatilityTrigger};
+pub use order_book::{BookSide, LiquidityWall};
+pub use liquidations::{LiquidationTracker, LiquidationCascade, LiquidationWindow};
+pub use session_calendar::{SessionCalendar, Session, SessionLiquidity, SessionState, NoTradeWindow};
+pub use btc_leader::{BtcLeaderFilter, LeaderState, LeaderVerdict};
+pub use score_calibration::{ScoreCalibration, CalibrationBin, CalibrationMethod, ScoredOutcome, ScoreJournal};
+pub use setup_rules::{RuleSetup, RuleCondition, SetupDefinition};
@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use crate::types::{BookLevel, OrderBook, TrendDirection, to_f64};
+use crate::intelligence::confluence_registry::ConfluenceContext;
+use crate::intelligence::confluence_scorer::ConfluenceSignal;
+
+// Levels per side the bid/ask imbalance is measured over
+pub const IMBALANCE_LEVELS: usize = 10;
+// Notional imbalance, in [-1, 1], for the book to lean one way
+const MIN_IMBALANCE: f64 = 0.25;
+// A level is a wall at this multiple of the median level of its side
+const WALL_MULTIPLE: f64 = 5.0;
+// Only walls this close to mid count as relevant to an entry or stop
+const WALL_RANGE_BPS: f64 = 50.0;
+// A wall counts as pulled once its level shrinks below this share of it
+const PULLED_SHARE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityWall {
+    pub side: BookSide,
+    pub price: f64,
+    pub quantity: f64,
+    pub notional: f64,
+    // Size relative to the median level of its side
+    pub size_multiple: f64,
+}
+
+impl LiquidityWall {
+    pub fn distance_bps(&self, price: f64) -> f64 {
+        if price <= 0.0 {
+            return f64::MAX;
+        }
+        (self.price - price).abs() / price * 10_000.0
+    }
+}
+
+// (bid - ask) / (bid + ask) notional over the best `levels` levels
+pub fn imbalance(book: &OrderBook, levels: usize) -> f64 {
+    let bid = side_notional(&book.bids, levels);
+    let ask = side_notional(&book.asks, levels);
+    if bid + ask > 0.0 { (bid - ask) / (bid + ask) } else { 0.0 }
+}
+
+// Outsized levels within WALL_RANGE_BPS of mid, nearest first
+pub fn find_walls(book: &OrderBook) -> Vec<LiquidityWall> {
+    match book.mid() {
+        Some(mid) => walls_near(book, to_f64(mid), WALL_RANGE_BPS),
+        None => Vec::new(),
+    }
+}
+
+// Walls within `range_bps` of `price`, e.g. around an entry or a stop
+pub fn walls_near(book: &OrderBook, price: f64, range_bps: f64) -> Vec<LiquidityWall> {
+    let mut walls: Vec<LiquidityWall> = side_walls(&book.bids, BookSide::Bid)
+        .into_iter()
+        .chain(side_walls(&book.asks, BookSide::Ask))
+        .filter(|w| w.distance_bps(price) <= range_bps)
+        .collect();
+    walls.sort_by(|a, b| a.distance_bps(price).total_cmp(&b.distance_bps(price)));
+    walls
+}
+
+// Walls of `previous` that were pulled by `current` while price stayed away
+// from them. A wall that got traded through is a fill, not a spoof.
+pub fn pulled_walls(previous: &OrderBook, current: &OrderBook) -> Vec<LiquidityWall> {
+    let (Some(best_bid), Some(best_ask)) = (current.best_bid().map(to_f64), current.best_ask().map(to_f64)) else {
+        return Vec::new();
+    };
+
+    find_walls(previous)
+        .into_iter()
+        .filter(|wall| {
+            let (levels, untouched) = match wall.side {
+                BookSide::Bid => (&current.bids, best_bid > wall.price),
+                BookSide::Ask => (&current.asks, best_ask < wall.price),
+            };
+
+            // Level must still be inside the visible depth to tell it was pulled
+            let visible = levels.last().is_some_and(|l| match wall.side {
+                BookSide::Bid => to_f64(l.price) <= wall.price,
+                BookSide::Ask => to_f64(l.price) >= wall.price,
+            });
+            let remaining = levels
+                .iter()
+                .find(|l| to_f64(l.price) == wall.price)
+                .map_or(0.0, |l| to_f64(l.quantity));
+
+            untouched && visible && remaining < wall.quantity * PULLED_SHARE
+        })
+        .collect()
+}
+
+// Registered in `default_registry` at weight 0; give them a weight through
+// CONFLUENCE_WEIGHTS to let the book move the score
+pub fn book_imbalance_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Desequilibrio Order Book";
+
+    let Some(book) = ctx.data.order_book() else {
+        return ConfluenceSignal::new(NAME, weight, false, "No order book snapshot".to_string());
+    };
+
+    let value = imbalance(book, IMBALANCE_LEVELS);
+    let direction = if value >= MIN_IMBALANCE {
+        TrendDirection::Long
+    } else if value <= -MIN_IMBALANCE {
+        TrendDirection::Short
+    } else {
+        TrendDirection::Neutral
+    };
+    let is_active = direction != TrendDirection::Neutral;
+
+    ConfluenceSignal::new(
+        NAME,
+        weight,
+        is_active,
+        format!(
+            "Top {} levels imbalance {:+.2} ({})",
+            IMBALANCE_LEVELS,
+            value,
+            if is_active { format!("{:?} leaning", direction) } else { format!("within ±{}", MIN_IMBALANCE) }
+        ),
+    )
+    .with_direction(direction)
+    .with_metric("imbalance", value)
+}
+
+// Bid walls below price support a long, ask walls above it cap one; votes
+// for the side with more wall notional near price
+pub fn liquidity_walls_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Muros de Liquidez";
+
+    let Some(book) = ctx.data.order_book() else {
+        return ConfluenceSignal::new(NAME, weight, false, "No order book snapshot".to_string());
+    };
+
+    let walls = find_walls(book);
+    let notional = |side: BookSide| walls.iter().filter(|w| w.side == side).map(|w| w.notional).sum::<f64>();
+    let (bid_walls, ask_walls) = (notional(BookSide::Bid), notional(BookSide::Ask));
+
+    let direction = if bid_walls > ask_walls {
+        TrendDirection::Long
+    } else if ask_walls > bid_walls {
+        TrendDirection::Short
+    } else {
+        TrendDirection::Neutral
+    };
+    let is_active = direction != TrendDirection::Neutral;
+
+    let reason = match walls.first() {
+        Some(nearest) => format!(
+            "{} walls within {} bps (bids ${:.0}, asks ${:.0}), nearest {:?} {:.0}x at {}",
+            walls.len(),
+            WALL_RANGE_BPS,
+            bid_walls,
+            ask_walls,
+            nearest.side,
+            nearest.size_multiple,
+            nearest.price
+        ),
+        None => format!("No level over {}x the median within {} bps", WALL_MULTIPLE, WALL_RANGE_BPS),
+    };
+
+    ConfluenceSignal::new(NAME, weight, is_active, reason)
+        .with_direction(direction)
+        .with_metric("bid_wall_notional", bid_walls)
+        .with_metric("ask_wall_notional", ask_walls)
+}
+
+// Pulled bid walls were fake support, pulled ask walls fake resistance, so
+// the signal votes against the side that was spoofed
+pub fn spoofing_check(ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Spoofing Detectado";
+
+    let books = ctx.data.order_books();
+    if books.len() < 2 {
+        return ConfluenceSignal::new(NAME, weight, false, "Need two order book snapshots".to_string());
+    }
+
+    let pulled: Vec<LiquidityWall> = books
+        .windows(2)
+        .flat_map(|pair| pulled_walls(&pair[0], &pair[1]))
+        .collect();
+    let notional = |side: BookSide| pulled.iter().filter(|w| w.side == side).map(|w| w.notional).sum::<f64>();
+    let (bids_pulled, asks_pulled) = (notional(BookSide::Bid), notional(BookSide::Ask));
+
+    let direction = if asks_pulled > bids_pulled {
+        TrendDirection::Long
+    } else if bids_pulled > asks_pulled {
+        TrendDirection::Short
+    } else {
+        TrendDirection::Neutral
+    };
+    let is_active = direction != TrendDirection::Neutral;
+
+    let reason = if pulled.is_empty() {
+        format!("No wall pulled over the last {} snapshots", books.len())
+    } else {
+        format!(
+            "{} walls pulled before price reached them (bids ${:.0}, asks ${:.0})",
+            pulled.len(),
+            bids_pulled,
+            asks_pulled
+        )
+    };
+
+    ConfluenceSignal::new(NAME, weight, is_active, reason)
+        .with_direction(direction)
+        .with_metric("bids_pulled", bids_pulled)
+        .with_metric("asks_pulled", asks_pulled)
+}
+
+fn side_walls(levels: &[BookLevel], side: BookSide) -> Vec<LiquidityWall> {
+    let mut sizes: Vec<f64> = levels.iter().map(|l| to_f64(l.quantity)).collect();
+    if sizes.len() < 3 {
+        return Vec::new();
+    }
+    sizes.sort_by(|a, b| a.total_cmp(b));
+    let median = sizes[sizes.len() / 2];
+    if median <= 0.0 {
+        return Vec::new();
+    }
+
+    levels
+        .iter()
+        .filter(|l| to_f64(l.quantity) >= median * WALL_MULTIPLE)
+        .map(|l| {
+            let (price, quantity) = (to_f64(l.price), to_f64(l.quantity));
+            LiquidityWall {
+                side,
+                price,
+                quantity,
+                notional: price * quantity,
+                size_multiple: quantity / median,
+            }
+        })
+        .collect()
+}
+
+fn side_notional(levels: &[BookLevel], count: usize) -> f64 {
+    levels.iter().take(count).map(|l| to_f64(l.price * l.quantity)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    // Ten levels a side, 0.1 apart with a 0.2 spread, 1 unit each unless
+    // overridden by `sizes`
+    fn book(best_bid: f64, sizes: &[(f64, f64)]) -> OrderBook {
+        let level = |price: f64| {
+            let quantity = sizes
+                .iter()
+                .find(|(p, _)| (p - price).abs() < 1e-9)
+                .map_or(1.0, |(_, q)| *q);
+            BookLevel {
+                price: Decimal::try_from(price).unwrap().round_dp(2),
+                quantity: Decimal::try_from(quantity).unwrap(),
+            }
+        };
+        OrderBook {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: 0,
+            bids: (0..10).map(|i| level(best_bid - 0.1 * i as f64)).collect(),
+            asks: (0..10)
+                .map(|i| level(best_bid + 0.2 + 0.1 * i as f64))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn imbalance_compares_bid_and_ask_notional() {
+        let book = OrderBook {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: 0,
+            bids: vec![BookLevel {
+                price: Decimal::from(99),
+                quantity: Decimal::from(3),
+            }],
+            asks: vec![
+                BookLevel {
+                    price: Decimal::from(101),
+                    quantity: Decimal::ONE,
+                },
+                BookLevel {
+                    price: Decimal::from(102),
+                    quantity: Decimal::from(50),
+                },
+            ],
+        };
+
+        // (297 - 101) / (297 + 101) over the best level only
+        assert!((imbalance(&book, 1) - 196.0 / 398.0).abs() < 1e-12);
+        assert!(imbalance(&book, 2) < -0.5);
+        assert_eq!(
+            imbalance(
+                &OrderBook {
+                    bids: Vec::new(),
+                    asks: Vec::new(),
+                    ..book
+                },
+                10
+            ),
+            0.0
+        );
+    }
+
+    #[test]
+    fn walls_are_outsized_levels_near_mid() {
+        // Mid is 100.0: 99.7 is 30 bps away, 100.3 is 30 bps, 99.0 is 100 bps
+        let book = book(99.9, &[(99.7, 10.0), (100.3, 6.0), (100.5, 4.0)]);
+        let walls = find_walls(&book);
+
+        let found: Vec<(BookSide, f64, f64)> = walls
+            .iter()
+            .map(|w| (w.side, w.price, w.size_multiple))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(BookSide::Bid, 99.7, 10.0), (BookSide::Ask, 100.3, 6.0)]
+        );
+        assert!((walls[0].notional - 997.0).abs() < 1e-9);
+
+        assert!(find_walls(&self::book(99.9, &[(99.0, 10.0)])).is_empty());
+    }
+
+    #[test]
+    fn pulled_walls_are_spoofs_only_if_price_never_reached_them() {
+        let previous = book(99.9, &[(99.7, 10.0)]);
+
+        // Shrunk to 1 with price still above it
+        let pulled = pulled_walls(&previous, &book(99.9, &[]));
+        assert_eq!(pulled.len(), 1);
+        assert_eq!((pulled[0].side, pulled[0].price), (BookSide::Bid, 99.7));
+
+        // Half of it is still there
+        assert!(pulled_walls(&previous, &book(99.9, &[(99.7, 5.0)])).is_empty());
+
+        // Price traded down through it: a fill, not a spoof
+        assert!(pulled_walls(&previous, &book(99.6, &[])).is_empty());
+
+        // Current depth no longer reaches the wall's level
+        let mut shallow = book(99.9, &[]);
+        shallow.bids.truncate(2);
+        assert!(pulled_walls(&previous, &shallow).is_empty());
+    }
+}
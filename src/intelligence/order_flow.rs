@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use crate::types::{Candle, TrendDirection, to_f64};
+
+// Minimum buy/sell imbalance on the breakout candle, in [-1, 1]
+const BREAKOUT_MIN_IMBALANCE: f64 = 0.2;
+
+// Per-candle buy/sell pressure. Uses the exchange's taker buy volume when the
+// candle carries it, otherwise estimates it from where the candle closed
+// within its range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderFlow {
+    pub timestamps: Vec<i64>,
+    // Taker buy minus taker sell volume
+    pub deltas: Vec<f64>,
+    pub cumulative_delta: Vec<f64>,
+    // (buy - sell) / (buy + sell)
+    pub imbalance: Vec<f64>,
+    // True if any candle had to fall back to the close-location estimate
+    pub estimated: bool,
+}
+
+impl OrderFlow {
+    pub fn from_candles(candles: &[Candle]) -> Self {
+        let mut flow = Self {
+            timestamps: Vec::with_capacity(candles.len()),
+            deltas: Vec::with_capacity(candles.len()),
+            cumulative_delta: Vec::with_capacity(candles.len()),
+            imbalance: Vec::with_capacity(candles.len()),
+            estimated: false,
+        };
+
+        let mut cumulative = 0.0;
+        for candle in candles {
+            let (buy, sell, estimated) = buy_sell_volume(candle);
+            let delta = buy - sell;
+            cumulative += delta;
+
+            flow.timestamps.push(candle.timestamp);
+            flow.deltas.push(delta);
+            flow.cumulative_delta.push(cumulative);
+            flow.imbalance.push(if buy + sell > 0.0 { delta / (buy + sell) } else { 0.0 });
+            flow.estimated |= estimated;
+        }
+
+        flow
+    }
+
+    pub fn cvd(&self) -> f64 {
+        self.cumulative_delta.last().copied().unwrap_or(0.0)
+    }
+
+    // Change in cumulative delta over the last `lookback` candles
+    pub fn cvd_change(&self, lookback: usize) -> f64 {
+        let len = self.cumulative_delta.len();
+        if len == 0 {
+            return 0.0;
+        }
+
+        let start = len.saturating_sub(lookback + 1);
+        self.cumulative_delta[len - 1] - self.cumulative_delta[start]
+    }
+
+    pub fn average_imbalance(&self, lookback: usize) -> f64 {
+        let recent: Vec<f64> = self.imbalance.iter().rev().take(lookback).copied().collect();
+        if recent.is_empty() {
+            return 0.0;
+        }
+        recent.iter().sum::<f64>() / recent.len() as f64
+    }
+
+    // A breakout is backed by flow when aggressors pushed in its direction on
+    // the breakout candle and delta accumulated that way over the lookback
+    pub fn confirms_breakout(&self, direction: TrendDirection, lookback: usize) -> bool {
+        let Some(&last_imbalance) = self.imbalance.last() else {
+            return false;
+        };
+        let change = self.cvd_change(lookback);
+
+        match direction {
+            TrendDirection::Long => last_imbalance >= BREAKOUT_MIN_IMBALANCE && change > 0.0,
+            TrendDirection::Short => last_imbalance <= -BREAKOUT_MIN_IMBALANCE && change < 0.0,
+            TrendDirection::Neutral => false,
+        }
+    }
+}
+
+// (buy, sell, estimated)
+fn buy_sell_volume(candle: &Candle) -> (f64, f64, bool) {
+    let volume = to_f64(candle.volume);
+
+    if let Some(taker_buy) = candle.taker_buy_base_volume {
+        let buy = to_f64(taker_buy).min(volume);
+        return (buy, volume - buy, false);
+    }
+
+    let range = to_f64(candle.range());
+    if range == 0.0 {
+        return (volume / 2.0, volume / 2.0, true);
+    }
+
+    // Close at the high counts as all buying, at the low as all selling
+    let buy_share = to_f64(candle.close - candle.low) / range;
+    (volume * buy_share, volume * (1.0 - buy_share), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::try_from(value).unwrap()
+    }
+
+    fn candle(index: i64, low: f64, high: f64, close: f64, volume: f64, taker_buy: Option<f64>) -> Candle {
+        let mut candle = Candle::new(index, dec(low), dec(high), dec(low), dec(close), dec(volume));
+        candle.taker_buy_base_volume = taker_buy.map(dec);
+        candle
+    }
+
+    // Taker buys of 7, 2 and 12 (capped at the volume) out of 10
+    fn reported_flow() -> OrderFlow {
+        OrderFlow::from_candles(&[
+            candle(0, 100.0, 101.0, 100.5, 10.0, Some(7.0)),
+            candle(1, 100.0, 101.0, 100.5, 10.0, Some(2.0)),
+            candle(2, 100.0, 101.0, 100.5, 10.0, Some(12.0)),
+        ])
+    }
+
+    #[test]
+    fn flow_uses_reported_taker_volume() {
+        let flow = reported_flow();
+
+        assert_eq!(flow.timestamps, vec![0, 1, 2]);
+        assert_eq!(flow.deltas, vec![4.0, -6.0, 10.0]);
+        assert_eq!(flow.cumulative_delta, vec![4.0, -2.0, 8.0]);
+        assert_eq!(flow.imbalance, vec![0.4, -0.6, 1.0]);
+        assert!(!flow.estimated);
+    }
+
+    #[test]
+    fn flow_estimates_from_the_close_without_taker_volume() {
+        let flow = OrderFlow::from_candles(&[
+            candle(0, 100.0, 110.0, 107.5, 8.0, None),
+            candle(1, 100.0, 100.0, 100.0, 8.0, None),
+            candle(2, 100.0, 110.0, 100.0, 0.0, None),
+            candle(3, 100.0, 110.0, 105.0, 10.0, Some(1.0)),
+        ]);
+
+        // Closed three quarters up the range: 6 bought, 2 sold
+        assert_eq!(flow.deltas, vec![4.0, 0.0, 0.0, -8.0]);
+        assert_eq!(flow.imbalance, vec![0.5, 0.0, 0.0, -0.8]);
+        assert!(flow.estimated);
+    }
+
+    #[test]
+    fn cvd_and_averages_over_the_lookback() {
+        let flow = reported_flow();
+
+        assert_eq!(flow.cvd(), 8.0);
+        assert_eq!(flow.cvd_change(1), 10.0);
+        assert_eq!(flow.cvd_change(2), 4.0);
+        assert_eq!(flow.cvd_change(50), 4.0);
+        assert!((flow.average_imbalance(2) - 0.2).abs() < 1e-12);
+        assert!((flow.average_imbalance(50) - 0.8 / 3.0).abs() < 1e-12);
+
+        let empty = OrderFlow::from_candles(&[]);
+        assert_eq!((empty.cvd(), empty.cvd_change(5), empty.average_imbalance(5)), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn breakout_needs_pressure_on_the_candle_and_over_the_lookback() {
+        let flow = reported_flow();
+        assert!(flow.confirms_breakout(TrendDirection::Long, 2));
+        assert!(!flow.confirms_breakout(TrendDirection::Short, 2));
+        assert!(!flow.confirms_breakout(TrendDirection::Neutral, 2));
+
+        // Buyers on the last candle only, after two candles of selling
+        let late = OrderFlow::from_candles(&[
+            candle(0, 100.0, 101.0, 100.5, 10.0, Some(0.0)),
+            candle(1, 100.0, 101.0, 100.5, 10.0, Some(0.0)),
+            candle(2, 100.0, 101.0, 100.5, 10.0, Some(6.0)),
+        ]);
+        assert!(late.confirms_breakout(TrendDirection::Long, 1));
+        assert!(!late.confirms_breakout(TrendDirection::Long, 2));
+
+        // Estimated selling into the low
+        let selling = OrderFlow::from_candles(&[
+            candle(0, 100.0, 101.0, 100.2, 10.0, None),
+            candle(1, 100.0, 101.0, 100.1, 10.0, None),
+        ]);
+        assert!(selling.confirms_breakout(TrendDirection::Short, 1));
+
+        assert!(!OrderFlow::from_candles(&[]).confirms_breakout(TrendDirection::Long, 5));
+    }
+}
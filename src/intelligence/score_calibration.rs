@@ -0,0 +1,291 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use crate::types::TrendDirection;
+
+// Binned calibration groups scores in buckets of this many points
+const BIN_WIDTH: u8 = 10;
+// A bin needs this many trades before its numbers are trusted
+pub const MIN_BIN_TRADES: usize = 10;
+
+// Confluence score of an entry and how the trade ended, in multiples of the
+// risk taken at entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredOutcome {
+    pub symbol: String,
+    pub strategy: String,
+    pub direction: TrendDirection,
+    pub score: u8,
+    pub r_multiple: f64,
+    pub closed_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CalibrationMethod {
+    // Fixed BIN_WIDTH buckets
+    Binned,
+    // Pool-adjacent-violators fit, win rate never drops as the score rises
+    Isotonic,
+}
+
+impl std::str::FromStr for CalibrationMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "binned" => Ok(CalibrationMethod::Binned),
+            "isotonic" => Ok(CalibrationMethod::Isotonic),
+            other => bail!("unknown calibration method '{}', expected binned or isotonic", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationBin {
+    pub score_from: u8,
+    pub score_to: u8,
+    pub trades: usize,
+    pub win_rate: f64,
+    pub expected_r: f64,
+}
+
+// Score -> (win rate, expected R) step function fitted on past outcomes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreCalibration {
+    pub method: CalibrationMethod,
+    pub bins: Vec<CalibrationBin>,
+    pub trades: usize,
+}
+
+// Trades sharing one bin while fitting
+#[derive(Clone, Copy)]
+struct Pool {
+    score_from: u8,
+    score_to: u8,
+    trades: usize,
+    wins: usize,
+    r_sum: f64,
+}
+
+impl Pool {
+    fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.trades as f64
+    }
+
+    fn merge(&mut self, other: Pool) {
+        self.score_to = other.score_to;
+        self.trades += other.trades;
+        self.wins += other.wins;
+        self.r_sum += other.r_sum;
+    }
+}
+
+impl ScoreCalibration {
+    pub fn fit(outcomes: &[ScoredOutcome], method: CalibrationMethod) -> Self {
+        let mut sorted: Vec<&ScoredOutcome> = outcomes.iter().collect();
+        sorted.sort_by_key(|o| o.score);
+
+        let bucket = |score: u8| match method {
+            CalibrationMethod::Binned => score / BIN_WIDTH,
+            CalibrationMethod::Isotonic => score,
+        };
+
+        // One pool per bucket, in score order
+        let mut pools: Vec<Pool> = Vec::new();
+        for outcome in sorted {
+            let single = Pool {
+                score_from: outcome.score,
+                score_to: outcome.score,
+                trades: 1,
+                wins: (outcome.r_multiple > 0.0) as usize,
+                r_sum: outcome.r_multiple,
+            };
+            match pools.last_mut() {
+                Some(pool) if bucket(pool.score_from) == bucket(outcome.score) => pool.merge(single),
+                _ => pools.push(single),
+            }
+        }
+
+        if method == CalibrationMethod::Binned {
+            for pool in &mut pools {
+                pool.score_from = pool.score_from / BIN_WIDTH * BIN_WIDTH;
+                pool.score_to = pool.score_from.saturating_add(BIN_WIDTH - 1).min(100);
+            }
+        } else {
+            // Merge backwards while a lower score pool wins more often
+            let mut fitted: Vec<Pool> = Vec::new();
+            for pool in pools {
+                fitted.push(pool);
+                while fitted.len() > 1 && fitted[fitted.len() - 2].win_rate() > fitted[fitted.len() - 1].win_rate() {
+                    let last = fitted.pop().unwrap();
+                    fitted.last_mut().unwrap().merge(last);
+                }
+            }
+            pools = fitted;
+        }
+
+        Self {
+            method,
+            trades: outcomes.len(),
+            bins: pools
+                .into_iter()
+                .map(|p| CalibrationBin {
+                    score_from: p.score_from,
+                    score_to: p.score_to,
+                    trades: p.trades,
+                    win_rate: p.win_rate(),
+                    expected_r: p.r_sum / p.trades as f64,
+                })
+                .collect(),
+        }
+    }
+
+    // Bin covering `score`; between fitted bins the lower one applies, and
+    // scores below every fitted bin have no estimate
+    pub fn bin(&self, score: u8) -> Option<&CalibrationBin> {
+        self.bins.iter().rev().find(|b| b.score_from <= score)
+    }
+
+    // None until the bin has MIN_BIN_TRADES trades
+    pub fn expected_r(&self, score: u8) -> Option<f64> {
+        self.bin(score).filter(|b| b.trades >= MIN_BIN_TRADES).map(|b| b.expected_r)
+    }
+
+    pub fn win_rate(&self, score: u8) -> Option<f64> {
+        self.bin(score).filter(|b| b.trades >= MIN_BIN_TRADES).map(|b| b.win_rate)
+    }
+
+    // One line per bin, for the logs
+    pub fn report(&self) -> Vec<String> {
+        self.bins
+            .iter()
+            .map(|b| {
+                format!(
+                    "score {:>3}-{:<3} {:>4} trades, win rate {:>5.1}%, expected R {:+.2}{}",
+                    b.score_from,
+                    b.score_to,
+                    b.trades,
+                    b.win_rate * 100.0,
+                    b.expected_r,
+                    if b.trades < MIN_BIN_TRADES { " (too few trades)" } else { "" }
+                )
+            })
+            .collect()
+    }
+}
+
+// Append-only JSON lines file of scored outcomes, written by the bot as
+// trades close and readable from backtest output in the same format
+pub struct ScoreJournal {
+    path: PathBuf,
+}
+
+impl ScoreJournal {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn load(&self) -> Result<Vec<ScoredOutcome>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = std::fs::File::open(&self.path)?;
+        let mut outcomes = Vec::new();
+        for (number, line) in std::io::BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(e) => tracing::warn!("Skipping line {} of {}: {}", number + 1, self.path.display(), e),
+            }
+        }
+
+        Ok(outcomes)
+    }
+
+    pub fn append(&self, outcome: &ScoredOutcome) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(outcome)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(score: u8, r_multiple: f64) -> ScoredOutcome {
+        ScoredOutcome {
+            symbol: "BTCUSDT".to_string(),
+            strategy: "test".to_string(),
+            direction: TrendDirection::Long,
+            score,
+            r_multiple,
+            closed_at: 0,
+        }
+    }
+
+    // `wins` winners at +2R and the rest losers at -1R, all at `score`
+    fn trades(score: u8, count: usize, wins: usize) -> Vec<ScoredOutcome> {
+        (0..count).map(|i| outcome(score, if i < wins { 2.0 } else { -1.0 })).collect()
+    }
+
+    #[test]
+    fn binned_groups_scores_by_ten_points() {
+        let outcomes: Vec<ScoredOutcome> = [trades(71, 6, 3), trades(79, 4, 1), trades(85, 10, 8)].concat();
+        let calibration = ScoreCalibration::fit(&outcomes, CalibrationMethod::Binned);
+
+        assert_eq!(calibration.trades, 20);
+        let ranges: Vec<(u8, u8, usize)> = calibration.bins.iter().map(|b| (b.score_from, b.score_to, b.trades)).collect();
+        assert_eq!(ranges, vec![(70, 79, 10), (80, 89, 10)]);
+
+        // 4 wins of 10: 0.4 x 2 - 0.6 x 1
+        assert_eq!(calibration.win_rate(75), Some(0.4));
+        assert!((calibration.expected_r(75).unwrap() - 0.2).abs() < 1e-12);
+        assert_eq!(calibration.win_rate(95), Some(0.8));
+    }
+
+    #[test]
+    fn isotonic_win_rate_never_drops_with_the_score() {
+        let outcomes: Vec<ScoredOutcome> = [trades(60, 10, 5), trades(70, 10, 2), trades(80, 10, 3), trades(90, 10, 9)].concat();
+        let calibration = ScoreCalibration::fit(&outcomes, CalibrationMethod::Isotonic);
+
+        // 60 and 70 pool at 0.35, then 80 at 0.3 joins them: (5 + 2 + 3) / 30
+        let ranges: Vec<(u8, u8, usize)> = calibration.bins.iter().map(|b| (b.score_from, b.score_to, b.trades)).collect();
+        assert_eq!(ranges, vec![(60, 80, 30), (90, 90, 10)]);
+        assert!(calibration.bins.windows(2).all(|w| w[0].win_rate <= w[1].win_rate));
+        assert!((calibration.win_rate(70).unwrap() - 10.0 / 30.0).abs() < 1e-12);
+        assert_eq!(calibration.win_rate(100), Some(0.9));
+    }
+
+    #[test]
+    fn scores_without_a_trusted_bin_have_no_estimate() {
+        let outcomes: Vec<ScoredOutcome> = [trades(70, 10, 5), trades(85, 3, 3)].concat();
+        let calibration = ScoreCalibration::fit(&outcomes, CalibrationMethod::Binned);
+
+        // Below the lowest bin is not the lowest bin
+        assert!(calibration.bin(69).is_none());
+        assert!(calibration.expected_r(50).is_none());
+        assert!(calibration.bin(70).is_some());
+
+        // Under MIN_BIN_TRADES
+        assert_eq!(calibration.bin(85).unwrap().trades, 3);
+        assert!(calibration.win_rate(85).is_none());
+        assert!(calibration.report()[1].contains("too few trades"));
+
+        assert!(ScoreCalibration::fit(&[], CalibrationMethod::Isotonic).expected_r(80).is_none());
+    }
+}
@@ -0,0 +1,327 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use crate::intelligence::confluence_registry::{ConfluenceCheck, ConfluenceContext};
+use crate::intelligence::confluence_scorer::ConfluenceSignal;
+
+// Weekend window from the strategy spec, Sat 22:00 to Mon 02:00 UTC
+const WEEKEND_START_HOUR: u32 = 22;
+const WEEKEND_END_HOUR: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Session {
+    Asia,
+    London,
+    NewYork,
+}
+
+impl Session {
+    pub const ALL: [Session; 3] = [Session::Asia, Session::London, Session::NewYork];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Session::Asia => "Asia",
+            Session::London => "London",
+            Session::NewYork => "New York",
+        }
+    }
+
+    // Home time zone and local opening hours, so DST moves the UTC window
+    fn hours(&self) -> (Tz, u32, u32) {
+        match self {
+            Session::Asia => (chrono_tz::Asia::Tokyo, 9, 18),
+            Session::London => (chrono_tz::Europe::London, 8, 17),
+            Session::NewYork => (chrono_tz::America::New_York, 8, 17),
+        }
+    }
+
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        let (tz, open, close) = self.hours();
+        let local = at.with_timezone(&tz);
+
+        !matches!(local.weekday(), Weekday::Sat | Weekday::Sun) && (open..close).contains(&local.hour())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SessionLiquidity {
+    Low,
+    Medium,
+    High,
+    // London and New York both open
+    Peak,
+}
+
+// UTC window with no new entries, optionally on one weekday only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoTradeWindow {
+    pub weekday: Option<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl NoTradeWindow {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.time();
+        self.weekday.is_none_or(|d| d == at.weekday()) && time >= self.start && time < self.end
+    }
+}
+
+impl std::fmt::Display for NoTradeWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(weekday) = self.weekday {
+            write!(f, "{} ", weekday)?;
+        }
+        write!(f, "{}-{} UTC", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionState {
+    pub at: DateTime<Utc>,
+    pub sessions: Vec<Session>,
+    pub liquidity: SessionLiquidity,
+    pub weekend: bool,
+    pub holiday: bool,
+    // Why no new entries are allowed right now, if they are not
+    pub no_trade: Option<String>,
+}
+
+impl SessionState {
+    // "London/New York overlap", "Asia", "off-hours", ...
+    pub fn label(&self) -> String {
+        match self.sessions.as_slice() {
+            [] => "off-hours".to_string(),
+            [session] => session.name().to_string(),
+            sessions => format!(
+                "{} overlap",
+                sessions.iter().map(|s| s.name()).collect::<Vec<_>>().join("/")
+            ),
+        }
+    }
+}
+
+// Trading sessions, the weekend window, holidays and extra no-trade windows.
+// Answers both "how liquid is it now" for scoring and "may we enter" for the bot.
+#[derive(Debug, Clone, Default)]
+pub struct SessionCalendar {
+    weekend_trading: bool,
+    holidays: Vec<NaiveDate>,
+    no_trade_windows: Vec<NoTradeWindow>,
+}
+
+impl SessionCalendar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_weekend_trading(mut self, enabled: bool) -> Self {
+        self.weekend_trading = enabled;
+        self
+    }
+
+    // Whole UTC days without new entries
+    pub fn with_holidays(mut self, holidays: Vec<NaiveDate>) -> Self {
+        self.holidays = holidays;
+        self
+    }
+
+    pub fn with_no_trade_windows(mut self, windows: Vec<NoTradeWindow>) -> Self {
+        self.no_trade_windows = windows;
+        self
+    }
+
+    pub fn is_weekend(&self, at: DateTime<Utc>) -> bool {
+        match at.weekday() {
+            Weekday::Sat => at.hour() >= WEEKEND_START_HOUR,
+            Weekday::Sun => true,
+            Weekday::Mon => at.hour() < WEEKEND_END_HOUR,
+            _ => false,
+        }
+    }
+
+    pub fn is_holiday(&self, at: DateTime<Utc>) -> bool {
+        self.holidays.contains(&at.date_naive())
+    }
+
+    pub fn state(&self, at: DateTime<Utc>) -> SessionState {
+        let sessions: Vec<Session> = Session::ALL.into_iter().filter(|s| s.is_open(at)).collect();
+        let weekend = self.is_weekend(at);
+        let holiday = self.is_holiday(at);
+
+        let liquidity = if weekend || holiday {
+            SessionLiquidity::Low
+        } else if sessions.contains(&Session::London) && sessions.contains(&Session::NewYork) {
+            SessionLiquidity::Peak
+        } else if sessions.contains(&Session::London) || sessions.contains(&Session::NewYork) {
+            SessionLiquidity::High
+        } else if sessions.contains(&Session::Asia) {
+            SessionLiquidity::Medium
+        } else {
+            SessionLiquidity::Low
+        };
+
+        let no_trade = if weekend && !self.weekend_trading {
+            Some("weekend window (Sat 22:00 - Mon 02:00 UTC)".to_string())
+        } else if holiday {
+            Some(format!("holiday {}", at.date_naive()))
+        } else {
+            self.no_trade_windows
+                .iter()
+                .find(|w| w.contains(at))
+                .map(|w| format!("no-trade window {}", w))
+        };
+
+        SessionState {
+            at,
+            sessions,
+            liquidity,
+            weekend,
+            holiday,
+            no_trade,
+        }
+    }
+}
+
+// The session signal: active while London or New York is open and entries
+// are allowed
+impl ConfluenceCheck for SessionCalendar {
+    fn evaluate(&self, ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+        session_signal(self, ctx.data.as_of, weight)
+    }
+}
+
+pub fn session_signal(calendar: &SessionCalendar, as_of: i64, weight: u8) -> ConfluenceSignal {
+    const NAME: &str = "Sesión Alta Liquidez";
+
+    let Some(at) = DateTime::from_timestamp_millis(as_of) else {
+        return ConfluenceSignal::new(NAME, weight, false, "Invalid evaluation time".to_string());
+    };
+    let state = calendar.state(at);
+    let is_active = state.liquidity >= SessionLiquidity::High && state.no_trade.is_none();
+
+    let mut reason = format!("{} at {} UTC, {:?} liquidity", state.label(), at.format("%H:%M"), state.liquidity);
+    if let Some(no_trade) = &state.no_trade {
+        reason.push_str(&format!(" ({})", no_trade));
+    }
+
+    ConfluenceSignal::new(NAME, weight, is_active, reason)
+        .with_metric("hour_utc", at.hour() as f64)
+        .with_metric("liquidity", state.liquidity as u8 as f64)
+}
+
+// "2026-12-25,2027-01-01"
+pub fn parse_holidays(spec: &str) -> Result<Vec<NaiveDate>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|e| {
+            NaiveDate::parse_from_str(e, "%Y-%m-%d")
+                .map_err(|_| anyhow::anyhow!("invalid holiday '{}', expected YYYY-MM-DD", e))
+        })
+        .collect()
+}
+
+// "Fri 21:00-23:59,13:25-13:35"; times are UTC and may not cross midnight
+pub fn parse_no_trade_windows(spec: &str) -> Result<Vec<NoTradeWindow>> {
+    let mut windows = Vec::new();
+
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (weekday, range) = match entry.split_once(' ') {
+            Some((day, range)) => {
+                let weekday: Weekday = day.parse()
+                    .map_err(|_| anyhow::anyhow!("invalid weekday in no-trade window '{}'", entry))?;
+                (Some(weekday), range.trim())
+            }
+            None => (None, entry),
+        };
+
+        let Some((start, end)) = range.split_once('-') else {
+            bail!("invalid no-trade window '{}', expected [Day] HH:MM-HH:MM", entry);
+        };
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value.trim(), "%H:%M")
+                .map_err(|_| anyhow::anyhow!("invalid time '{}' in no-trade window '{}'", value.trim(), entry))
+        };
+        let (start, end) = (time(start)?, time(end)?);
+        if end <= start {
+            bail!("no-trade window '{}' must end after it starts", entry);
+        }
+
+        windows.push(NoTradeWindow { weekday, start, end });
+    }
+
+    Ok(windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // 2024-01-02 00:00 UTC, a Tuesday
+    const BASE: i64 = 1_704_153_600_000;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn session_signal_follows_utc_hours() {
+        let hour = 3_600_000;
+
+        let calendar = SessionCalendar::new();
+        let overlap = session_signal(&calendar, BASE + 14 * hour, 10);
+        assert!(overlap.is_active);
+        assert!(overlap.reason.contains("overlap"));
+
+        let asia = session_signal(&calendar, BASE + 3 * hour, 10);
+        assert!(!asia.is_active);
+    }
+
+    #[test]
+    fn session_windows_follow_dst_and_weekend() {
+        let calendar = SessionCalendar::new();
+
+        // London opens 08:00 local: 08:00 UTC in winter, 07:00 UTC in summer
+        assert_eq!(calendar.state(utc(2024, 1, 2, 7, 0)).label(), "Asia");
+        assert_eq!(calendar.state(utc(2024, 7, 2, 7, 0)).label(), "Asia/London overlap");
+
+        // New York opens 08:00 local: 13:00 UTC in winter, 12:00 UTC in summer
+        assert_eq!(calendar.state(utc(2024, 1, 2, 12, 0)).liquidity, SessionLiquidity::High);
+        assert_eq!(calendar.state(utc(2024, 7, 2, 12, 0)).liquidity, SessionLiquidity::Peak);
+
+        // Sat 22:00 to Mon 02:00 UTC
+        assert!(calendar.state(utc(2024, 1, 6, 21, 0)).no_trade.is_none());
+        assert!(calendar.state(utc(2024, 1, 6, 22, 0)).no_trade.is_some());
+        assert!(calendar.state(utc(2024, 1, 8, 1, 0)).no_trade.is_some());
+        assert!(calendar.state(utc(2024, 1, 8, 2, 0)).no_trade.is_none());
+        assert!(calendar.clone().with_weekend_trading(true).state(utc(2024, 1, 7, 12, 0)).no_trade.is_none());
+    }
+
+    #[test]
+    fn holidays_and_no_trade_windows_block_entries() {
+        let calendar = SessionCalendar::new()
+            .with_holidays(parse_holidays("2024-12-25, 2025-01-01").unwrap())
+            .with_no_trade_windows(parse_no_trade_windows("Fri 21:00-23:59,13:25-13:35").unwrap());
+
+        // Christmas during the London/NY overlap is still closed
+        let christmas = calendar.state(utc(2024, 12, 25, 14, 0));
+        assert!(christmas.holiday);
+        assert_eq!(christmas.liquidity, SessionLiquidity::Low);
+        assert!(christmas.no_trade.unwrap().contains("holiday"));
+        assert!(calendar.state(utc(2024, 12, 26, 14, 0)).no_trade.is_none());
+
+        // Windows include their start and exclude their end
+        assert!(calendar.state(utc(2024, 1, 3, 13, 25)).no_trade.is_some());
+        assert!(calendar.state(utc(2024, 1, 3, 13, 35)).no_trade.is_none());
+        assert!(calendar.state(utc(2024, 1, 5, 21, 0)).no_trade.is_some());
+        assert!(calendar.state(utc(2024, 1, 4, 21, 0)).no_trade.is_none());
+        assert!(!session_signal(&calendar, utc(2024, 1, 3, 13, 30).timestamp_millis(), 10).is_active);
+
+        assert!(parse_holidays("2024-13-01").is_err());
+        assert!(parse_no_trade_windows("Fri 23:00-21:00").is_err());
+        assert!(parse_no_trade_windows("Funday 10:00-11:00").is_err());
+    }
+}
@@ -0,0 +1,595 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::path::Path;
+use crate::types::{MarketData, Timeframe, TrendDirection, to_f64};
+use crate::intelligence::confluence_registry::{ConfluenceCheck, ConfluenceContext, ConfluenceRegistry};
+use crate::intelligence::confluence_scorer::ConfluenceSignal;
+
+// Anti-overfitting rule from the strategy spec
+pub const MAX_CONDITIONS: usize = 7;
+
+// One setup as written in the rules file:
+//
+//   [[setups]]
+//   name = "pullback_h1"
+//   direction = "long"
+//   timeframe = "1h"
+//   conditions = ["rsi(14) between 40 and 60", "close > ema(50)", "4h: adx(14) > 25"]
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetupDefinition {
+    pub name: String,
+    // Score points when every condition holds (default 0, logged only)
+    pub weight: Option<u8>,
+    // long, short or any (default)
+    pub direction: Option<String>,
+    // Timeframe for conditions without their own prefix; the scoring
+    // timeframe when unset
+    pub timeframe: Option<String>,
+    pub conditions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SetupFile {
+    #[serde(default)]
+    setups: Vec<SetupDefinition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Indicator {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    Rsi(usize),
+    Ema(usize),
+    Adx(usize),
+    Atr(usize),
+    VolumeSma(usize),
+}
+
+impl Indicator {
+    fn parse(name: &str, period: Option<usize>) -> Result<Self> {
+        let indicator = match (name, period) {
+            ("open", None) => Indicator::Open,
+            ("high", None) => Indicator::High,
+            ("low", None) => Indicator::Low,
+            ("close", None) => Indicator::Close,
+            ("volume", None) => Indicator::Volume,
+            ("rsi", Some(p)) => Indicator::Rsi(p),
+            ("ema", Some(p)) => Indicator::Ema(p),
+            ("adx", Some(p)) => Indicator::Adx(p),
+            ("atr", Some(p)) => Indicator::Atr(p),
+            ("volume_sma", Some(p)) => Indicator::VolumeSma(p),
+            ("open" | "high" | "low" | "close" | "volume", Some(_)) => bail!("'{}' takes no period", name),
+            ("rsi" | "ema" | "adx" | "atr" | "volume_sma", None) => bail!("'{}' needs a period, e.g. {}(14)", name, name),
+            _ => bail!("unknown indicator '{}'", name),
+        };
+
+        if period == Some(0) {
+            bail!("'{}' period must be > 0", name);
+        }
+        Ok(indicator)
+    }
+
+    // Candles needed before the value means anything
+    fn min_candles(&self) -> usize {
+        match self {
+            Indicator::Rsi(p) | Indicator::Adx(p) | Indicator::Atr(p) => p + 1,
+            Indicator::Ema(p) | Indicator::VolumeSma(p) => *p,
+            _ => 1,
+        }
+    }
+
+    pub fn value(&self, data: &MarketData) -> Option<f64> {
+        if data.candles.len() < self.min_candles() {
+            return None;
+        }
+        let last = data.last_candle()?;
+
+        Some(match self {
+            Indicator::Open => to_f64(last.open),
+            Indicator::High => to_f64(last.high),
+            Indicator::Low => to_f64(last.low),
+            Indicator::Close => to_f64(last.close),
+            Indicator::Volume => to_f64(last.volume),
+            Indicator::Rsi(p) => data.rsi(*p),
+            Indicator::Ema(p) => to_f64(data.ema(*p)),
+            Indicator::Adx(p) => data.adx(*p),
+            Indicator::Atr(p) => to_f64(data.atr(*p)),
+            Indicator::VolumeSma(p) => to_f64(data.volume_sma(*p)),
+        })
+    }
+}
+
+impl std::fmt::Display for Indicator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Indicator::Open => write!(f, "open"),
+            Indicator::High => write!(f, "high"),
+            Indicator::Low => write!(f, "low"),
+            Indicator::Close => write!(f, "close"),
+            Indicator::Volume => write!(f, "volume"),
+            Indicator::Rsi(p) => write!(f, "rsi({})", p),
+            Indicator::Ema(p) => write!(f, "ema({})", p),
+            Indicator::Adx(p) => write!(f, "adx({})", p),
+            Indicator::Atr(p) => write!(f, "atr({})", p),
+            Indicator::VolumeSma(p) => write!(f, "volume_sma({})", p),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Value(f64),
+    Indicator(Indicator),
+}
+
+impl Operand {
+    fn value(&self, data: &MarketData) -> Option<f64> {
+        match self {
+            Operand::Value(value) => Some(*value),
+            Operand::Indicator(indicator) => indicator.value(data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    fn holds(&self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Predicate {
+    Compare { left: Operand, comparison: Comparison, right: Operand },
+    // Inclusive on both ends
+    Between { value: Operand, low: Operand, high: Operand },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleCondition {
+    // As written in the file, for logs
+    pub text: String,
+    // None evaluates on the scoring timeframe
+    pub timeframe: Option<Timeframe>,
+    pub predicate: Predicate,
+}
+
+impl RuleCondition {
+    // "[tf:] operand (> | >= | < | <=) operand" or "[tf:] operand between operand and operand"
+    pub fn parse(text: &str) -> Result<Self> {
+        let (timeframe, expression) = match text.split_once(':') {
+            Some((timeframe, expression)) => (Some(timeframe.parse::<Timeframe>()?), expression),
+            None => (None, text),
+        };
+
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser { tokens: &tokens, position: 0 };
+        let left = parser.operand()?;
+
+        let predicate = match parser.next() {
+            Some(Token::Word(word)) if word == "between" => {
+                let low = parser.operand()?;
+                match parser.next() {
+                    Some(Token::Word(word)) if word == "and" => {}
+                    _ => bail!("expected 'and' in '{}'", text.trim()),
+                }
+                Predicate::Between { value: left, low, high: parser.operand()? }
+            }
+            Some(Token::Comparison(comparison)) => Predicate::Compare {
+                left,
+                comparison: *comparison,
+                right: parser.operand()?,
+            },
+            _ => bail!("expected a comparison or 'between' in '{}'", text.trim()),
+        };
+
+        if parser.position < tokens.len() {
+            bail!("unexpected trailing input in '{}'", text.trim());
+        }
+        if let Predicate::Between { low: Operand::Value(low), high: Operand::Value(high), .. } = predicate {
+            if low > high {
+                bail!("empty range in '{}'", text.trim());
+            }
+        }
+
+        Ok(Self {
+            text: text.trim().to_string(),
+            timeframe,
+            predicate,
+        })
+    }
+
+    fn indicators(&self) -> Vec<Indicator> {
+        let operands = match self.predicate {
+            Predicate::Compare { left, right, .. } => vec![left, right],
+            Predicate::Between { value, low, high } => vec![value, low, high],
+        };
+        operands
+            .into_iter()
+            .filter_map(|o| match o {
+                Operand::Indicator(indicator) => Some(indicator),
+                Operand::Value(_) => None,
+            })
+            .collect()
+    }
+
+    // Whether the condition holds, or why it could not be evaluated
+    pub fn holds(&self, data: &MarketData) -> Result<bool, String> {
+        let value = |operand: &Operand| {
+            operand.value(data).ok_or_else(|| format!("not enough {:?} candles for '{}'", data.timeframe, self.text))
+        };
+
+        Ok(match &self.predicate {
+            Predicate::Compare { left, comparison, right } => comparison.holds(value(left)?, value(right)?),
+            Predicate::Between { value: operand, low, high } => {
+                let operand = value(operand)?;
+                operand >= value(low)? && operand <= value(high)?
+            }
+        })
+    }
+}
+
+// A named set of conditions that votes `direction` when all of them hold
+#[derive(Debug, Clone)]
+pub struct RuleSetup {
+    pub name: String,
+    pub weight: u8,
+    pub direction: TrendDirection,
+    pub conditions: Vec<RuleCondition>,
+}
+
+impl RuleSetup {
+    pub fn compile(definition: &SetupDefinition) -> Result<Self> {
+        let name = definition.name.trim();
+        if name.is_empty() {
+            bail!("setup without a name");
+        }
+        if definition.conditions.is_empty() {
+            bail!("setup '{}' has no conditions", name);
+        }
+        if definition.conditions.len() > MAX_CONDITIONS {
+            bail!(
+                "setup '{}' has {} conditions, at most {} are allowed",
+                name,
+                definition.conditions.len(),
+                MAX_CONDITIONS
+            );
+        }
+
+        let weight = definition.weight.unwrap_or(0);
+        if weight > 100 {
+            bail!("setup '{}' weight must be <= 100", name);
+        }
+
+        let direction = match definition.direction.as_deref().map(|d| d.trim().to_lowercase()) {
+            None => TrendDirection::Neutral,
+            Some(d) if d == "any" => TrendDirection::Neutral,
+            Some(d) if d == "long" => TrendDirection::Long,
+            Some(d) if d == "short" => TrendDirection::Short,
+            Some(d) => bail!("setup '{}' direction '{}' must be long, short or any", name, d),
+        };
+
+        let default_timeframe = definition.timeframe.as_deref().map(str::parse::<Timeframe>).transpose()?;
+        let conditions = definition.conditions
+            .iter()
+            .map(|c| {
+                let mut condition = RuleCondition::parse(c)
+                    .map_err(|e| anyhow::anyhow!("setup '{}': {}", name, e))?;
+                condition.timeframe = condition.timeframe.or(default_timeframe);
+                Ok(condition)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            name: name.to_string(),
+            weight,
+            direction,
+            conditions,
+        })
+    }
+
+    // Timeframes the conditions read besides the scoring one
+    pub fn timeframes(&self) -> Vec<Timeframe> {
+        let mut timeframes: Vec<Timeframe> = self.conditions.iter().filter_map(|c| c.timeframe).collect();
+        timeframes.sort_by_key(|tf| tf.to_minutes());
+        timeframes.dedup();
+        timeframes
+    }
+}
+
+impl ConfluenceCheck for RuleSetup {
+    fn evaluate(&self, ctx: &ConfluenceContext, weight: u8) -> ConfluenceSignal {
+        let mut metrics = Vec::new();
+        let mut failed = None;
+
+        for condition in &self.conditions {
+            let aligned;
+            let data = match condition.timeframe {
+                Some(timeframe) if timeframe != ctx.timeframe => {
+                    aligned = ctx.data.aligned(timeframe);
+                    match &aligned {
+                        Some(data) => data,
+                        None => {
+                            failed = Some(format!("no {:?} data for '{}'", timeframe, condition.text));
+                            break;
+                        }
+                    }
+                }
+                _ => ctx.market_data,
+            };
+
+            for indicator in condition.indicators() {
+                if let Some(value) = indicator.value(data) {
+                    metrics.push((format!("{:?} {}", data.timeframe, indicator), value));
+                }
+            }
+
+            match condition.holds(data) {
+                Ok(true) => {}
+                Ok(false) => {
+                    failed = Some(format!("'{}' not met", condition.text));
+                    break;
+                }
+                Err(reason) => {
+                    failed = Some(reason);
+                    break;
+                }
+            }
+        }
+
+        let signal = match failed {
+            Some(reason) => ConfluenceSignal::new(&self.name, weight, false, reason),
+            None => ConfluenceSignal::new(
+                &self.name,
+                weight,
+                true,
+                format!("All {} conditions met", self.conditions.len()),
+            ),
+        };
+
+        metrics
+            .into_iter()
+            .fold(signal.with_direction(self.direction), |signal, (key, value)| signal.with_metric(&key, value))
+    }
+}
+
+// Reads a TOML or YAML rules file (by extension) and compiles every setup
+pub fn load_setup_rules(path: impl AsRef<Path>) -> Result<Vec<RuleSetup>> {
+    let path = path.as_ref();
+    let file: SetupFile = ::config::Config::builder()
+        .add_source(::config::File::from(path))
+        .build()
+        .and_then(|c| c.try_deserialize())
+        .map_err(|e| anyhow::anyhow!("invalid setup rules file {}: {}", path.display(), e))?;
+
+    let mut setups: Vec<RuleSetup> = Vec::new();
+    for definition in &file.setups {
+        let setup = RuleSetup::compile(definition)?;
+        if setups.iter().any(|s| s.name == setup.name) {
+            bail!("setup '{}' is defined twice", setup.name);
+        }
+        setups.push(setup);
+    }
+
+    Ok(setups)
+}
+
+// Adds each setup as a check keyed by its name, with its own weight
+pub fn register_setups(registry: &mut ConfluenceRegistry, setups: &[RuleSetup]) -> Result<()> {
+    for setup in setups {
+        registry.register(&setup.name, setup.weight, std::sync::Arc::new(setup.clone()))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Comparison(Comparison),
+    Open,
+    Close,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+        } else if c == '>' || c == '<' {
+            chars.next();
+            let or_equal = chars.next_if_eq(&'=').is_some();
+            tokens.push(Token::Comparison(match (c, or_equal) {
+                ('>', false) => Comparison::Greater,
+                ('>', true) => Comparison::GreaterOrEqual,
+                ('<', false) => Comparison::Less,
+                _ => Comparison::LessOrEqual,
+            }));
+        } else if c.is_ascii_digit() || c == '.' || c == '-' {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.' || *c == '-') {
+                number.push(c);
+            }
+            let value = number.parse().map_err(|_| anyhow::anyhow!("invalid number '{}'", number))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                word.push(c);
+            }
+            tokens.push(Token::Word(word.to_lowercase()));
+        } else {
+            bail!("unexpected '{}' in '{}'", c, expression.trim());
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    // A number, or an indicator name with an optional "(period)"
+    fn operand(&mut self) -> Result<Operand> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => Ok(Operand::Value(value)),
+            Some(Token::Word(name)) => {
+                let period = if self.tokens.get(self.position) == Some(&Token::Open) {
+                    self.position += 1;
+                    let period = match self.next() {
+                        Some(Token::Number(p)) if p.fract() == 0.0 && *p >= 0.0 => *p as usize,
+                        _ => bail!("'{}' period must be a whole number", name),
+                    };
+                    if self.next() != Some(&Token::Close) {
+                        bail!("missing ')' after {}({}", name, period);
+                    }
+                    Some(period)
+                } else {
+                    None
+                };
+                Ok(Operand::Indicator(Indicator::parse(&name, period)?))
+            }
+            _ => bail!("expected a number or an indicator"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Candle, MultiTimeframeData};
+    use rust_decimal::Decimal;
+
+    fn trending_candles(count: usize, step: f64, timeframe: Timeframe) -> MarketData {
+        let dec = |value: f64| Decimal::try_from(value).unwrap();
+        let candles = (0..count)
+            .map(|i| {
+                let open = 100.0 + step * i as f64;
+                let close = open + step;
+                Candle::new(i as i64 * timeframe.to_millis(), dec(open), dec(close + 0.1), dec(open - 0.1), dec(close), dec(100.0))
+            })
+            .collect();
+
+        MarketData {
+            symbol: "BTCUSDT".to_string(),
+            candles,
+            timeframe,
+        }
+    }
+
+    fn write_rules(extension: &str, contents: &str) -> Result<Vec<RuleSetup>> {
+        let path = std::env::temp_dir().join(format!("setup_rules_{}_{}.{}", std::process::id(), extension, extension));
+        std::fs::write(&path, contents).unwrap();
+        let setups = load_setup_rules(&path);
+        std::fs::remove_file(&path).unwrap();
+        setups
+    }
+
+    fn definition(conditions: Vec<&str>) -> SetupDefinition {
+        SetupDefinition {
+            name: "test".to_string(),
+            weight: None,
+            direction: None,
+            timeframe: None,
+            conditions: conditions.into_iter().map(String::from).collect(),
+        }
+    }
+
+    #[test]
+    fn setup_rules_compile_and_cap_conditions() {
+        let setups = write_rules("toml", r#"
+            [[setups]]
+            name = "trend_pullback"
+            direction = "long"
+            conditions = ["rsi(14) between 40 and 100", "close > ema(20)", "1h: adx(14) > 25"]
+        "#).unwrap();
+
+        let setup = &setups[0];
+        assert_eq!(setup.direction, TrendDirection::Long);
+        assert_eq!(setup.timeframes(), vec![Timeframe::H1]);
+
+        let mut data = MultiTimeframeData::new("BTCUSDT".to_string(), i64::MAX);
+        data.insert(trending_candles(60, 0.5, Timeframe::M15));
+        data.insert(trending_candles(60, 0.5, Timeframe::H1));
+        let market_data = data.aligned(Timeframe::M15).unwrap();
+        let ctx = ConfluenceContext {
+            data: &data,
+            timeframe: Timeframe::M15,
+            market_data: &market_data,
+            breakout: None,
+            direction: TrendDirection::Long,
+        };
+        let signal = setup.evaluate(&ctx, 10);
+        assert!(signal.is_active, "{}", signal.reason);
+        assert_eq!(signal.score, 10);
+        assert!(signal.metrics.contains_key("H1 adx(14)"));
+
+        assert!(RuleSetup::compile(&definition(vec!["close > ema(50)"; MAX_CONDITIONS])).is_ok());
+        assert!(RuleSetup::compile(&definition(vec!["close > ema(50)"; MAX_CONDITIONS + 1])).is_err());
+        assert!(RuleSetup::compile(&definition(vec!["close > ema"])).is_err());
+        assert!(RuleSetup::compile(&definition(vec!["rsi(14) between 40 60"])).is_err());
+        assert!(RuleSetup::compile(&definition(vec!["rsi(14) between 60 and 40"])).is_err());
+    }
+
+    #[test]
+    fn unknown_indicators_are_rejected() {
+        let error = RuleSetup::compile(&definition(vec!["macd(12) > 0"])).unwrap_err();
+        assert!(error.to_string().contains("unknown indicator 'macd'"), "{}", error);
+
+        assert!(RuleCondition::parse("close(14) > 0").is_err());
+        assert!(RuleCondition::parse("ema(0) > 0").is_err());
+        assert!(RuleCondition::parse("2x: close > 0").is_err());
+    }
+
+    #[test]
+    fn yaml_rules_load_and_invalid_files_are_rejected() {
+        let setups = write_rules("yaml", "
+setups:
+  - name: oversold
+    weight: 5
+    direction: short
+    timeframe: 4h
+    conditions:
+      - rsi(14) < 30
+").unwrap();
+        assert_eq!(setups[0].weight, 5);
+        assert_eq!(setups[0].direction, TrendDirection::Short);
+        assert_eq!(setups[0].conditions[0].timeframe, Some(Timeframe::H4));
+
+        assert!(write_rules("toml", "[[setups]]\nname = \"broken\"\nconditions = [\"close > 0\"").is_err());
+        assert!(write_rules("yaml", "setups:\n  - name: [unclosed\n").is_err());
+        assert!(write_rules("toml", "[[setups]]\nname = \"no_conditions\"\n").is_err());
+
+        let duplicate = "[[setups]]\nname = \"a\"\nconditions = [\"close > 0\"]\n".repeat(2);
+        assert!(write_rules("toml", &duplicate).unwrap_err().to_string().contains("defined twice"));
+    }
+}
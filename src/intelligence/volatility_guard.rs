@@ -0,0 +1,264 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::types::{MarketData, TrendDirection, to_f64};
+
+// No new trades while ATR(14) > 2.5 x ATR(50) or after a > 5% drop in 1h
+const ATR_EXPANSION_LIMIT: f64 = 2.5;
+const FLASH_DROP_PERCENT: f64 = 5.0;
+const FLASH_WINDOW_MS: i64 = 60 * 60 * 1000;
+pub const DEFAULT_COOLDOWN_MS: i64 = 2 * 60 * 60 * 1000;
+// Tightened stops sit this many ATR(14) away from price
+const TIGHT_STOP_ATR: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VolatilityTrigger {
+    AtrExpansion { atr_fast: f64, atr_slow: f64, ratio: f64 },
+    FlashCrash { from_price: f64, to_price: f64, drop_percent: f64 },
+}
+
+impl VolatilityTrigger {
+    pub fn reason(&self) -> String {
+        match self {
+            VolatilityTrigger::AtrExpansion { atr_fast, atr_slow, ratio } => format!(
+                "ATR(14) {:.2} is {:.1}x ATR(50) {:.2} (limit {}x)",
+                atr_fast, ratio, atr_slow, ATR_EXPANSION_LIMIT
+            ),
+            VolatilityTrigger::FlashCrash { from_price, to_price, drop_percent } => format!(
+                "Price fell {:.1}% from {:.2} to {:.2} within 1h (limit {}%)",
+                drop_percent, from_price, to_price, FLASH_DROP_PERCENT
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolatilityBlock {
+    pub symbol: String,
+    pub trigger: VolatilityTrigger,
+    pub reason: String,
+    pub triggered_at: i64,
+    // Entries stay blocked until this time, pushed back while the trigger holds
+    pub until: i64,
+}
+
+// Per-symbol entry block for the spec's extreme volatility conditions
+pub struct VolatilityGuard {
+    cooldown_ms: i64,
+    tighten_stops: bool,
+    blocks: HashMap<String, VolatilityBlock>,
+}
+
+impl VolatilityGuard {
+    pub fn new() -> Self {
+        Self::with_cooldown(DEFAULT_COOLDOWN_MS, false)
+    }
+
+    pub fn with_cooldown(cooldown_ms: i64, tighten_stops: bool) -> Self {
+        Self {
+            cooldown_ms,
+            tighten_stops,
+            blocks: HashMap::new(),
+        }
+    }
+
+    // Conditions on the latest closed bars, without touching any state
+    pub fn check(&self, data: &MarketData) -> Option<VolatilityTrigger> {
+        let atr_fast = to_f64(data.atr(14));
+        let atr_slow = to_f64(data.atr(50));
+        if atr_slow > 0.0 && atr_fast > atr_slow * ATR_EXPANSION_LIMIT {
+            return Some(VolatilityTrigger::AtrExpansion {
+                atr_fast,
+                atr_slow,
+                ratio: atr_fast / atr_slow,
+            });
+        }
+
+        flash_crash(data)
+    }
+
+    // Returns the block only when the symbol was not blocked before, so the
+    // caller can log and alert once per episode
+    pub fn update(&mut self, data: &MarketData, now: i64) -> Option<VolatilityBlock> {
+        let trigger = self.check(data)?;
+        let until = now + self.cooldown_ms;
+
+        if let Some(block) = self.blocks.get_mut(&data.symbol) {
+            if block.until > now {
+                block.until = block.until.max(until);
+                return None;
+            }
+        }
+
+        let block = VolatilityBlock {
+            symbol: data.symbol.clone(),
+            trigger,
+            reason: trigger.reason(),
+            triggered_at: now,
+            until,
+        };
+        self.blocks.insert(data.symbol.clone(), block.clone());
+        Some(block)
+    }
+
+    pub fn blocked(&self, symbol: &str, now: i64) -> Option<&VolatilityBlock> {
+        self.blocks.get(symbol).filter(|b| b.until > now)
+    }
+
+    // Stop pulled to TIGHT_STOP_ATR x ATR from price while the symbol is
+    // blocked; None if disabled, not blocked or the current stop is tighter
+    pub fn tightened_stop(
+        &self,
+        symbol: &str,
+        direction: TrendDirection,
+        current_price: Decimal,
+        current_stop: Decimal,
+        atr: Decimal,
+        now: i64,
+    ) -> Option<Decimal> {
+        if !self.tighten_stops || self.blocked(symbol, now).is_none() {
+            return None;
+        }
+
+        let distance = atr * Decimal::try_from(TIGHT_STOP_ATR).ok()?;
+        match direction {
+            TrendDirection::Long => {
+                let stop = current_price - distance;
+                (stop > current_stop).then_some(stop)
+            }
+            TrendDirection::Short => {
+                let stop = current_price + distance;
+                (stop < current_stop).then_some(stop)
+            }
+            TrendDirection::Neutral => None,
+        }
+    }
+}
+
+impl Default for VolatilityGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Largest high-to-later-low fall among the bars of the last hour
+fn flash_crash(data: &MarketData) -> Option<VolatilityTrigger> {
+    let last = data.last_candle()?;
+    let window_start = last.close_time(data.timeframe) - FLASH_WINDOW_MS;
+
+    let mut peak = f64::MIN;
+    let mut worst: Option<(f64, f64)> = None;
+
+    for candle in data.candles.iter().filter(|c| c.timestamp >= window_start) {
+        peak = peak.max(to_f64(candle.high));
+        let low = to_f64(candle.low);
+        if peak > 0.0 && worst.is_none_or(|(from, to)| (peak - low) / peak > (from - to) / from) {
+            worst = Some((peak, low));
+        }
+    }
+
+    let (from_price, to_price) = worst?;
+    let drop_percent = (from_price - to_price) / from_price * 100.0;
+    (drop_percent > FLASH_DROP_PERCENT).then_some(VolatilityTrigger::FlashCrash {
+        from_price,
+        to_price,
+        drop_percent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Candle, Timeframe};
+
+    const M15: i64 = 15 * 60_000;
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::try_from(value).unwrap()
+    }
+
+    fn market_data(candles: Vec<Candle>) -> MarketData {
+        MarketData {
+            symbol: "SOLUSDT".to_string(),
+            candles,
+            timeframe: Timeframe::M15,
+        }
+    }
+
+    // Doji bars at 1000 with a range of 1, then 14 bars with `last_range`
+    fn expanding(last_range: f64) -> MarketData {
+        let candles = (0..70)
+            .map(|i| {
+                let half = if i >= 56 { last_range / 2.0 } else { 0.5 };
+                Candle::new(i as i64 * M15, dec(1000.0), dec(1000.0 + half), dec(1000.0 - half), dec(1000.0), dec(100.0))
+            })
+            .collect();
+        market_data(candles)
+    }
+
+    // Flat bars at 100, the last one wicking down to `low`
+    fn crash(low: f64) -> MarketData {
+        let mut candles: Vec<Candle> = (0..60)
+            .map(|i| Candle::new(i as i64 * M15, dec(100.0), dec(100.0), dec(99.9), dec(100.0), dec(100.0)))
+            .collect();
+        candles.push(Candle::new(60 * M15, dec(100.0), dec(100.0), dec(low), dec(100.0), dec(100.0)));
+        market_data(candles)
+    }
+
+    #[test]
+    fn atr_expansion_triggers_above_two_and_a_half_times() {
+        let guard = VolatilityGuard::new();
+
+        // ATR(14) 6 against ATR(50) (14 x 6 + 36) / 50 = 2.4: exactly 2.5x
+        assert!(guard.check(&expanding(6.0)).is_none());
+
+        match guard.check(&expanding(6.5)) {
+            Some(VolatilityTrigger::AtrExpansion { atr_fast, atr_slow, ratio }) => {
+                assert!((atr_fast - 6.5).abs() < 1e-9);
+                assert!((atr_slow - 2.54).abs() < 1e-9);
+                assert!(ratio > ATR_EXPANSION_LIMIT);
+            }
+            other => panic!("expected ATR expansion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn flash_crash_needs_more_than_five_percent_within_the_hour() {
+        let guard = VolatilityGuard::new();
+
+        assert!(guard.check(&crash(95.0)).is_none());
+        match guard.check(&crash(94.9)) {
+            Some(VolatilityTrigger::FlashCrash { from_price, to_price, drop_percent }) => {
+                assert_eq!((from_price, to_price), (100.0, 94.9));
+                assert!(drop_percent > FLASH_DROP_PERCENT);
+            }
+            other => panic!("expected flash crash, got {:?}", other),
+        }
+
+        // A low before the high is a rally, not a crash
+        let mut data = crash(100.0);
+        data.candles[57] = Candle::new(57 * M15, dec(90.5), dec(91.0), dec(90.0), dec(90.5), dec(100.0));
+        assert!(flash_crash(&data).is_none());
+    }
+
+    #[test]
+    fn block_is_reported_once_and_extended_while_the_trigger_holds() {
+        let hour = 60 * 60_000;
+        let mut guard = VolatilityGuard::with_cooldown(2 * hour, true);
+        let data = crash(90.0);
+
+        let block = guard.update(&data, 0).unwrap();
+        assert_eq!(block.until, 2 * hour);
+        assert!(guard.update(&data, hour).is_none());
+        assert_eq!(guard.blocked("SOLUSDT", 2 * hour).unwrap().until, 3 * hour);
+        assert!(guard.blocked("SOLUSDT", 3 * hour).is_none());
+        assert!(guard.update(&crash(100.0), 3 * hour).is_none());
+        assert!(guard.update(&data, 3 * hour).is_some());
+
+        // Stops only move closer while blocked
+        let tighten = |stop: f64, now| guard.tightened_stop("SOLUSDT", TrendDirection::Long, dec(100.0), dec(stop), dec(2.0), now);
+        assert_eq!(tighten(95.0, 3 * hour), Some(dec(98.0)));
+        assert_eq!(tighten(99.0, 3 * hour), None);
+        assert_eq!(tighten(95.0, 6 * hour), None);
+    }
+}
@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+use crate::types::{Candle, Timeframe, to_f64};
+
+// Share of total volume contained in the value area
+const VALUE_AREA_SHARE: f64 = 0.70;
+// Node thresholds relative to the mean bin volume
+const HIGH_VOLUME_NODE_FACTOR: f64 = 1.5;
+const LOW_VOLUME_NODE_FACTOR: f64 = 0.5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeBin {
+    pub price_low: f64,
+    pub price_high: f64,
+    pub volume: f64,
+}
+
+impl VolumeBin {
+    pub fn mid(&self) -> f64 {
+        (self.price_low + self.price_high) / 2.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeProfile {
+    pub bins: Vec<VolumeBin>,
+    pub total_volume: f64,
+    pub point_of_control: f64,
+    pub value_area_high: f64,
+    pub value_area_low: f64,
+    pub high_volume_nodes: Vec<f64>,
+    pub low_volume_nodes: Vec<f64>,
+}
+
+impl VolumeProfile {
+    // Each candle's volume is spread evenly over the price range it covered
+    pub fn build(candles: &[Candle], bin_count: usize) -> Option<Self> {
+        if candles.is_empty() || bin_count == 0 {
+            return None;
+        }
+
+        let low = candles.iter().map(|c| to_f64(c.low)).fold(f64::MAX, f64::min);
+        let high = candles.iter().map(|c| to_f64(c.high)).fold(f64::MIN, f64::max);
+        if high <= low {
+            return None;
+        }
+
+        let bin_size = (high - low) / bin_count as f64;
+        let mut bins: Vec<VolumeBin> = (0..bin_count)
+            .map(|i| VolumeBin {
+                price_low: low + bin_size * i as f64,
+                price_high: low + bin_size * (i + 1) as f64,
+                volume: 0.0,
+            })
+            .collect();
+
+        let bin_index = |price: f64| (((price - low) / bin_size) as usize).min(bin_count - 1);
+
+        for candle in candles {
+            let volume = to_f64(candle.volume);
+            let (candle_low, candle_high) = (to_f64(candle.low), to_f64(candle.high));
+
+            if candle_high <= candle_low {
+                bins[bin_index(candle_low)].volume += volume;
+                continue;
+            }
+
+            for bin in &mut bins[bin_index(candle_low)..=bin_index(candle_high)] {
+                let overlap = candle_high.min(bin.price_high) - candle_low.max(bin.price_low);
+                if overlap > 0.0 {
+                    bin.volume += volume * overlap / (candle_high - candle_low);
+                }
+            }
+        }
+
+        let total_volume: f64 = bins.iter().map(|b| b.volume).sum();
+        if total_volume == 0.0 {
+            return None;
+        }
+
+        let poc_index = bins
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.volume.total_cmp(&b.1.volume))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+
+        // Grow the value area from the POC towards the heavier neighbour
+        let (mut lo, mut hi) = (poc_index, poc_index);
+        let mut area_volume = bins[poc_index].volume;
+
+        while area_volume < total_volume * VALUE_AREA_SHARE {
+            let below = if lo > 0 { Some(bins[lo - 1].volume) } else { None };
+            let above = bins.get(hi + 1).map(|b| b.volume);
+
+            match (below, above) {
+                (Some(b), Some(a)) if a >= b => {
+                    hi += 1;
+                    area_volume += a;
+                }
+                (Some(b), _) => {
+                    lo -= 1;
+                    area_volume += b;
+                }
+                (None, Some(a)) => {
+                    hi += 1;
+                    area_volume += a;
+                }
+                (None, None) => break,
+            }
+        }
+
+        let mean_volume = total_volume / bin_count as f64;
+        let mut high_volume_nodes = Vec::new();
+        let mut low_volume_nodes = Vec::new();
+
+        for i in 0..bin_count {
+            let volume = bins[i].volume;
+            let left = if i > 0 { Some(bins[i - 1].volume) } else { None };
+            let right = bins.get(i + 1).map(|b| b.volume);
+
+            // Edge bins only need to beat their single neighbour
+            let is_peak = left.is_none_or(|l| l <= volume) && right.is_none_or(|r| r <= volume);
+            let is_trough = left.is_none_or(|l| l >= volume) && right.is_none_or(|r| r >= volume);
+
+            if is_peak && volume >= mean_volume * HIGH_VOLUME_NODE_FACTOR {
+                high_volume_nodes.push(bins[i].mid());
+            } else if is_trough && volume <= mean_volume * LOW_VOLUME_NODE_FACTOR {
+                low_volume_nodes.push(bins[i].mid());
+            }
+        }
+
+        Some(Self {
+            point_of_control: bins[poc_index].mid(),
+            value_area_high: bins[hi].price_high,
+            value_area_low: bins[lo].price_low,
+            bins,
+            total_volume,
+            high_volume_nodes,
+            low_volume_nodes,
+        })
+    }
+
+    pub fn key_levels(&self) -> Vec<KeyLevel> {
+        vec![
+            KeyLevel::new("POC", self.point_of_control),
+            KeyLevel::new("VAH", self.value_area_high),
+            KeyLevel::new("VAL", self.value_area_low),
+        ]
+    }
+
+    pub fn is_in_value_area(&self, price: f64) -> bool {
+        price >= self.value_area_low && price <= self.value_area_high
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyLevel {
+    pub name: String,
+    pub price: f64,
+}
+
+impl KeyLevel {
+    pub fn new(name: &str, price: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Vwap {
+    pub value: f64,
+    pub std_dev: f64,
+    pub anchor: i64,
+}
+
+impl Vwap {
+    // Volume weighted average of the typical price from `anchor` onwards
+    pub fn anchored(candles: &[Candle], anchor: i64) -> Option<Self> {
+        let mut volume_sum = 0.0;
+        let mut weighted_sum = 0.0;
+        let mut weighted_sq_sum = 0.0;
+
+        for candle in candles.iter().filter(|c| c.timestamp >= anchor) {
+            let typical = to_f64(candle.high + candle.low + candle.close) / 3.0;
+            let volume = to_f64(candle.volume);
+
+            volume_sum += volume;
+            weighted_sum += typical * volume;
+            weighted_sq_sum += typical * typical * volume;
+        }
+
+        if volume_sum == 0.0 {
+            return None;
+        }
+
+        let value = weighted_sum / volume_sum;
+        let variance = (weighted_sq_sum / volume_sum - value * value).max(0.0);
+
+        Some(Self {
+            value,
+            std_dev: variance.sqrt(),
+            anchor,
+        })
+    }
+
+    // Session VWAP resetting at the UTC day boundary of the last candle
+    pub fn session(candles: &[Candle]) -> Option<Self> {
+        let last = candles.last()?;
+        Self::anchored(candles, Timeframe::D1.bucket_start(last.timestamp))
+    }
+
+    // (lower, upper) band `multiplier` standard deviations away
+    pub fn band(&self, multiplier: f64) -> (f64, f64) {
+        (
+            self.value - self.std_dev * multiplier,
+            self.value + self.std_dev * multiplier,
+        )
+    }
+
+    pub fn key_levels(&self) -> Vec<KeyLevel> {
+        let (lower, upper) = self.band(1.0);
+        vec![
+            KeyLevel::new("VWAP", self.value),
+            KeyLevel::new("VWAP -1σ", lower),
+            KeyLevel::new("VWAP +1σ", upper),
+        ]
+    }
+}
+
+// Closest level within `tolerance` of `price`
+pub fn nearest_level(levels: &[KeyLevel], price: f64, tolerance: f64) -> Option<&KeyLevel> {
+    levels
+        .iter()
+        .filter(|l| (l.price - price).abs() <= tolerance)
+        .min_by(|a, b| (a.price - price).abs().total_cmp(&(b.price - price).abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    // 2024-01-02 00:00 UTC
+    const DAY: i64 = 1_704_153_600_000;
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::try_from(value).unwrap()
+    }
+
+    fn candle(timestamp: i64, low: f64, high: f64, volume: f64) -> Candle {
+        Candle::new(timestamp, dec(low), dec(high), dec(low), dec(high), dec(volume))
+    }
+
+    // A flat bar at `price`, whose typical price is `price`
+    fn print(timestamp: i64, price: f64, volume: f64) -> Candle {
+        candle(timestamp, price, price, volume)
+    }
+
+    #[test]
+    fn profile_finds_poc_value_area_and_nodes() {
+        // Ten 1.0 bins from 100 to 110 holding 3 each, plus 18 at 104-105,
+        // 6 at 107-108 and a thin 0.5 at the top
+        let candles = [
+            candle(DAY, 100.0, 109.0, 27.0),
+            candle(DAY + 1, 104.0, 105.0, 18.0),
+            candle(DAY + 2, 107.0, 108.0, 6.0),
+            candle(DAY + 3, 109.0, 110.0, 0.5),
+        ];
+        let profile = VolumeProfile::build(&candles, 10).unwrap();
+
+        let volumes: Vec<f64> = profile.bins.iter().map(|b| b.volume).collect();
+        assert_eq!(volumes, vec![3.0, 3.0, 3.0, 3.0, 21.0, 3.0, 3.0, 9.0, 3.0, 0.5]);
+        assert_eq!(profile.total_volume, 51.5);
+        assert_eq!(profile.point_of_control, 104.5);
+
+        // Grown from the POC towards the heavier side until 70% is covered
+        assert_eq!((profile.value_area_low, profile.value_area_high), (104.0, 109.0));
+        assert!(profile.is_in_value_area(104.0));
+        assert!(!profile.is_in_value_area(103.9));
+
+        assert_eq!(profile.high_volume_nodes, vec![104.5, 107.5]);
+        assert_eq!(profile.low_volume_nodes, vec![109.5]);
+    }
+
+    #[test]
+    fn profile_needs_candles_with_a_range() {
+        assert!(VolumeProfile::build(&[], 10).is_none());
+        assert!(VolumeProfile::build(&[print(DAY, 100.0, 5.0)], 10).is_none());
+        assert!(VolumeProfile::build(&[candle(DAY, 100.0, 101.0, 0.0)], 10).is_none());
+    }
+
+    #[test]
+    fn anchored_vwap_weights_typical_prices_from_the_anchor() {
+        let candles = [print(DAY, 90.0, 5.0), print(DAY + 1, 100.0, 1.0), print(DAY + 2, 110.0, 3.0)];
+        let vwap = Vwap::anchored(&candles, DAY + 1).unwrap();
+
+        // (100 * 1 + 110 * 3) / 4, variance 11575 - 107.5²
+        assert_eq!(vwap.value, 107.5);
+        assert!((vwap.std_dev - 18.75_f64.sqrt()).abs() < 1e-9);
+
+        let (lower, upper) = vwap.band(2.0);
+        assert!((lower - (107.5 - 2.0 * vwap.std_dev)).abs() < 1e-9);
+        assert!((upper - (107.5 + 2.0 * vwap.std_dev)).abs() < 1e-9);
+
+        assert!(Vwap::anchored(&candles, DAY + 3).is_none());
+    }
+
+    #[test]
+    fn session_vwap_resets_at_the_utc_day() {
+        let candles = [
+            print(DAY - 60_000, 50.0, 100.0),
+            print(DAY + 60_000, 100.0, 1.0),
+            print(DAY + 120_000, 110.0, 3.0),
+        ];
+        let vwap = Vwap::session(&candles).unwrap();
+
+        assert_eq!(vwap.anchor, DAY);
+        assert_eq!(vwap.value, 107.5);
+        assert!(Vwap::session(&[]).is_none());
+    }
+
+    #[test]
+    fn nearest_level_within_tolerance() {
+        let levels = [KeyLevel::new("POC", 100.0), KeyLevel::new("VAH", 102.0), KeyLevel::new("VWAP", 101.2)];
+
+        assert_eq!(nearest_level(&levels, 101.5, 0.5).unwrap().name, "VWAP");
+        assert_eq!(nearest_level(&levels, 101.7, 0.5).unwrap().name, "VAH");
+        assert!(nearest_level(&levels, 99.0, 0.5).is_none());
+    }
+}
@@ -1,8 +1,10 @@
 pub mod config;
 pub mod types;
+pub mod resample;
 pub mod intelligence;
 pub mod risk_v2;
 pub mod execution_v2;
 pub mod monitoring;
 pub mod exchange;
+pub mod strategy;
 pub mod bot;
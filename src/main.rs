@@ -1,20 +1,11 @@
 use anyhow::Result;
 use tracing::{info, error};
-use tracing_subscriber;
 use std::sync::Arc;
 
-mod config;
-mod types;
-mod intelligence;
-mod risk_v2;
-mod execution_v2;
-mod monitoring;
-mod exchange;
-mod bot;
-
-use config::Config;
-use exchange::{BybitConnector, BinanceConnector};
-use bot::TradingBot;
+use hyrotrader_bot::{exchange, types};
+use hyrotrader_bot::config::Config;
+use hyrotrader_bot::exchange::{BybitConnector, BinanceConnector};
+use hyrotrader_bot::bot::TradingBot;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -91,21 +82,41 @@ async fn main() -> Result<()> {
         }
     }
 
+    let rules = config.challenge_rules()?;
+    let kill_switch_losses = config.max_consecutive_losses.unwrap_or(4);
+    let bot_limits = format!(
+        "  ✓ Bot limits: daily loss < {}%, drawdown < {}%",
+        config.max_daily_loss_percent,
+        config.max_total_dd_percent
+    );
+    let confluence_rule = format!("  ✓ Confluence score ≥ {} required", config.min_confluence_score);
+    let portfolio = config.portfolio_limits()?;
+    let portfolio_rule = format!(
+        "  ✓ Max {} position{} at a time, {}% total risk at stop, {}% net exposure",
+        portfolio.max_positions,
+        if portfolio.max_positions == 1 { "" } else { "s" },
+        portfolio.max_total_risk_percent,
+        portfolio.max_net_exposure_percent
+    );
+    let risk_rule = format!("  ✓ Risk per trade: {}% - {}% adaptive", config.risk_per_trade_min, config.risk_per_trade_max);
+
     // Initialize trading bot
     info!("Initializing trading bot...");
-    let mut bot = TradingBot::new(config, exchange);
+    let mut bot = TradingBot::new(config, exchange)?;
 
     info!("✅ Bot initialized successfully");
     info!("");
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     info!("  HYROTRADER CHALLENGE - TRADING RULES");
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    info!("  ✓ Max 1 position at a time");
-    info!("  ✓ Confluence score ≥ 70 required");
-    info!("  ✓ Risk per trade: 0.3% - 1.0% adaptive");
-    info!("  ✓ Max daily drawdown: < 5%");
-    info!("  ✓ Max total drawdown: < 10%");
-    info!("  ✓ Kill-switch at 4 consecutive losses");
+    for rule in rules.describe() {
+        info!("  ✓ {}", rule);
+    }
+    info!("{}", portfolio_rule);
+    info!("{}", confluence_rule);
+    info!("{}", risk_rule);
+    info!("{}", bot_limits);
+    info!("  ✓ Kill-switch at {} consecutive losses", kill_switch_losses);
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     info!("");
 
@@ -72,7 +72,7 @@ impl TelegramAlerter {
             trade.entry_price,
             exit_price,
             pnl,
-            (pnl / (trade.entry_price * trade.size as f64)) * 100.0,
+            (pnl / (trade.entry_price * trade.size)) * 100.0,
             reason,
         );
 
@@ -294,7 +294,7 @@ pub struct TradeInfo {
     pub tp3: f64,
     pub risk_percent: f64,
     pub confluence_score: u8,
-    pub size: u64,
+    pub size: f64,
 }
 
 #[derive(Debug, Clone)]
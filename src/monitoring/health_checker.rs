@@ -15,7 +15,7 @@ impl HealthChecker {
     }
 
     pub fn check_system_health(&self) -> HealthStatus {
-        let mut issues = Vec::new();
+        let issues: Vec<String> = Vec::new();
 
         // TODO: Implement health checks
         // - Exchange API connectivity
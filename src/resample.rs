@@ -0,0 +1,241 @@
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use crate::types::{Candle, MarketData, Timeframe, Trade};
+
+// Build `target` bars from `source` bars. Buckets are UTC aligned (see
+// `Timeframe::bucket_start`); a leading bucket that is missing its first
+// source bars is dropped so no bar is built from a partial window.
+pub fn resample(candles: &[Candle], source: Timeframe, target: Timeframe) -> Result<Vec<Candle>> {
+    if !target.is_multiple_of(source) {
+        bail!(
+            "cannot resample {}m bars into {}m bars",
+            source.to_minutes(),
+            target.to_minutes()
+        );
+    }
+
+    let mut sorted: Vec<&Candle> = candles.iter().collect();
+    sorted.sort_by_key(|c| c.timestamp);
+
+    let mut bars: Vec<Candle> = Vec::new();
+    let mut current_bucket: Option<i64> = None;
+
+    for candle in sorted {
+        let bucket = target.bucket_start(candle.timestamp);
+
+        if current_bucket == Some(bucket) {
+            if let Some(bar) = bars.last_mut() {
+                merge(bar, candle);
+            }
+            continue;
+        }
+
+        // First bar seen mid-bucket: the window is incomplete
+        if current_bucket.is_none() && candle.timestamp != bucket {
+            continue;
+        }
+
+        current_bucket = Some(bucket);
+        bars.push(Candle {
+            timestamp: bucket,
+            ..candle.clone()
+        });
+    }
+
+    Ok(bars)
+}
+
+pub fn resample_market_data(data: &MarketData, target: Timeframe) -> Result<MarketData> {
+    Ok(MarketData {
+        symbol: data.symbol.clone(),
+        candles: resample(&data.candles, data.timeframe, target)?,
+        timeframe: target,
+    })
+}
+
+// Build bars straight from trades; buckets without trades are skipped
+pub fn candles_from_trades(trades: &[Trade], timeframe: Timeframe) -> Vec<Candle> {
+    let mut sorted: Vec<&Trade> = trades.iter().collect();
+    sorted.sort_by_key(|t| t.timestamp);
+
+    let mut bars: Vec<Candle> = Vec::new();
+
+    for trade in sorted {
+        let bucket = timeframe.bucket_start(trade.timestamp);
+
+        match bars.last_mut() {
+            Some(bar) if bar.timestamp == bucket => {
+                bar.high = bar.high.max(trade.price);
+                bar.low = bar.low.min(trade.price);
+                bar.close = trade.price;
+            }
+            _ => bars.push(Candle {
+                quote_volume: Some(Decimal::ZERO),
+                trade_count: Some(0),
+                taker_buy_base_volume: Some(Decimal::ZERO),
+                taker_buy_quote_volume: Some(Decimal::ZERO),
+                ..Candle::new(bucket, trade.price, trade.price, trade.price, trade.price, Decimal::ZERO)
+            }),
+        }
+
+        if let Some(bar) = bars.last_mut() {
+            bar.volume += trade.quantity;
+            bar.quote_volume = bar.quote_volume.map(|q| q + trade.price * trade.quantity);
+            bar.trade_count = bar.trade_count.map(|n| n + 1);
+
+            // Taker side is all-or-nothing per bar: one unknown trade hides it
+            match trade.taker_buy {
+                Some(true) => {
+                    bar.taker_buy_base_volume = bar.taker_buy_base_volume.map(|v| v + trade.quantity);
+                    bar.taker_buy_quote_volume = bar.taker_buy_quote_volume
+                        .map(|v| v + trade.price * trade.quantity);
+                }
+                Some(false) => {}
+                None => {
+                    bar.taker_buy_base_volume = None;
+                    bar.taker_buy_quote_volume = None;
+                }
+            }
+        }
+    }
+
+    bars
+}
+
+// Largest native interval that `target` can be built from exactly
+pub fn best_source_timeframe(target: Timeframe, native: &[Timeframe]) -> Option<Timeframe> {
+    native
+        .iter()
+        .copied()
+        .filter(|tf| target.is_multiple_of(*tf))
+        .max_by_key(|tf| tf.to_minutes())
+}
+
+fn merge(bar: &mut Candle, candle: &Candle) {
+    bar.high = bar.high.max(candle.high);
+    bar.low = bar.low.min(candle.low);
+    bar.close = candle.close;
+    bar.volume += candle.volume;
+
+    // Optional fields stay known only if every source bar reported them
+    bar.quote_volume = add(bar.quote_volume, candle.quote_volume);
+    bar.trade_count = bar.trade_count.zip(candle.trade_count).map(|(a, b)| a + b);
+    bar.taker_buy_base_volume = add(bar.taker_buy_base_volume, candle.taker_buy_base_volume);
+    bar.taker_buy_quote_volume = add(bar.taker_buy_quote_volume, candle.taker_buy_quote_volume);
+}
+
+fn add(a: Option<Decimal>, b: Option<Decimal>) -> Option<Decimal> {
+    a.zip(b).map(|(a, b)| a + b)
+}
+
+// Source bars needed to produce `limit` target bars, plus one target bar of
+// slack for the dropped leading bucket
+pub fn source_limit(target: Timeframe, source: Timeframe, limit: usize) -> usize {
+    let ratio = (target.to_minutes() / source.to_minutes().max(1)).max(1) as usize;
+    (limit + 1) * ratio
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-02 00:00 UTC, a Tuesday
+    const BASE: i64 = 1_704_153_600_000;
+    const MINUTE: i64 = 60_000;
+    const WEEK: u32 = 10_080;
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::try_from(value).unwrap()
+    }
+
+    fn bar(minute: i64, open: f64, high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle {
+            trade_count: Some(10),
+            ..Candle::new(BASE + minute * MINUTE, dec(open), dec(high), dec(low), dec(close), dec(volume))
+        }
+    }
+
+    fn trade(minute: i64, price: f64, quantity: f64, taker_buy: Option<bool>) -> Trade {
+        Trade {
+            timestamp: BASE + minute * MINUTE,
+            price: dec(price),
+            quantity: dec(quantity),
+            taker_buy,
+        }
+    }
+
+    #[test]
+    fn bucket_start_aligns_to_utc_and_monday() {
+        assert_eq!(Timeframe::H4.bucket_start(BASE + 330 * MINUTE), BASE + 240 * MINUTE);
+        assert_eq!(Timeframe::D1.bucket_start(BASE + 1_439 * MINUTE), BASE);
+        assert_eq!(Timeframe::M15.bucket_start(-MINUTE), -15 * MINUTE);
+
+        // Wednesday 12:00 and Monday 00:00 both fall in the week of Mon 2024-01-01
+        let week = Timeframe::Custom(WEEK);
+        let monday = BASE - 1_440 * MINUTE;
+        assert_eq!(week.bucket_start(BASE + 2_160 * MINUTE), monday);
+        assert_eq!(week.bucket_start(monday), monday);
+        assert_eq!(week.bucket_start(monday - 1), monday - 7 * 1_440 * MINUTE);
+    }
+
+    #[test]
+    fn resample_merges_bars_and_drops_leading_partial_bucket() {
+        let candles = vec![
+            // 00:05 and 00:10 belong to a bucket that started before the data
+            bar(10, 100.0, 101.0, 99.0, 100.5, 1.0),
+            bar(5, 100.0, 101.0, 99.0, 100.0, 1.0),
+            bar(15, 100.5, 102.0, 100.0, 101.0, 2.0),
+            bar(20, 101.0, 103.0, 100.5, 102.0, 3.0),
+            Candle { trade_count: None, ..bar(25, 102.0, 102.5, 99.5, 101.5, 4.0) },
+            bar(30, 101.5, 101.8, 101.0, 101.2, 5.0),
+        ];
+
+        let bars = resample(&candles, Timeframe::M5, Timeframe::M15).unwrap();
+        assert_eq!(bars.len(), 2);
+
+        let first = &bars[0];
+        assert_eq!(first.timestamp, BASE + 15 * MINUTE);
+        assert_eq!((first.open, first.high, first.low, first.close), (dec(100.5), dec(103.0), dec(99.5), dec(101.5)));
+        assert_eq!(first.volume, dec(9.0));
+        // One source bar without a trade count hides it for the whole bar
+        assert_eq!(first.trade_count, None);
+
+        // Trailing bucket is kept even while still incomplete
+        assert_eq!(bars[1].timestamp, BASE + 30 * MINUTE);
+        assert_eq!(bars[1].trade_count, Some(10));
+    }
+
+    #[test]
+    fn resample_rejects_uneven_timeframes() {
+        assert!(resample(&[], Timeframe::M15, Timeframe::Custom(20)).is_err());
+        assert!(resample(&[], Timeframe::H1, Timeframe::M15).is_err());
+        assert!(resample(&[], Timeframe::D1, Timeframe::Custom(WEEK)).is_ok());
+        assert_eq!(best_source_timeframe(Timeframe::H4, &[Timeframe::M1, Timeframe::M15, Timeframe::Custom(90)]), Some(Timeframe::M15));
+        assert_eq!(source_limit(Timeframe::H1, Timeframe::M15, 10), 44);
+    }
+
+    #[test]
+    fn candles_from_trades_skip_empty_buckets() {
+        let trades = vec![
+            trade(1, 100.0, 1.0, Some(true)),
+            trade(0, 99.0, 2.0, Some(false)),
+            trade(3, 101.0, 1.0, Some(true)),
+            // Nothing traded 00:05-00:10
+            trade(11, 102.0, 1.0, Some(true)),
+            trade(12, 103.0, 1.0, None),
+        ];
+
+        let bars = candles_from_trades(&trades, Timeframe::M5);
+        assert_eq!(bars.iter().map(|b| b.timestamp).collect::<Vec<_>>(), vec![BASE, BASE + 10 * MINUTE]);
+
+        let first = &bars[0];
+        assert_eq!((first.open, first.high, first.low, first.close), (dec(99.0), dec(101.0), dec(99.0), dec(101.0)));
+        assert_eq!(first.volume, dec(4.0));
+        assert_eq!(first.quote_volume, Some(dec(399.0)));
+        assert_eq!(first.trade_count, Some(3));
+        assert_eq!(first.taker_buy_base_volume, Some(dec(2.0)));
+
+        // A trade without a taker side makes the bar's split unknown
+        assert_eq!(bars[1].taker_buy_base_volume, None);
+    }
+}
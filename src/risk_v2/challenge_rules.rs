@@ -0,0 +1,438 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengePhase {
+    OneStep,
+    TwoStepPhase1,
+    TwoStepPhase2,
+    Funded,
+}
+
+impl std::str::FromStr for ChallengePhase {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().replace('_', "-").as_str() {
+            "one-step" | "1-step" => Ok(ChallengePhase::OneStep),
+            "two-step-1" | "two-step-phase-1" | "phase-1" => Ok(ChallengePhase::TwoStepPhase1),
+            "two-step-2" | "two-step-phase-2" | "phase-2" => Ok(ChallengePhase::TwoStepPhase2),
+            "funded" => Ok(ChallengePhase::Funded),
+            other => bail!("unknown challenge phase '{}', expected one-step, two-step-1, two-step-2 or funded", other),
+        }
+    }
+}
+
+// Firm limits of one phase, all in percent of the initial balance except
+// the daily loss, which is measured from the day's starting equity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleLimits {
+    // None once funded
+    pub profit_target_percent: Option<f64>,
+    pub max_daily_loss_percent: f64,
+    pub max_drawdown_percent: f64,
+    pub min_trading_days: u32,
+    pub max_risk_per_trade_percent: f64,
+    pub stop_loss_required: bool,
+}
+
+impl RuleLimits {
+    // HyroTrader defaults per phase
+    pub fn for_phase(phase: ChallengePhase) -> Self {
+        let target = match phase {
+            ChallengePhase::OneStep | ChallengePhase::TwoStepPhase1 => Some(10.0),
+            ChallengePhase::TwoStepPhase2 => Some(5.0),
+            ChallengePhase::Funded => None,
+        };
+
+        Self {
+            profit_target_percent: target,
+            max_daily_loss_percent: 5.0,
+            max_drawdown_percent: 10.0,
+            min_trading_days: if phase == ChallengePhase::Funded { 0 } else { 10 },
+            max_risk_per_trade_percent: 3.0,
+            stop_loss_required: true,
+        }
+    }
+}
+
+// Account figures the rules are checked against
+#[derive(Debug, Clone, Copy)]
+pub struct AccountState {
+    pub initial_balance: f64,
+    pub equity: f64,
+    // Today's loss so far, percent of the day's starting equity
+    pub daily_loss_percent: f64,
+    pub day_start_equity: f64,
+    // Further loss if every open position hit its stop now, in the account
+    // currency; profit locked in by trailed stops offsets it, never below 0
+    pub open_risk_at_stop: f64,
+    pub valid_trading_days: u32,
+}
+
+impl AccountState {
+    pub fn drawdown_percent(&self) -> f64 {
+        if self.initial_balance <= 0.0 {
+            return 0.0;
+        }
+        ((self.initial_balance - self.equity) / self.initial_balance * 100.0).max(0.0)
+    }
+
+    pub fn profit_percent(&self) -> f64 {
+        if self.initial_balance <= 0.0 {
+            return 0.0;
+        }
+        (self.equity - self.initial_balance) / self.initial_balance * 100.0
+    }
+}
+
+// Order about to be sent, with its loss if the stop is hit
+#[derive(Debug, Clone)]
+pub struct ProposedOrder {
+    pub symbol: String,
+    pub entry_price: f64,
+    pub stop_loss: Option<f64>,
+    pub risk_percent: f64,
+    pub loss_at_stop: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleKind {
+    DailyLoss,
+    MaxDrawdown,
+    RiskPerTrade,
+    StopLoss,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ViolationKind {
+    // An order was refused before it could breach the rule
+    Rejected,
+    // The account is past the limit
+    Breached,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleViolation {
+    pub phase: ChallengePhase,
+    pub rule: RuleKind,
+    pub kind: ViolationKind,
+    pub symbol: Option<String>,
+    pub value: f64,
+    pub limit: f64,
+    pub reason: String,
+    pub timestamp: i64,
+}
+
+// How far the account is from one limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitStatus {
+    pub name: String,
+    pub value: f64,
+    pub limit: f64,
+    // Room left before a loss limit, or what is still missing for a goal
+    pub remaining: f64,
+}
+
+impl std::fmt::Display for LimitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:.2}/{:.2} ({:.2} left)", self.name, self.value, self.limit, self.remaining)
+    }
+}
+
+// The prop firm's rules for the current phase: pre-trade checks that refuse
+// orders which could break a rule, and live checks of the account
+#[derive(Debug, Clone)]
+pub struct ChallengeRules {
+    pub phase: ChallengePhase,
+    pub limits: RuleLimits,
+    // Breaches already reported, so each is logged once per episode
+    breached: Vec<RuleKind>,
+}
+
+impl ChallengeRules {
+    pub fn new(phase: ChallengePhase) -> Self {
+        Self::with_limits(phase, RuleLimits::for_phase(phase))
+    }
+
+    pub fn with_limits(phase: ChallengePhase, limits: RuleLimits) -> Self {
+        Self {
+            phase,
+            limits,
+            breached: Vec::new(),
+        }
+    }
+
+    // One line per rule, for the startup log
+    pub fn describe(&self) -> Vec<String> {
+        let limits = &self.limits;
+        let mut lines = vec![format!("Phase: {:?}", self.phase)];
+        if let Some(target) = limits.profit_target_percent {
+            lines.push(format!("Profit target: {}%", target));
+        }
+        lines.push(format!("Max daily loss: < {}%", limits.max_daily_loss_percent));
+        lines.push(format!("Max total drawdown: < {}%", limits.max_drawdown_percent));
+        if limits.min_trading_days > 0 {
+            lines.push(format!("Min trading days: {}", limits.min_trading_days));
+        }
+        lines.push(format!("Max risk per trade: {}%", limits.max_risk_per_trade_percent));
+        if limits.stop_loss_required {
+            lines.push("Stop loss required on every trade".to_string());
+        }
+        lines
+    }
+
+    // Refuses the order if it lacks a stop or if hitting its stop, with every
+    // open position stopped out too, could break the per-trade, daily loss or
+    // drawdown limit
+    pub fn check_order(&self, account: &AccountState, order: &ProposedOrder, now: i64) -> Result<(), RuleViolation> {
+        let limits = &self.limits;
+        let violation = |rule, value: f64, limit: f64, reason: String| RuleViolation {
+            phase: self.phase,
+            rule,
+            kind: ViolationKind::Rejected,
+            symbol: Some(order.symbol.clone()),
+            value,
+            limit,
+            reason,
+            timestamp: now,
+        };
+
+        if limits.stop_loss_required && order.stop_loss.is_none_or(|s| s <= 0.0 || s == order.entry_price) {
+            return Err(violation(RuleKind::StopLoss, 0.0, 0.0, format!("{} order without a stop loss", order.symbol)));
+        }
+
+        if order.risk_percent > limits.max_risk_per_trade_percent {
+            return Err(violation(
+                RuleKind::RiskPerTrade,
+                order.risk_percent,
+                limits.max_risk_per_trade_percent,
+                format!("risk {:.2}% above the {:.2}% per-trade cap", order.risk_percent, limits.max_risk_per_trade_percent),
+            ));
+        }
+
+        let loss_at_stops = account.open_risk_at_stop + order.loss_at_stop;
+        let day_loss = if account.day_start_equity > 0.0 {
+            account.daily_loss_percent + loss_at_stops / account.day_start_equity * 100.0
+        } else {
+            account.daily_loss_percent
+        };
+        if day_loss >= limits.max_daily_loss_percent {
+            return Err(violation(
+                RuleKind::DailyLoss,
+                day_loss,
+                limits.max_daily_loss_percent,
+                format!("stop would take the daily loss to {:.2}% (limit {:.2}%)", day_loss, limits.max_daily_loss_percent),
+            ));
+        }
+
+        let drawdown = if account.initial_balance > 0.0 {
+            account.drawdown_percent() + loss_at_stops / account.initial_balance * 100.0
+        } else {
+            0.0
+        };
+        if drawdown >= limits.max_drawdown_percent {
+            return Err(violation(
+                RuleKind::MaxDrawdown,
+                drawdown,
+                limits.max_drawdown_percent,
+                format!("stop would take the drawdown to {:.2}% (limit {:.2}%)", drawdown, limits.max_drawdown_percent),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Distance to every limit, worst first
+    pub fn status(&self, account: &AccountState) -> Vec<LimitStatus> {
+        let limits = &self.limits;
+        let mut status = vec![
+            LimitStatus {
+                name: "daily loss %".to_string(),
+                value: account.daily_loss_percent,
+                limit: limits.max_daily_loss_percent,
+                remaining: limits.max_daily_loss_percent - account.daily_loss_percent,
+            },
+            LimitStatus {
+                name: "drawdown %".to_string(),
+                value: account.drawdown_percent(),
+                limit: limits.max_drawdown_percent,
+                remaining: limits.max_drawdown_percent - account.drawdown_percent(),
+            },
+        ];
+        if let Some(target) = limits.profit_target_percent {
+            status.push(LimitStatus {
+                name: "profit %".to_string(),
+                value: account.profit_percent(),
+                limit: target,
+                remaining: (target - account.profit_percent()).max(0.0),
+            });
+        }
+        if limits.min_trading_days > 0 {
+            status.push(LimitStatus {
+                name: "trading days".to_string(),
+                value: account.valid_trading_days as f64,
+                limit: limits.min_trading_days as f64,
+                remaining: limits.min_trading_days.saturating_sub(account.valid_trading_days) as f64,
+            });
+        }
+        status
+    }
+
+    // Loss limits the account is past, each returned once until it recovers
+    pub fn monitor(&mut self, account: &AccountState, now: i64) -> Vec<RuleViolation> {
+        let checks = [
+            (RuleKind::DailyLoss, account.daily_loss_percent, self.limits.max_daily_loss_percent),
+            (RuleKind::MaxDrawdown, account.drawdown_percent(), self.limits.max_drawdown_percent),
+        ];
+
+        let mut violations = Vec::new();
+        for (rule, value, limit) in checks {
+            if value < limit {
+                self.breached.retain(|r| *r != rule);
+                continue;
+            }
+            if self.breached.contains(&rule) {
+                continue;
+            }
+
+            self.breached.push(rule);
+            violations.push(RuleViolation {
+                phase: self.phase,
+                rule,
+                kind: ViolationKind::Breached,
+                symbol: None,
+                value,
+                limit,
+                reason: format!("{:?} at {:.2}%, limit {:.2}%", rule, value, limit),
+                timestamp: now,
+            });
+        }
+        violations
+    }
+
+    pub fn target_reached(&self, account: &AccountState) -> bool {
+        self.limits.profit_target_percent.is_some_and(|t| account.profit_percent() >= t)
+            && account.valid_trading_days >= self.limits.min_trading_days
+    }
+}
+
+// Append-only JSON lines file of rule violations
+pub struct ViolationLog {
+    path: PathBuf,
+}
+
+impl ViolationLog {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn append(&self, violation: &RuleViolation) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(violation)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(equity: f64, daily_loss_percent: f64, open_risk_at_stop: f64) -> AccountState {
+        AccountState {
+            initial_balance: 10_000.0,
+            equity,
+            daily_loss_percent,
+            day_start_equity: 10_000.0,
+            open_risk_at_stop,
+            valid_trading_days: 0,
+        }
+    }
+
+    fn order(stop_loss: Option<f64>, risk_percent: f64, loss_at_stop: f64) -> ProposedOrder {
+        ProposedOrder {
+            symbol: "BTCUSDT".to_string(),
+            entry_price: 100.0,
+            stop_loss,
+            risk_percent,
+            loss_at_stop,
+        }
+    }
+
+    fn rejected_by(rules: &ChallengeRules, account: &AccountState, order: &ProposedOrder) -> Option<RuleKind> {
+        rules.check_order(account, order, 0).err().map(|v| v.rule)
+    }
+
+    #[test]
+    fn orders_need_a_stop_and_a_capped_risk() {
+        let rules = ChallengeRules::new(ChallengePhase::OneStep);
+        let flat = account(10_000.0, 0.0, 0.0);
+
+        assert_eq!(rejected_by(&rules, &flat, &order(None, 1.0, 100.0)), Some(RuleKind::StopLoss));
+        assert_eq!(rejected_by(&rules, &flat, &order(Some(100.0), 1.0, 100.0)), Some(RuleKind::StopLoss));
+        assert_eq!(rejected_by(&rules, &flat, &order(Some(98.0), 3.0, 300.0)), None);
+        assert_eq!(rejected_by(&rules, &flat, &order(Some(98.0), 3.01, 301.0)), Some(RuleKind::RiskPerTrade));
+    }
+
+    #[test]
+    fn stops_of_open_positions_count_against_the_loss_limits() {
+        let rules = ChallengeRules::new(ChallengePhase::OneStep);
+        let entry = order(Some(98.0), 1.0, 100.0);
+
+        // 3.5% lost today + 1% for this stop stays under 5%
+        assert_eq!(rejected_by(&rules, &account(9_650.0, 3.5, 0.0), &entry), None);
+        // ...until 0.5% more sits at the stops of open positions
+        let violation = rules.check_order(&account(9_650.0, 3.5, 50.0), &entry, 0).unwrap_err();
+        assert_eq!((violation.rule, violation.kind), (RuleKind::DailyLoss, ViolationKind::Rejected));
+        assert!((violation.value - 5.0).abs() < 1e-9);
+
+        // 8% down overall, a fresh day: 8 + 1 + 0.5 stays under 10%, 8 + 1 + 1 does not
+        assert_eq!(rejected_by(&rules, &account(9_200.0, 0.0, 50.0), &entry), None);
+        assert_eq!(rejected_by(&rules, &account(9_200.0, 0.0, 100.0), &entry), Some(RuleKind::MaxDrawdown));
+    }
+
+    #[test]
+    fn breaches_are_reported_once_per_episode() {
+        let mut rules = ChallengeRules::new(ChallengePhase::OneStep);
+
+        let breached = rules.monitor(&account(9_500.0, 5.0, 0.0), 0);
+        assert_eq!(breached.len(), 1);
+        assert_eq!((breached[0].rule, breached[0].kind), (RuleKind::DailyLoss, ViolationKind::Breached));
+        assert!(rules.monitor(&account(9_400.0, 6.0, 0.0), 1).is_empty());
+
+        // Recovering re-arms the rule
+        assert!(rules.monitor(&account(9_600.0, 4.0, 0.0), 2).is_empty());
+        assert_eq!(rules.monitor(&account(9_500.0, 5.0, 0.0), 3).len(), 1);
+
+        let both = rules.monitor(&account(9_000.0, 5.0, 0.0), 4);
+        assert_eq!(both.iter().map(|v| v.rule).collect::<Vec<_>>(), vec![RuleKind::MaxDrawdown]);
+    }
+
+    #[test]
+    fn target_needs_the_profit_and_the_trading_days() {
+        let rules = ChallengeRules::new(ChallengePhase::TwoStepPhase2);
+        let mut state = account(10_500.0, 0.0, 0.0);
+
+        state.valid_trading_days = 9;
+        assert!(!rules.target_reached(&state));
+        state.valid_trading_days = 10;
+        assert!(rules.target_reached(&state));
+        state.equity = 10_499.0;
+        assert!(!rules.target_reached(&state));
+
+        // Funded accounts have no target
+        state.equity = 20_000.0;
+        assert!(!ChallengeRules::new(ChallengePhase::Funded).target_reached(&state));
+    }
+}
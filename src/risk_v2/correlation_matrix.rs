@@ -1,28 +1,192 @@
+use anyhow::{bail, Result};
 use std::collections::HashMap;
+use crate::types::{paired_log_returns, MarketData, Timeframe, TrendDirection};
 
+// Log returns of this many bars of `timeframe`, refreshed hourly
+pub const DEFAULT_WINDOW: usize = 100;
+pub const DEFAULT_TIMEFRAME: Timeframe = Timeframe::H1;
+pub const DEFAULT_REFRESH_MS: i64 = 60 * 60 * 1000;
+// Pairs at or above this |correlation| share exposure
+pub const DEFAULT_THRESHOLD: f64 = 0.7;
+// Correlated risk at stop allowed across positions, percent of equity
+pub const DEFAULT_MAX_CORRELATED_RISK: f64 = 1.5;
+// A new position cut below this share of its risk is rejected instead
+const MIN_SIZE_FACTOR: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrelationMethod {
+    Pearson,
+    // Pearson on ranks, less swayed by a few large moves
+    Spearman,
+}
+
+impl std::str::FromStr for CorrelationMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "pearson" => Ok(CorrelationMethod::Pearson),
+            "spearman" => Ok(CorrelationMethod::Spearman),
+            other => bail!("unknown correlation method '{}', expected pearson or spearman", other),
+        }
+    }
+}
+
+// An open position as the exposure check sees it
+#[derive(Debug, Clone)]
+pub struct Exposure {
+    pub symbol: String,
+    pub direction: TrendDirection,
+    // Loss at the stop, percent of equity
+    pub risk_percent: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExposureVerdict {
+    Allow,
+    // Keep the entry at `factor` of its risk
+    Reduce { factor: f64, reason: String },
+    Reject { reason: String },
+}
+
+// Rolling correlation of log returns between every pair of tracked symbols
 pub struct CorrelationMatrix {
+    method: CorrelationMethod,
+    window: usize,
+    timeframe: Timeframe,
+    refresh_ms: i64,
+    threshold: f64,
+    max_correlated_risk: f64,
     correlations: HashMap<(String, String), f64>,
+    updated_at: Option<i64>,
 }
 
 impl CorrelationMatrix {
     pub fn new() -> Self {
         Self {
+            method: CorrelationMethod::Pearson,
+            window: DEFAULT_WINDOW,
+            timeframe: DEFAULT_TIMEFRAME,
+            refresh_ms: DEFAULT_REFRESH_MS,
+            threshold: DEFAULT_THRESHOLD,
+            max_correlated_risk: DEFAULT_MAX_CORRELATED_RISK,
             correlations: HashMap::new(),
+            updated_at: None,
         }
     }
 
-    pub fn calculate_correlation(&mut self, _symbol1: &str, _symbol2: &str) -> f64 {
-        // TODO: Implement correlation calculation
-        0.0
+    pub fn with_method(mut self, method: CorrelationMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_window(mut self, window: usize, timeframe: Timeframe) -> Self {
+        self.window = window;
+        self.timeframe = timeframe;
+        self
+    }
+
+    pub fn with_refresh_interval(mut self, refresh_ms: i64) -> Self {
+        self.refresh_ms = refresh_ms;
+        self
+    }
+
+    pub fn with_exposure_limit(mut self, threshold: f64, max_correlated_risk: f64) -> Self {
+        self.threshold = threshold;
+        self.max_correlated_risk = max_correlated_risk;
+        self
+    }
+
+    pub fn timeframe(&self) -> Timeframe {
+        self.timeframe
+    }
+
+    // Bars to fetch per symbol for one refresh
+    pub fn bars_needed(&self) -> usize {
+        self.window + 1
+    }
+
+    pub fn needs_refresh(&self, now: i64) -> bool {
+        self.updated_at.is_none_or(|t| now - t >= self.refresh_ms)
+    }
+
+    // Recomputes every pair from closed bars of the matrix timeframe
+    pub fn update(&mut self, series: &HashMap<String, MarketData>, now: i64) {
+        let mut symbols: Vec<&String> = series.keys().collect();
+        symbols.sort();
+
+        self.correlations.clear();
+        for (i, a) in symbols.iter().enumerate() {
+            for b in &symbols[i + 1..] {
+                if let Some(correlation) = rolling_correlation(&series[*a], &series[*b], self.window, self.method) {
+                    self.correlations.insert(((*a).clone(), (*b).clone()), correlation);
+                }
+            }
+        }
+        self.updated_at = Some(now);
+    }
+
+    pub fn correlation(&self, symbol1: &str, symbol2: &str) -> Option<f64> {
+        if symbol1 == symbol2 {
+            return Some(1.0);
+        }
+        self.correlations.get(&pair_key(symbol1, symbol2)).copied()
     }
 
     pub fn are_highly_correlated(&self, symbol1: &str, symbol2: &str, threshold: f64) -> bool {
-        let key = (symbol1.to_string(), symbol2.to_string());
-        self.correlations
-            .get(&key)
-            .map(|&corr| corr.abs() > threshold)
+        self.correlation(symbol1, symbol2)
+            .map(|corr| corr.abs() > threshold)
             .unwrap_or(false)
     }
+
+    pub fn pairs(&self) -> impl Iterator<Item = (&(String, String), &f64)> {
+        self.correlations.iter()
+    }
+
+    // Risk the new entry would add to its correlated group: positions in the
+    // same direction and positively correlated add up, opposite positions
+    // on a correlated symbol offset. Above the cap the entry is shrunk to
+    // fit, or rejected if too little of it would be left.
+    pub fn check_exposure(&self, symbol: &str, direction: TrendDirection, risk_percent: f64, positions: &[Exposure]) -> ExposureVerdict {
+        let mut existing = 0.0;
+        let mut correlated = Vec::new();
+        for position in positions {
+            let Some(correlation) = self.correlation(symbol, &position.symbol) else {
+                continue;
+            };
+            if correlation.abs() < self.threshold {
+                continue;
+            }
+
+            let same_side = if position.direction == direction { 1.0 } else { -1.0 };
+            existing += correlation * same_side * position.risk_percent;
+            correlated.push(format!("{} {:+.2}", position.symbol, correlation));
+        }
+
+        if correlated.is_empty() || existing + risk_percent <= self.max_correlated_risk {
+            return ExposureVerdict::Allow;
+        }
+
+        let room = self.max_correlated_risk - existing;
+        let factor = room / risk_percent;
+        let group = correlated.join(", ");
+        if factor < MIN_SIZE_FACTOR {
+            return ExposureVerdict::Reject {
+                reason: format!(
+                    "correlated risk {:.2}% with {} leaves no room under the {:.2}% cap",
+                    existing, group, self.max_correlated_risk
+                ),
+            };
+        }
+
+        ExposureVerdict::Reduce {
+            factor,
+            reason: format!(
+                "correlated with {}, risk cut to {:.2}% to stay under the {:.2}% cap",
+                group, room, self.max_correlated_risk
+            ),
+        }
+    }
 }
 
 impl Default for CorrelationMatrix {
@@ -30,3 +194,161 @@ impl Default for CorrelationMatrix {
         Self::new()
     }
 }
+
+fn pair_key(symbol1: &str, symbol2: &str) -> (String, String) {
+    if symbol1 <= symbol2 {
+        (symbol1.to_string(), symbol2.to_string())
+    } else {
+        (symbol2.to_string(), symbol1.to_string())
+    }
+}
+
+// Correlation of log returns over the last `window` bars both series share
+pub fn rolling_correlation(a: &MarketData, b: &MarketData, window: usize, method: CorrelationMethod) -> Option<f64> {
+    let (x, y) = paired_log_returns(a, b, window)?;
+
+    match method {
+        CorrelationMethod::Pearson => pearson(&x, &y),
+        CorrelationMethod::Spearman => pearson(&ranks(&x), &ranks(&y)),
+    }
+}
+
+pub fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    let n = x.len().min(y.len());
+    if n < 2 {
+        return None;
+    }
+
+    let mean_x = x[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = y[..n].iter().sum::<f64>() / n as f64;
+    let mut covariance = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for i in 0..n {
+        covariance += (x[i] - mean_x) * (y[i] - mean_y);
+        var_x += (x[i] - mean_x).powi(2);
+        var_y += (y[i] - mean_y).powi(2);
+    }
+
+    (var_x > 0.0 && var_y > 0.0).then(|| covariance / (var_x * var_y).sqrt())
+}
+
+// 1-based ranks, ties share their average rank
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        for index in &order[i..=j] {
+            ranks[*index] = rank;
+        }
+        i = j + 1;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Candle;
+    use rust_decimal::Decimal;
+
+    const H1: i64 = 60 * 60_000;
+
+    fn series(symbol: &str, bars: &[(i64, f64)]) -> MarketData {
+        let dec = |value: f64| Decimal::try_from(value).unwrap();
+        let candles = bars
+            .iter()
+            .map(|&(ts, close)| Candle::new(ts, dec(close), dec(close), dec(close), dec(close), dec(1.0)))
+            .collect();
+        MarketData {
+            symbol: symbol.to_string(),
+            candles,
+            timeframe: Timeframe::H1,
+        }
+    }
+
+    fn matrix(pairs: &[(&str, &str, f64)]) -> CorrelationMatrix {
+        let mut matrix = CorrelationMatrix::new().with_exposure_limit(0.7, 1.5);
+        for (a, b, correlation) in pairs {
+            matrix.correlations.insert(pair_key(a, b), *correlation);
+        }
+        matrix
+    }
+
+    fn position(symbol: &str, direction: TrendDirection, risk_percent: f64) -> Exposure {
+        Exposure {
+            symbol: symbol.to_string(),
+            direction,
+            risk_percent,
+        }
+    }
+
+    #[test]
+    fn pearson_and_ranks_match_hand_computed_values() {
+        // cov 11, var_x 5, var_y 26
+        let r = pearson(&[1.0, 2.0, 3.0, 4.0], &[2.0, 4.0, 5.0, 9.0]).unwrap();
+        assert!((r - 11.0 / 130f64.sqrt()).abs() < 1e-12);
+        assert!((pearson(&[1.0, 2.0, 3.0], &[6.0, 4.0, 2.0]).unwrap() + 1.0).abs() < 1e-12);
+        assert!(pearson(&[1.0, 1.0, 1.0], &[1.0, 2.0, 3.0]).is_none());
+        assert!(pearson(&[1.0], &[1.0]).is_none());
+
+        assert_eq!(ranks(&[3.0, 1.0, 3.0, 2.0]), vec![3.5, 1.0, 3.5, 2.0]);
+        assert_eq!(ranks(&[5.0, 5.0, 5.0]), vec![2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn rolling_correlation_pairs_bars_by_timestamp() {
+        let a: Vec<(i64, f64)> = (0..40).map(|i| (i * H1, 100.0 + ((i * 7) % 11) as f64)).collect();
+
+        // Same returns, one bar missing and off-grid bars that must be ignored
+        let mut b: Vec<(i64, f64)> = a.iter().filter(|(ts, _)| *ts != 10 * H1).map(|&(ts, c)| (ts, c * 2.0)).collect();
+        b.extend((0..40).map(|i| (i * H1 + H1 / 2, if i % 2 == 0 { 50.0 } else { 500.0 })));
+        b.sort_by_key(|bar| bar.0);
+        let correlation = rolling_correlation(&series("A", &a), &series("B", &b), DEFAULT_WINDOW, CorrelationMethod::Pearson).unwrap();
+        assert!((correlation - 1.0).abs() < 1e-9, "{}", correlation);
+
+        // Inverse prices have negated log returns
+        let inverse: Vec<(i64, f64)> = a.iter().map(|&(ts, c)| (ts, 10_000.0 / c)).collect();
+        let correlation = rolling_correlation(&series("A", &a), &series("C", &inverse), DEFAULT_WINDOW, CorrelationMethod::Spearman).unwrap();
+        assert!((correlation + 1.0).abs() < 1e-9, "{}", correlation);
+
+        // Under MIN_PAIRED_RETURNS shared returns
+        assert!(rolling_correlation(&series("A", &a[..30]), &series("B", &b), DEFAULT_WINDOW, CorrelationMethod::Pearson).is_none());
+    }
+
+    #[test]
+    fn correlated_exposure_is_capped() {
+        let matrix = matrix(&[("SOLUSDT", "ETHUSDT", 0.75), ("SOLUSDT", "AVAXUSDT", 0.75), ("SOLUSDT", "BNBUSDT", -0.75), ("SOLUSDT", "XRPUSDT", 0.5)]);
+        let long = TrendDirection::Long;
+        let short = TrendDirection::Short;
+
+        // 0.75 x 0.5 + 1.0 stays under 1.5; weakly correlated positions are ignored
+        assert_eq!(matrix.check_exposure("SOLUSDT", long, 1.0, &[position("ETHUSDT", long, 0.5), position("XRPUSDT", long, 5.0)]), ExposureVerdict::Allow);
+
+        // 0.75 already used leaves 0.75 of a 1.0 entry
+        let eth = [position("ETHUSDT", long, 1.0)];
+        match matrix.check_exposure("SOLUSDT", long, 1.0, &eth) {
+            ExposureVerdict::Reduce { factor, .. } => assert_eq!(factor, 0.75),
+            other => panic!("expected reduce, got {:?}", other),
+        }
+
+        // Short on a negatively correlated symbol adds up the same way
+        assert!(matches!(matrix.check_exposure("SOLUSDT", long, 1.0, &[position("BNBUSDT", short, 1.0)]), ExposureVerdict::Reduce { .. }));
+
+        // A short on a positively correlated symbol offsets the long
+        let hedged = [position("ETHUSDT", long, 1.0), position("AVAXUSDT", short, 1.0)];
+        assert_eq!(matrix.check_exposure("SOLUSDT", long, 1.0, &hedged), ExposureVerdict::Allow);
+
+        // Rejected once less than MIN_SIZE_FACTOR of the entry would fit
+        assert!(matches!(matrix.check_exposure("SOLUSDT", long, 3.0, &eth), ExposureVerdict::Reduce { factor, .. } if factor == MIN_SIZE_FACTOR));
+        assert!(matches!(matrix.check_exposure("SOLUSDT", long, 3.1, &eth), ExposureVerdict::Reject { .. }));
+    }
+}
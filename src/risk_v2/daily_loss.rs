@@ -0,0 +1,313 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+// Share of the daily limit at which a warning goes out, in percent
+pub const DEFAULT_WARNING_LEVELS: [f64; 2] = [50.0, 80.0];
+
+// When the prop firm's trading day starts, UTC midnight by default. Kept in
+// the firm's time zone so a boundary like 00:00 CET follows DST.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayBoundary {
+    timezone: Tz,
+    time: NaiveTime,
+}
+
+impl DayBoundary {
+    pub fn new(timezone: Tz, time: NaiveTime) -> Self {
+        Self { timezone, time }
+    }
+
+    // "Europe/Prague" and "00:00"
+    pub fn parse(timezone: &str, time: &str) -> Result<Self> {
+        let timezone: Tz = timezone.trim().parse()
+            .map_err(|_| anyhow::anyhow!("unknown time zone '{}'", timezone.trim()))?;
+        let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|_| anyhow::anyhow!("invalid day boundary '{}', expected HH:MM", time.trim()))?;
+        Ok(Self::new(timezone, time))
+    }
+
+    // Trading day `at` falls in, named by the local date it started on
+    pub fn trading_day(&self, at: DateTime<Utc>) -> NaiveDate {
+        let local = at.with_timezone(&self.timezone);
+        if local.time() >= self.time {
+            local.date_naive()
+        } else {
+            local.date_naive() - Duration::days(1)
+        }
+    }
+
+    pub fn day_start(&self, day: NaiveDate) -> DateTime<Utc> {
+        // A boundary skipped by a DST jump starts the day an hour later
+        self.timezone
+            .from_local_datetime(&day.and_time(self.time))
+            .earliest()
+            .or_else(|| self.timezone.from_local_datetime(&(day.and_time(self.time) + Duration::hours(1))).earliest())
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|| day.and_time(self.time).and_utc())
+    }
+
+    pub fn next_start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        self.day_start(self.trading_day(at) + Duration::days(1))
+    }
+}
+
+impl Default for DayBoundary {
+    fn default() -> Self {
+        Self::new(chrono_tz::UTC, NaiveTime::MIN)
+    }
+}
+
+// Equity at the day boundary, unrealized P&L of positions carried over included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySnapshot {
+    pub day: NaiveDate,
+    pub taken_at: DateTime<Utc>,
+    pub start_equity: Decimal,
+    pub start_unrealized: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub enum DailyLossEvent {
+    NewDay(DailySnapshot),
+    // `level` percent of the daily limit used up
+    Warning { level: f64, loss_percent: f64 },
+    // Limit hit: no new entries until the next boundary, positions closed
+    // when `flatten` is set
+    LimitHit { loss_percent: f64, flatten: bool },
+}
+
+// Realized plus unrealized P&L of the current prop-firm day against the
+// equity snapshot taken at its start
+#[derive(Debug, Clone)]
+pub struct DailyLossLedger {
+    boundary: DayBoundary,
+    max_loss_percent: f64,
+    warning_levels: Vec<f64>,
+    flatten_on_limit: bool,
+    snapshot: Option<DailySnapshot>,
+    realized: Decimal,
+    unrealized: Decimal,
+    warnings_sent: usize,
+    limit_hit: bool,
+}
+
+impl DailyLossLedger {
+    pub fn new(max_loss_percent: f64) -> Self {
+        Self {
+            boundary: DayBoundary::default(),
+            max_loss_percent,
+            warning_levels: DEFAULT_WARNING_LEVELS.to_vec(),
+            flatten_on_limit: false,
+            snapshot: None,
+            realized: Decimal::ZERO,
+            unrealized: Decimal::ZERO,
+            warnings_sent: 0,
+            limit_hit: false,
+        }
+    }
+
+    pub fn with_boundary(mut self, boundary: DayBoundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn with_warning_levels(mut self, mut levels: Vec<f64>) -> Self {
+        levels.sort_by(|a, b| a.total_cmp(b));
+        self.warning_levels = levels;
+        self
+    }
+
+    pub fn with_flatten_on_limit(mut self, flatten: bool) -> Self {
+        self.flatten_on_limit = flatten;
+        self
+    }
+
+    pub fn record_realized(&mut self, pnl: Decimal) {
+        self.realized += pnl;
+    }
+
+    // Call every cycle with the account balance and the open positions'
+    // unrealized P&L; rolls the day over at the boundary
+    pub fn update(&mut self, now: DateTime<Utc>, balance: Decimal, unrealized: Decimal) -> Vec<DailyLossEvent> {
+        let mut events = Vec::new();
+        self.unrealized = unrealized;
+
+        let day = self.boundary.trading_day(now);
+        if self.snapshot.as_ref().is_none_or(|s| s.day != day) {
+            let snapshot = DailySnapshot {
+                day,
+                taken_at: now,
+                start_equity: balance + unrealized,
+                start_unrealized: unrealized,
+            };
+            self.snapshot = Some(snapshot.clone());
+            self.realized = Decimal::ZERO;
+            self.warnings_sent = 0;
+            self.limit_hit = false;
+            events.push(DailyLossEvent::NewDay(snapshot));
+        }
+
+        let loss_percent = self.loss_percent();
+        if self.limit_hit {
+            return events;
+        }
+
+        if loss_percent >= self.max_loss_percent {
+            self.limit_hit = true;
+            events.push(DailyLossEvent::LimitHit {
+                loss_percent,
+                flatten: self.flatten_on_limit,
+            });
+            return events;
+        }
+
+        let used = if self.max_loss_percent > 0.0 { loss_percent / self.max_loss_percent * 100.0 } else { 0.0 };
+        let reached = self.warning_levels.iter().filter(|l| used >= **l).count();
+        if reached > self.warnings_sent {
+            self.warnings_sent = reached;
+            events.push(DailyLossEvent::Warning {
+                level: self.warning_levels[reached - 1],
+                loss_percent,
+            });
+        }
+
+        events
+    }
+
+    pub fn snapshot(&self) -> Option<&DailySnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    pub fn unrealized(&self) -> Decimal {
+        self.unrealized
+    }
+
+    // P&L since the boundary: closed today plus the change in open positions
+    pub fn pnl(&self) -> Decimal {
+        let start_unrealized = self.snapshot.as_ref().map_or(Decimal::ZERO, |s| s.start_unrealized);
+        self.realized + self.unrealized - start_unrealized
+    }
+
+    // Loss as a percent of the starting equity, 0 while the day is up
+    pub fn loss_percent(&self) -> f64 {
+        let Some(snapshot) = &self.snapshot else {
+            return 0.0;
+        };
+        if snapshot.start_equity <= Decimal::ZERO {
+            return 0.0;
+        }
+
+        let pnl = (self.pnl() / snapshot.start_equity * Decimal::from(100)).to_f64().unwrap_or(0.0);
+        (-pnl).max(0.0)
+    }
+
+    // Why new entries are refused today, if they are
+    pub fn blocked(&self) -> Option<String> {
+        self.limit_hit.then(|| {
+            format!(
+                "daily loss {:.2}% reached the {:.2}% limit, trading resumes at the next day boundary",
+                self.loss_percent(),
+                self.max_loss_percent
+            )
+        })
+    }
+
+    pub fn next_reset(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.boundary.next_start(now)
+    }
+}
+
+// "50,80" -> [50.0, 80.0], each a percent of the daily limit
+pub fn parse_warning_levels(spec: &str) -> Result<Vec<f64>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|e| {
+            let level: f64 = e.parse().map_err(|_| anyhow::anyhow!("invalid daily loss warning level '{}'", e))?;
+            if level <= 0.0 || level >= 100.0 {
+                bail!("daily loss warning level {} must be between 0 and 100", level);
+            }
+            Ok(level)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn utc_midnight_starts_the_next_day() {
+        let boundary = DayBoundary::default();
+        assert_eq!(boundary.trading_day(utc(2024, 3, 1, 23, 59, 59)), date(2024, 3, 1));
+        assert_eq!(boundary.trading_day(utc(2024, 3, 2, 0, 0, 0)), date(2024, 3, 2));
+        assert_eq!(boundary.next_start(utc(2024, 3, 1, 23, 59, 59)), utc(2024, 3, 2, 0, 0, 0));
+        assert_eq!(boundary.next_start(utc(2024, 3, 2, 0, 0, 0)), utc(2024, 3, 3, 0, 0, 0));
+    }
+
+    #[test]
+    fn local_boundary_follows_dst() {
+        // 00:00 Prague is 23:00 UTC in winter and 22:00 UTC in summer
+        let boundary = DayBoundary::parse("Europe/Prague", "00:00").unwrap();
+        assert_eq!(boundary.trading_day(utc(2024, 1, 10, 22, 59, 59)), date(2024, 1, 10));
+        assert_eq!(boundary.trading_day(utc(2024, 1, 10, 23, 0, 0)), date(2024, 1, 11));
+        assert_eq!(boundary.trading_day(utc(2024, 7, 10, 21, 59, 59)), date(2024, 7, 10));
+        assert_eq!(boundary.trading_day(utc(2024, 7, 10, 22, 0, 0)), date(2024, 7, 11));
+
+        // 02:30 does not exist on 2024-03-31, the day starts at 03:30 CEST
+        let skipped = DayBoundary::parse("Europe/Prague", "02:30").unwrap();
+        assert_eq!(skipped.day_start(date(2024, 3, 31)), utc(2024, 3, 31, 1, 30, 0));
+
+        assert!(DayBoundary::parse("Mars/Olympus", "00:00").is_err());
+        assert!(DayBoundary::parse("UTC", "24:00").is_err());
+    }
+
+    #[test]
+    fn ledger_warns_hits_the_limit_and_resets_at_the_boundary() {
+        let boundary = DayBoundary::parse("UTC", "17:00").unwrap();
+        let mut ledger = DailyLossLedger::new(5.0).with_boundary(boundary).with_flatten_on_limit(true);
+
+        // Day opens with 100 of unrealized profit already on the books
+        let events = ledger.update(utc(2024, 3, 1, 17, 0, 0), Decimal::from(9_900), Decimal::from(100));
+        assert!(matches!(events.as_slice(), [DailyLossEvent::NewDay(s)] if s.start_equity == Decimal::from(10_000)));
+
+        // Giving back the carried profit counts against today
+        ledger.record_realized(Decimal::from(-150));
+        let events = ledger.update(utc(2024, 3, 1, 20, 0, 0), Decimal::from(9_750), Decimal::from(-100));
+        assert_eq!(ledger.pnl(), Decimal::from(-350));
+        assert!(matches!(events.as_slice(), [DailyLossEvent::Warning { level, .. }] if *level == 50.0));
+
+        ledger.record_realized(Decimal::from(-250));
+        let events = ledger.update(utc(2024, 3, 2, 16, 59, 59), Decimal::from(9_500), Decimal::ZERO);
+        assert!(matches!(events.as_slice(), [DailyLossEvent::LimitHit { flatten: true, .. }]));
+        assert!(ledger.blocked().is_some());
+        assert!(ledger.update(utc(2024, 3, 2, 16, 59, 59), Decimal::from(9_500), Decimal::ZERO).is_empty());
+
+        // 17:00 UTC starts a new day from the current equity
+        let events = ledger.update(utc(2024, 3, 2, 17, 0, 0), Decimal::from(9_500), Decimal::ZERO);
+        assert!(matches!(events.as_slice(), [DailyLossEvent::NewDay(s)] if s.day == date(2024, 3, 2)));
+        assert!(ledger.blocked().is_none());
+        assert_eq!(ledger.loss_percent(), 0.0);
+        assert_eq!(ledger.next_reset(utc(2024, 3, 2, 17, 0, 0)), utc(2024, 3, 3, 17, 0, 0));
+    }
+
+    #[test]
+    fn warning_levels_must_be_inside_the_limit() {
+        assert_eq!(parse_warning_levels("50, 80").unwrap(), vec![50.0, 80.0]);
+        assert!(parse_warning_levels("0").is_err());
+        assert!(parse_warning_levels("100").is_err());
+        assert!(parse_warning_levels("half").is_err());
+    }
+}
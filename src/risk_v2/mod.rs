@@ -1,7 +1,17 @@
 pub mod adaptive_sizing;
 pub mod streak_detector;
 pub mod correlation_matrix;
+pub mod position_sizer;
+pub mod daily_loss;
+pub mod challenge_rules;
+pub mod trading_days;
+pub mod portfolio;
 
 pub use adaptive_sizing::{AdaptiveRiskManager, TradeResult};
 pub use streak_detector::StreakDetector;
-pub use correlation_matrix::CorrelationMatrix;
+pub use correlation_matrix::{CorrelationMatrix, CorrelationMethod, Exposure, ExposureVerdict};
+pub use position_sizer::{PositionSizer, SizingBreakdown};
+pub use daily_loss::{DailyLossLedger, DailyLossEvent, DailySnapshot, DayBoundary};
+pub use challenge_rules::{ChallengeRules, ChallengePhase, RuleLimits, RuleViolation, AccountState, ProposedOrder, ViolationLog};
+pub use trading_days::{TradingDayTracker, TradeEvent, ValidDayRule};
+pub use portfolio::{PortfolioManager, PortfolioLimits, PortfolioDecision, PositionExposure};
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::types::TrendDirection;
+
+pub const DEFAULT_MAX_POSITIONS: usize = 1;
+// Loss at the stop of all open positions together, percent of equity
+pub const DEFAULT_MAX_TOTAL_RISK: f64 = 2.0;
+// Net notional on one side, percent of equity
+pub const DEFAULT_MAX_NET_EXPOSURE: f64 = 300.0;
+// How long an entry over a limit waits for room before it is dropped
+pub const DEFAULT_ENTRY_QUEUE_MS: i64 = 15 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionExposure {
+    pub symbol: String,
+    pub direction: TrendDirection,
+    pub notional: f64,
+    // Loss if the current stop is hit, 0 once the stop locks in profit
+    pub risk_at_stop: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioLimits {
+    pub max_positions: usize,
+    pub max_total_risk_percent: f64,
+    pub max_net_exposure_percent: f64,
+}
+
+impl Default for PortfolioLimits {
+    fn default() -> Self {
+        Self {
+            max_positions: DEFAULT_MAX_POSITIONS,
+            max_total_risk_percent: DEFAULT_MAX_TOTAL_RISK,
+            max_net_exposure_percent: DEFAULT_MAX_NET_EXPOSURE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortfolioDecision {
+    Accept,
+    // Would fit once open positions close or shrink
+    Queue { reason: String },
+    // Would not fit even with nothing else open
+    Reject { reason: String },
+}
+
+// Open exposure per symbol and side, checked against the concurrent
+// position, total risk and net exposure limits
+#[derive(Debug, Clone, Default)]
+pub struct PortfolioManager {
+    limits: PortfolioLimits,
+    open: HashMap<String, PositionExposure>,
+}
+
+impl PortfolioManager {
+    pub fn new(limits: PortfolioLimits) -> Self {
+        Self {
+            limits,
+            open: HashMap::new(),
+        }
+    }
+
+    pub fn limits(&self) -> &PortfolioLimits {
+        &self.limits
+    }
+
+    // Replaces the open book with the bot's positions as they are now
+    pub fn sync_open(&mut self, exposures: Vec<PositionExposure>) {
+        self.open = exposures.into_iter().map(|e| (e.symbol.clone(), e)).collect();
+    }
+
+    // Books an accepted entry right away, so the next check in the same
+    // cycle sees it before the next sync
+    pub fn commit(&mut self, exposure: PositionExposure) {
+        self.open.insert(exposure.symbol.clone(), exposure);
+    }
+
+    pub fn open_count(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn total_risk(&self) -> f64 {
+        self.open.values().map(|e| e.risk_at_stop).sum()
+    }
+
+    // Notional long minus notional short, positive when net long
+    pub fn net_notional(&self) -> f64 {
+        self.open
+            .values()
+            .map(|e| match e.direction {
+                TrendDirection::Long => e.notional,
+                TrendDirection::Short => -e.notional,
+                TrendDirection::Neutral => 0.0,
+            })
+            .sum()
+    }
+
+    pub fn gross_notional(&self) -> f64 {
+        self.open.values().map(|e| e.notional).sum()
+    }
+
+    pub fn check(&self, candidate: &PositionExposure, equity: f64) -> PortfolioDecision {
+        if self.open.contains_key(&candidate.symbol) {
+            return PortfolioDecision::Reject {
+                reason: format!("{} position already open", candidate.symbol),
+            };
+        }
+        if equity <= 0.0 {
+            return PortfolioDecision::Reject { reason: "no equity".to_string() };
+        }
+
+        let max_risk = equity * self.limits.max_total_risk_percent / 100.0;
+        let max_net = equity * self.limits.max_net_exposure_percent / 100.0;
+        let sign = if candidate.direction == TrendDirection::Short { -1.0 } else { 1.0 };
+
+        // Limits the candidate breaks on its own can never be met by waiting
+        if candidate.risk_at_stop > max_risk {
+            return PortfolioDecision::Reject {
+                reason: format!("risk ${:.2} alone exceeds the {:.2}% total risk cap", candidate.risk_at_stop, self.limits.max_total_risk_percent),
+            };
+        }
+        if candidate.notional > max_net {
+            return PortfolioDecision::Reject {
+                reason: format!("notional ${:.0} alone exceeds the {:.0}% net exposure cap", candidate.notional, self.limits.max_net_exposure_percent),
+            };
+        }
+
+        if self.open.len() >= self.limits.max_positions {
+            return PortfolioDecision::Queue {
+                reason: format!("{} of {} positions open", self.open.len(), self.limits.max_positions),
+            };
+        }
+
+        let total_risk = self.total_risk() + candidate.risk_at_stop;
+        if total_risk > max_risk {
+            return PortfolioDecision::Queue {
+                reason: format!(
+                    "total risk at stop {:.2}% over the {:.2}% cap",
+                    total_risk / equity * 100.0,
+                    self.limits.max_total_risk_percent
+                ),
+            };
+        }
+
+        let net = (self.net_notional() + sign * candidate.notional).abs();
+        if net > max_net {
+            return PortfolioDecision::Queue {
+                reason: format!(
+                    "net {:?} exposure {:.0}% over the {:.0}% cap",
+                    candidate.direction,
+                    net / equity * 100.0,
+                    self.limits.max_net_exposure_percent
+                ),
+            };
+        }
+
+        PortfolioDecision::Accept
+    }
+
+    // "2/3 positions, risk 1.20%, net +150% / gross 250% of equity"
+    pub fn summary(&self, equity: f64) -> String {
+        let percent = |value: f64| if equity > 0.0 { value / equity * 100.0 } else { 0.0 };
+        format!(
+            "{}/{} positions, risk {:.2}%, net {:+.0}% / gross {:.0}% of equity",
+            self.open.len(),
+            self.limits.max_positions,
+            percent(self.total_risk()),
+            percent(self.net_notional()),
+            percent(self.gross_notional())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EQUITY: f64 = 10_000.0;
+
+    fn exposure(symbol: &str, direction: TrendDirection, notional: f64, risk_at_stop: f64) -> PositionExposure {
+        PositionExposure {
+            symbol: symbol.to_string(),
+            direction,
+            notional,
+            risk_at_stop,
+        }
+    }
+
+    // 2 positions, $200 at stop, $30,000 net on 10,000 of equity
+    fn manager(open: Vec<PositionExposure>) -> PortfolioManager {
+        let mut manager = PortfolioManager::new(PortfolioLimits {
+            max_positions: 2,
+            max_total_risk_percent: 2.0,
+            max_net_exposure_percent: 300.0,
+        });
+        manager.sync_open(open);
+        manager
+    }
+
+    fn queued(decision: PortfolioDecision) -> bool {
+        matches!(decision, PortfolioDecision::Queue { .. })
+    }
+
+    fn rejected(decision: PortfolioDecision) -> bool {
+        matches!(decision, PortfolioDecision::Reject { .. })
+    }
+
+    #[test]
+    fn entries_over_a_shared_limit_wait_for_room() {
+        let long = TrendDirection::Long;
+        let short = TrendDirection::Short;
+        let candidate = exposure("SOLUSDT", long, 10_000.0, 100.0);
+
+        assert_eq!(manager(vec![]).check(&candidate, EQUITY), PortfolioDecision::Accept);
+
+        let full = manager(vec![exposure("BTCUSDT", long, 1_000.0, 10.0), exposure("ETHUSDT", long, 1_000.0, 10.0)]);
+        assert!(queued(full.check(&candidate, EQUITY)));
+
+        let risky = manager(vec![exposure("BTCUSDT", long, 1_000.0, 150.0)]);
+        assert!(queued(risky.check(&candidate, EQUITY)));
+        assert_eq!(risky.check(&exposure("SOLUSDT", long, 10_000.0, 50.0), EQUITY), PortfolioDecision::Accept);
+
+        // Net exposure: longs add up, a short offsets them
+        let net_long = manager(vec![exposure("BTCUSDT", long, 25_000.0, 10.0)]);
+        assert!(queued(net_long.check(&candidate, EQUITY)));
+        assert_eq!(net_long.check(&exposure("SOLUSDT", short, 10_000.0, 100.0), EQUITY), PortfolioDecision::Accept);
+        assert_eq!(net_long.net_notional(), 25_000.0);
+    }
+
+    #[test]
+    fn entries_that_never_fit_are_rejected() {
+        let manager = manager(vec![exposure("SOLUSDT", TrendDirection::Long, 1_000.0, 10.0)]);
+
+        assert!(rejected(manager.check(&exposure("BTCUSDT", TrendDirection::Long, 1_000.0, 201.0), EQUITY)));
+        assert!(rejected(manager.check(&exposure("BTCUSDT", TrendDirection::Short, 30_001.0, 10.0), EQUITY)));
+        assert!(rejected(manager.check(&exposure("SOLUSDT", TrendDirection::Long, 1_000.0, 10.0), EQUITY)));
+        assert!(rejected(manager.check(&exposure("BTCUSDT", TrendDirection::Long, 1_000.0, 10.0), 0.0)));
+    }
+
+    #[test]
+    fn committed_entries_count_before_the_next_sync() {
+        let mut manager = manager(vec![]);
+        let first = exposure("BTCUSDT", TrendDirection::Long, 5_000.0, 120.0);
+        let second = exposure("ETHUSDT", TrendDirection::Long, 5_000.0, 120.0);
+
+        assert_eq!(manager.check(&first, EQUITY), PortfolioDecision::Accept);
+        manager.commit(first);
+        assert!(queued(manager.check(&second, EQUITY)));
+        assert_eq!((manager.open_count(), manager.total_risk()), (1, 120.0));
+        assert_eq!(manager.summary(EQUITY), "1/2 positions, risk 1.20%, net +50% / gross 50% of equity");
+    }
+}
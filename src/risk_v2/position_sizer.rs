@@ -0,0 +1,290 @@
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use crate::exchange::Instrument;
+
+// Bybit linear taker fee per side, as a fraction of notional
+pub const DEFAULT_TAKER_FEE: f64 = 0.00055;
+// Expected slippage per fill, in basis points of price
+pub const DEFAULT_SLIPPAGE_BPS: f64 = 2.0;
+pub const DEFAULT_MAX_LEVERAGE: f64 = 10.0;
+
+// How an order quantity was derived from the risk percent, step by step
+#[derive(Debug, Clone)]
+pub struct SizingBreakdown {
+    // Equity * risk percent, the most the trade may lose at its stop
+    pub risk_budget: Decimal,
+    pub stop_distance: Decimal,
+    // Loss per base unit at the stop: distance plus round-trip fees and slippage
+    pub loss_per_unit: Decimal,
+    // Quantity before caps and rounding, in base units
+    pub raw_quantity: Decimal,
+    // Order quantity in contracts, and the same in base units
+    pub contracts: Decimal,
+    pub quantity: Decimal,
+    pub notional: Decimal,
+    pub leverage: Decimal,
+    // Of the loss at the stop, what fees and slippage account for
+    pub fees: Decimal,
+    pub slippage: Decimal,
+    pub loss_at_stop: Decimal,
+    // One line per cap or rounding applied, or why the size is zero
+    pub adjustments: Vec<String>,
+}
+
+impl SizingBreakdown {
+    pub fn is_tradeable(&self) -> bool {
+        self.contracts > Decimal::ZERO
+    }
+
+    // Loss at the stop as a percent of the risk budget
+    pub fn budget_used_percent(&self) -> f64 {
+        if self.risk_budget.is_zero() {
+            return 0.0;
+        }
+        (self.loss_at_stop / self.risk_budget * Decimal::from(100)).to_f64().unwrap_or(0.0)
+    }
+}
+
+// Turns a risk percent into an order quantity for one instrument. The loss
+// at the stop, fees and slippage included, never exceeds the risk budget:
+// every rounding goes down and a size that cannot meet the exchange minimums
+// is rejected rather than rounded up.
+#[derive(Debug, Clone)]
+pub struct PositionSizer {
+    contract_size: Decimal,
+    qty_step: Decimal,
+    min_order_qty: Decimal,
+    min_notional: Decimal,
+    max_leverage: Decimal,
+    taker_fee: Decimal,
+    slippage_bps: Decimal,
+}
+
+impl PositionSizer {
+    pub fn new() -> Self {
+        Self {
+            contract_size: Decimal::ONE,
+            qty_step: Decimal::ZERO,
+            min_order_qty: Decimal::ZERO,
+            min_notional: Decimal::ZERO,
+            max_leverage: Decimal::try_from(DEFAULT_MAX_LEVERAGE).unwrap(),
+            taker_fee: Decimal::try_from(DEFAULT_TAKER_FEE).unwrap(),
+            slippage_bps: Decimal::try_from(DEFAULT_SLIPPAGE_BPS).unwrap(),
+        }
+    }
+
+    pub fn for_instrument(mut self, instrument: &Instrument) -> Self {
+        let dec = |value: f64| Decimal::try_from(value).unwrap_or_default();
+        if instrument.contract_size > 0.0 {
+            self.contract_size = dec(instrument.contract_size);
+        }
+        self.qty_step = dec(instrument.qty_step);
+        self.min_order_qty = dec(instrument.min_order_qty);
+        self.min_notional = dec(instrument.min_notional);
+        self
+    }
+
+    pub fn with_max_leverage(mut self, max_leverage: f64) -> Self {
+        self.max_leverage = Decimal::try_from(max_leverage).unwrap_or(self.max_leverage);
+        self
+    }
+
+    // Per side, as a fraction of notional
+    pub fn with_taker_fee(mut self, taker_fee: f64) -> Self {
+        self.taker_fee = Decimal::try_from(taker_fee).unwrap_or(self.taker_fee);
+        self
+    }
+
+    pub fn with_slippage_bps(mut self, slippage_bps: f64) -> Self {
+        self.slippage_bps = Decimal::try_from(slippage_bps).unwrap_or(self.slippage_bps);
+        self
+    }
+
+    // Base units rounded down to whole quantity steps, for partial closes
+    pub fn round_down(&self, quantity: Decimal) -> Decimal {
+        let step = self.qty_step * self.contract_size;
+        if step > Decimal::ZERO {
+            (quantity / step).floor() * step
+        } else {
+            quantity
+        }
+    }
+
+    pub fn size(&self, risk_percent: Decimal, equity: Decimal, entry_price: Decimal, stop_price: Decimal) -> SizingBreakdown {
+        let risk_budget = equity * risk_percent / Decimal::from(100);
+        let stop_distance = (entry_price - stop_price).abs();
+        // Both fills pay the taker fee and slip, the exit at the stop price
+        let fill_value = entry_price + stop_price;
+        let fee_per_unit = fill_value * self.taker_fee;
+        let slippage_per_unit = fill_value * self.slippage_bps / Decimal::from(10_000);
+        let loss_per_unit = stop_distance + fee_per_unit + slippage_per_unit;
+
+        let mut adjustments = Vec::new();
+        let raw_quantity = if loss_per_unit > Decimal::ZERO && risk_budget > Decimal::ZERO {
+            risk_budget / loss_per_unit
+        } else {
+            adjustments.push("no risk budget or stop distance".to_string());
+            Decimal::ZERO
+        };
+        let mut quantity = raw_quantity;
+
+        if entry_price > Decimal::ZERO && self.max_leverage > Decimal::ZERO {
+            let max_quantity = equity * self.max_leverage / entry_price;
+            if quantity > max_quantity {
+                adjustments.push(format!(
+                    "capped from {} to {} by {}x max leverage",
+                    quantity.round_dp(6),
+                    max_quantity.round_dp(6),
+                    self.max_leverage
+                ));
+                quantity = max_quantity;
+            }
+        }
+
+        let mut contracts = quantity / self.contract_size;
+        if self.qty_step > Decimal::ZERO {
+            let stepped = (contracts / self.qty_step).floor() * self.qty_step;
+            if stepped != contracts {
+                adjustments.push(format!(
+                    "rounded down from {} to {} contracts (step {})",
+                    contracts.round_dp(6),
+                    stepped,
+                    self.qty_step
+                ));
+            }
+            contracts = stepped;
+        }
+
+        if contracts > Decimal::ZERO && contracts < self.min_order_qty {
+            adjustments.push(format!(
+                "{} contracts below the minimum order of {}, rounding up would exceed the risk budget",
+                contracts,
+                self.min_order_qty
+            ));
+            contracts = Decimal::ZERO;
+        }
+
+        let notional = contracts * self.contract_size * entry_price;
+        if contracts > Decimal::ZERO && notional < self.min_notional {
+            adjustments.push(format!(
+                "notional {} below the minimum of {}",
+                notional.round_dp(2),
+                self.min_notional
+            ));
+            contracts = Decimal::ZERO;
+        }
+
+        let quantity = contracts * self.contract_size;
+        let notional = quantity * entry_price;
+
+        SizingBreakdown {
+            risk_budget,
+            stop_distance,
+            loss_per_unit,
+            raw_quantity,
+            contracts,
+            quantity,
+            notional,
+            leverage: if equity > Decimal::ZERO { notional / equity } else { Decimal::ZERO },
+            fees: quantity * fee_per_unit,
+            slippage: quantity * slippage_per_unit,
+            loss_at_stop: quantity * loss_per_unit,
+            adjustments,
+        }
+    }
+}
+
+impl Default for PositionSizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::InstrumentKind;
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::try_from(value).unwrap()
+    }
+
+    fn instrument(contract_size: f64, qty_step: f64, min_order_qty: f64, min_notional: f64) -> Instrument {
+        Instrument {
+            symbol: "SOLUSDT".to_string(),
+            base_asset: "SOL".to_string(),
+            quote_asset: "USDT".to_string(),
+            kind: InstrumentKind::LinearPerpetual,
+            is_trading: true,
+            tick_size: 0.01,
+            contract_size,
+            qty_step,
+            min_order_qty,
+            min_notional,
+        }
+    }
+
+    // 1% of 10,000 with the stop 2 below a 100 entry
+    fn size(sizer: &PositionSizer) -> SizingBreakdown {
+        sizer.size(Decimal::ONE, Decimal::from(10_000), Decimal::from(100), Decimal::from(98))
+    }
+
+    #[test]
+    fn fees_and_slippage_shrink_the_size_to_fit_the_budget() {
+        let frictionless = PositionSizer::new().with_taker_fee(0.0).with_slippage_bps(0.0);
+        let sizing = size(&frictionless);
+        assert_eq!(sizing.quantity, Decimal::from(50));
+        assert_eq!(sizing.loss_at_stop, Decimal::from(100));
+
+        // 2 + 198 x (0.00055 + 0.0002) per unit
+        let sizing = size(&PositionSizer::new());
+        assert_eq!(sizing.loss_per_unit, dec(2.1485));
+        assert!(sizing.quantity < Decimal::from(47) && sizing.quantity > Decimal::from(46));
+        assert!(sizing.loss_at_stop <= sizing.risk_budget);
+        assert!(sizing.fees > Decimal::ZERO && sizing.slippage > Decimal::ZERO);
+    }
+
+    #[test]
+    fn quantity_rounds_down_to_whole_steps() {
+        let sizing = size(&PositionSizer::new().for_instrument(&instrument(1.0, 0.1, 0.0, 0.0)));
+        assert_eq!(sizing.contracts, dec(46.5));
+        assert!(sizing.adjustments[0].contains("rounded down"));
+        assert!(sizing.loss_at_stop <= sizing.risk_budget);
+
+        // Contracts of 10 units, whole contracts only
+        let sizing = size(&PositionSizer::new().for_instrument(&instrument(10.0, 1.0, 0.0, 0.0)));
+        assert_eq!((sizing.contracts, sizing.quantity), (Decimal::from(4), Decimal::from(40)));
+
+        let sizer = PositionSizer::new().for_instrument(&instrument(10.0, 0.01, 0.0, 0.0));
+        assert_eq!(sizer.round_down(dec(1.234)), dec(1.2));
+        assert_eq!(sizer.round_down(dec(0.09)), Decimal::ZERO);
+        assert_eq!(PositionSizer::new().round_down(dec(1.234)), dec(1.234));
+    }
+
+    #[test]
+    fn sizes_under_the_exchange_minimums_are_rejected() {
+        let sizing = size(&PositionSizer::new().for_instrument(&instrument(1.0, 1.0, 50.0, 0.0)));
+        assert!(!sizing.is_tradeable());
+        assert_eq!(sizing.loss_at_stop, Decimal::ZERO);
+        assert!(sizing.adjustments.last().unwrap().contains("below the minimum order"));
+
+        let sizing = size(&PositionSizer::new().for_instrument(&instrument(1.0, 1.0, 1.0, 5_000.0)));
+        assert!(!sizing.is_tradeable());
+        assert!(sizing.adjustments.last().unwrap().contains("notional"));
+
+        let sizing = PositionSizer::new().size(Decimal::ZERO, Decimal::from(10_000), Decimal::from(100), Decimal::from(98));
+        assert!(!sizing.is_tradeable());
+    }
+
+    #[test]
+    fn tight_stops_are_capped_by_max_leverage() {
+        // A 0.1 stop would take 1,000 units, 10x the equity
+        let sizer = PositionSizer::new().with_taker_fee(0.0).with_slippage_bps(0.0).with_max_leverage(5.0);
+        let sizing = sizer.size(Decimal::ONE, Decimal::from(10_000), Decimal::from(100), dec(99.9));
+        assert_eq!(sizing.raw_quantity, Decimal::from(1_000));
+        assert_eq!(sizing.quantity, Decimal::from(500));
+        assert_eq!(sizing.leverage, Decimal::from(5));
+        assert!(sizing.adjustments[0].contains("max leverage"));
+        assert_eq!(sizing.budget_used_percent(), 50.0);
+    }
+}
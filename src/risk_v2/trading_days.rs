@@ -0,0 +1,164 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeEvent {
+    Opened,
+    Closed,
+}
+
+// What makes a UTC day count towards the minimum trading days
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidDayRule {
+    // A qualifying trade was opened that day
+    Opened,
+    // A qualifying trade was opened or closed that day
+    OpenedOrClosed,
+}
+
+impl std::str::FromStr for ValidDayRule {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "opened" => Ok(ValidDayRule::Opened),
+            "opened_or_closed" => Ok(ValidDayRule::OpenedOrClosed),
+            other => bail!("unknown valid day rule '{}', expected opened or opened_or_closed", other),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrackerState {
+    days: BTreeSet<NaiveDate>,
+}
+
+// Distinct UTC days with qualifying trades, saved after every new day so the
+// count survives restarts
+pub struct TradingDayTracker {
+    path: PathBuf,
+    rule: ValidDayRule,
+    // Trades risking less than this do not qualify
+    min_risk_percent: f64,
+    days: BTreeSet<NaiveDate>,
+}
+
+impl TradingDayTracker {
+    pub fn load(path: impl AsRef<Path>, rule: ValidDayRule, min_risk_percent: f64) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let state: TrackerState = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            TrackerState::default()
+        };
+
+        Ok(Self {
+            path,
+            rule,
+            min_risk_percent,
+            days: state.days,
+        })
+    }
+
+    // The day, when this trade made it a new valid day
+    pub fn record(&mut self, event: TradeEvent, at: DateTime<Utc>, risk_percent: f64) -> Result<Option<NaiveDate>> {
+        if risk_percent < self.min_risk_percent {
+            return Ok(None);
+        }
+        if event == TradeEvent::Closed && self.rule == ValidDayRule::Opened {
+            return Ok(None);
+        }
+
+        let day = at.date_naive();
+        if !self.days.insert(day) {
+            return Ok(None);
+        }
+
+        // Not counted until it is on disk, so the next trade retries the save
+        if let Err(e) = self.save() {
+            self.days.remove(&day);
+            return Err(e);
+        }
+        Ok(Some(day))
+    }
+
+    pub fn count(&self) -> u32 {
+        self.days.len() as u32
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = &NaiveDate> {
+        self.days.iter()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Written aside and renamed so a crash never leaves half a file
+        let state = TrackerState { days: self.days.clone() };
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string_pretty(&state)?)?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("trading_days_{}_{}", std::process::id(), name))
+            .join("trading_days.json")
+    }
+
+    fn utc(d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn days_survive_a_reload_and_count_once() {
+        let path = temp_path("round_trip");
+        let _ = std::fs::remove_file(&path);
+
+        let mut tracker = TradingDayTracker::load(&path, ValidDayRule::Opened, 0.25).unwrap();
+        assert_eq!(tracker.record(TradeEvent::Opened, utc(1, 9), 0.5).unwrap(), NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(tracker.record(TradeEvent::Opened, utc(1, 23), 0.5).unwrap(), None);
+        assert_eq!(tracker.record(TradeEvent::Opened, utc(2, 0), 0.1).unwrap(), None);
+        assert_eq!(tracker.record(TradeEvent::Closed, utc(2, 1), 0.5).unwrap(), None);
+        assert_eq!(tracker.count(), 1);
+
+        let mut reloaded = TradingDayTracker::load(&path, ValidDayRule::OpenedOrClosed, 0.25).unwrap();
+        assert_eq!(reloaded.count(), 1);
+        assert_eq!(reloaded.record(TradeEvent::Opened, utc(1, 12), 0.5).unwrap(), None);
+        assert!(reloaded.record(TradeEvent::Closed, utc(2, 1), 0.5).unwrap().is_some());
+        assert_eq!(TradingDayTracker::load(&path, ValidDayRule::Opened, 0.25).unwrap().count(), 2);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn corrupt_state_file_is_an_error_and_left_untouched() {
+        let path = temp_path("corrupt");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "{\"days\": [\"2024-03-01\"").unwrap();
+
+        assert!(TradingDayTracker::load(&path, ValidDayRule::Opened, 0.0).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"days\": [\"2024-03-01\"");
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn valid_day_rule_parses_both_spellings() {
+        assert_eq!("opened-or-closed".parse::<ValidDayRule>().unwrap(), ValidDayRule::OpenedOrClosed);
+        assert_eq!(" Opened ".parse::<ValidDayRule>().unwrap(), ValidDayRule::Opened);
+        assert!("filled".parse::<ValidDayRule>().is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tracing::info;
+use crate::execution_v2::{DynamicTPManager, SmartEntryManager, TakeProfitLevels};
+use crate::intelligence::{ConfluenceScorer, MarketRegime};
+use crate::types::{MultiTimeframeData, Timeframe, TrendDirection};
+use super::{log_order_book, EntryIntent, Fill, FillKind, Intent, Strategy, StrategyContext, StrategySettings};
+
+const SIGNAL_TIMEFRAME: Timeframe = Timeframe::M15;
+// M15 candles the Fibonacci swing is measured over
+const SWING_LOOKBACK: usize = 50;
+// Stop beyond the extreme of the last M5 candles plus a slice of M15 ATR
+const STOP_LOOKBACK: usize = 5;
+const STOP_ATR_BUFFER: f64 = 0.25;
+// Entries the bot did not fill by then may be sent again
+const PENDING_ENTRY_TTL_MS: i64 = 5 * 60 * 1000;
+
+struct ActiveTrade {
+    direction: TrendDirection,
+    targets: TakeProfitLevels,
+}
+
+// Trend setup from the strategy spec: M15 breakout and retest scored by
+// confluence, Fibonacci entry zone, M5 confirmation, ATR based targets
+pub struct BreakoutRetestStrategy {
+    settings: StrategySettings,
+    confluence_scorer: ConfluenceScorer,
+    entry_manager: SmartEntryManager,
+    tp_manager: DynamicTPManager,
+    // Entry sent to the bot, waiting for its fill, and when it was sent
+    pending: HashMap<String, (EntryIntent, i64)>,
+    trades: HashMap<String, ActiveTrade>,
+}
+
+impl BreakoutRetestStrategy {
+    pub fn new(confluence_scorer: ConfluenceScorer, risk_budget_percent: f64) -> Self {
+        Self {
+            settings: StrategySettings {
+                name: "breakout_retest".to_string(),
+                symbols: Vec::new(),
+                timeframes: MultiTimeframeData::DEFAULT_TIMEFRAMES.to_vec(),
+                bar_timeframe: Timeframe::M5,
+                risk_budget_percent,
+            },
+            entry_manager: SmartEntryManager::with_scorer(confluence_scorer.clone()),
+            confluence_scorer,
+            tp_manager: DynamicTPManager::new(),
+            pending: HashMap::new(),
+            trades: HashMap::new(),
+        }
+    }
+
+    async fn find_entry(&self, symbol: &str, data: &MultiTimeframeData) -> Option<EntryIntent> {
+        let confluence = self.confluence_scorer.calculate_score(data, SIGNAL_TIMEFRAME).await;
+        if !confluence.is_valid {
+            return None;
+        }
+
+        info!("🎯 Potential {:?} setup on {} - Confluence: {}/100 (long {}, short {}, neutral {})",
+            confluence.direction,
+            symbol,
+            confluence.total_score,
+            confluence.long_score,
+            confluence.short_score,
+            confluence.neutral_score
+        );
+        for signal in &confluence.signals {
+            info!("   {} {} [{:?}] ({}/{}): {}",
+                if signal.is_active { "✓" } else { "✗" },
+                signal.name,
+                signal.direction,
+                signal.score,
+                signal.weight,
+                signal.reason
+            );
+        }
+
+        let signal_data = data.aligned(SIGNAL_TIMEFRAME)?;
+        let swing = &signal_data.candles[signal_data.candles.len().saturating_sub(SWING_LOOKBACK)..];
+        let swing_high = swing.iter().map(|c| c.high).max()?;
+        let swing_low = swing.iter().map(|c| c.low).min()?;
+
+        let entry = self.entry_manager
+            .validate_entry(data, SIGNAL_TIMEFRAME, swing_high, swing_low, confluence.direction)
+            .await?;
+
+        let m5 = data.aligned(Timeframe::M5)?;
+        let recent = &m5.candles[m5.candles.len().saturating_sub(STOP_LOOKBACK)..];
+        let atr = signal_data.atr(14);
+        let buffer = atr * Decimal::try_from(STOP_ATR_BUFFER).unwrap();
+
+        let stop_loss = match entry.direction {
+            TrendDirection::Long => recent.iter().map(|c| c.low).min()? - buffer,
+            TrendDirection::Short => recent.iter().map(|c| c.high).max()? + buffer,
+            TrendDirection::Neutral => return None,
+        };
+
+        Some(EntryIntent {
+            symbol: symbol.to_string(),
+            direction: entry.direction,
+            entry_price: entry.price,
+            stop_loss,
+            targets: self.tp_manager.calculate_targets(entry.price, stop_loss, atr, entry.direction),
+            confluence_score: entry.confluence_score,
+            reason: format!("{:?} retest in {:?} Fibonacci zone", entry.direction, entry.fib_zone),
+        })
+    }
+
+    // Partial exits at TP1-3 and the trailing stop once TP1 is hit. The stop
+    // itself is enforced by the bot.
+    fn manage_trade(&mut self, symbol: &str, data: &MultiTimeframeData) -> Vec<Intent> {
+        let mut intents = Vec::new();
+        let (Some(trade), Some(m5)) = (self.trades.get_mut(symbol), data.aligned(Timeframe::M5)) else {
+            return intents;
+        };
+
+        let price = m5.close();
+        let direction = trade.direction;
+        let reached = |level: Decimal| match direction {
+            TrendDirection::Long => price >= level,
+            TrendDirection::Short => price <= level,
+            TrendDirection::Neutral => false,
+        };
+
+        let targets = &mut trade.targets;
+        for (name, tp) in [("TP1", &mut targets.tp1), ("TP2", &mut targets.tp2), ("TP3", &mut targets.tp3)] {
+            if !tp.hit && reached(tp.price) {
+                tp.hit = true;
+                intents.push(Intent::Exit {
+                    symbol: symbol.to_string(),
+                    price,
+                    size_percent: tp.size_percent,
+                    reason: format!("{} hit at {}", name, tp.price),
+                });
+            }
+        }
+
+        if targets.tp1.hit {
+            targets.trailing_stop.enabled = true;
+            let previous = targets.trailing_stop.current_level;
+            self.tp_manager.update_trailing_stop(targets, price, direction);
+
+            if targets.trailing_stop.current_level != previous {
+                intents.push(Intent::MoveStop {
+                    symbol: symbol.to_string(),
+                    stop_loss: targets.trailing_stop.current_level,
+                    reason: "Trailing stop".to_string(),
+                });
+            }
+        }
+
+        intents
+    }
+}
+
+#[async_trait]
+impl Strategy for BreakoutRetestStrategy {
+    fn settings(&self) -> &StrategySettings {
+        &self.settings
+    }
+
+    async fn on_bar(&mut self, data: &MultiTimeframeData, ctx: &StrategyContext) -> Vec<Intent> {
+        if ctx.has_position {
+            return self.manage_trade(&ctx.symbol, data);
+        }
+
+        // Closed by the bot without a fill reaching us, e.g. after a restart
+        self.trades.remove(&ctx.symbol);
+
+        if ctx.regime != Some(MarketRegime::Trending) || self.pending.contains_key(&ctx.symbol) {
+            return Vec::new();
+        }
+
+        match self.find_entry(&ctx.symbol, data).await {
+            Some(entry) => {
+                log_order_book(data, &entry);
+                self.pending.insert(ctx.symbol.clone(), (entry.clone(), ctx.now));
+                vec![Intent::Enter(entry)]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        match fill.kind {
+            FillKind::Open => {
+                // The fill carries the intent, so entries the bot queued past
+                // the pending TTL are still managed
+                let entry = self.pending.remove(&fill.symbol).map(|(entry, _)| entry);
+                if let Some(entry) = fill.entry.clone().or(entry) {
+                    self.trades.insert(fill.symbol.clone(), ActiveTrade {
+                        direction: entry.direction,
+                        targets: entry.targets,
+                    });
+                }
+            }
+            FillKind::Reduce => {}
+            FillKind::Close => {
+                self.trades.remove(&fill.symbol);
+            }
+        }
+
+        Vec::new()
+    }
+
+    fn on_timer(&mut self, now: i64) -> Vec<Intent> {
+        self.pending.retain(|_, (_, sent_at)| now - *sent_at < PENDING_ENTRY_TTL_MS);
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_v2::{TPLevel, TrailingStop};
+
+    fn entry() -> EntryIntent {
+        let level = |price: i64| TPLevel {
+            price: Decimal::from(price),
+            size_percent: Decimal::from(30),
+            hit: false,
+        };
+        EntryIntent {
+            symbol: "SOLUSDT".to_string(),
+            direction: TrendDirection::Long,
+            entry_price: Decimal::from(100),
+            stop_loss: Decimal::from(98),
+            targets: TakeProfitLevels {
+                tp1: level(103),
+                tp2: level(105),
+                tp3: level(108),
+                trailing_stop: TrailingStop {
+                    enabled: false,
+                    distance: Decimal::ONE,
+                    current_level: Decimal::from(98),
+                },
+            },
+            confluence_score: 80,
+            reason: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn entry_filled_after_the_pending_ttl_is_still_managed() {
+        let mut strategy = BreakoutRetestStrategy::new(ConfluenceScorer::new(70), 1.0);
+        strategy.pending.insert("SOLUSDT".to_string(), (entry(), 0));
+
+        // Queued by the bot for 6 minutes, past the strategy's own TTL
+        let six_minutes = 6 * 60 * 1000;
+        strategy.on_timer(six_minutes);
+        assert!(strategy.pending.is_empty());
+
+        strategy.on_fill(&Fill {
+            symbol: "SOLUSDT".to_string(),
+            order_id: "1".to_string(),
+            kind: FillKind::Open,
+            direction: TrendDirection::Long,
+            price: Decimal::from(100),
+            quantity: Decimal::ONE,
+            timestamp: six_minutes,
+            entry: Some(entry()),
+        });
+        let trade = strategy.trades.get("SOLUSDT").expect("filled entry is managed");
+        assert_eq!(trade.direction, TrendDirection::Long);
+        assert_eq!(trade.targets.tp1.price, Decimal::from(103));
+    }
+}
@@ -0,0 +1,145 @@
+pub mod breakout_retest;
+pub mod range_reversion;
+
+pub use breakout_retest::BreakoutRetestStrategy;
+pub use range_reversion::RangeReversionStrategy;
+
+use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use tracing::info;
+use crate::execution_v2::TakeProfitLevels;
+use crate::intelligence::{order_book, MarketRegime};
+use crate::types::{MultiTimeframeData, Timeframe, TrendDirection};
+
+// What a strategy trades and how much it may risk
+#[derive(Debug, Clone)]
+pub struct StrategySettings {
+    pub name: String,
+    // Empty means the ranker's active assets
+    pub symbols: Vec<String>,
+    pub timeframes: Vec<Timeframe>,
+    // on_bar fires once per closed bar of this timeframe
+    pub bar_timeframe: Timeframe,
+    // Cap on risk per trade, percent of balance
+    pub risk_budget_percent: f64,
+}
+
+impl StrategySettings {
+    pub fn trades(&self, symbol: &str, active_assets: &[String]) -> bool {
+        if self.symbols.is_empty() {
+            active_assets.iter().any(|s| s == symbol)
+        } else {
+            self.symbols.iter().any(|s| s == symbol)
+        }
+    }
+}
+
+// Bot state a strategy sees alongside the bars
+#[derive(Debug, Clone)]
+pub struct StrategyContext {
+    pub symbol: String,
+    pub regime: Option<MarketRegime>,
+    pub balance: Decimal,
+    // Position on `symbol` opened by this strategy
+    pub has_position: bool,
+    pub now: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryIntent {
+    pub symbol: String,
+    pub direction: TrendDirection,
+    pub entry_price: Decimal,
+    pub stop_loss: Decimal,
+    pub targets: TakeProfitLevels,
+    pub confluence_score: u8,
+    pub reason: String,
+}
+
+// What a strategy wants done; the bot sizes, checks and executes it
+#[derive(Debug, Clone)]
+pub enum Intent {
+    Enter(EntryIntent),
+    MoveStop {
+        symbol: String,
+        stop_loss: Decimal,
+        reason: String,
+    },
+    // Close `size_percent` of the position's original size
+    Exit {
+        symbol: String,
+        price: Decimal,
+        size_percent: Decimal,
+        reason: String,
+    },
+}
+
+// Walls this close to the entry or stop are reported with the entry
+const BOOK_WALL_RANGE_BPS: f64 = 20.0;
+
+// Book state next to an entry decision: imbalance, spread and resting walls
+// around the entry and the stop
+pub fn log_order_book(data: &MultiTimeframeData, entry: &EntryIntent) {
+    let Some(book) = data.order_book() else {
+        info!("   📖 No order book snapshot for {}", entry.symbol);
+        return;
+    };
+
+    info!("   📖 Book: imbalance {:+.2}, spread {:.2} bps, depth ${:.0}",
+        order_book::imbalance(book, order_book::IMBALANCE_LEVELS),
+        book.spread_bps().unwrap_or(0.0),
+        book.depth_usd(order_book::IMBALANCE_LEVELS)
+    );
+    for (label, price) in [("entry", entry.entry_price), ("stop", entry.stop_loss)] {
+        let price = price.to_f64().unwrap_or(0.0);
+        for wall in order_book::walls_near(book, price, BOOK_WALL_RANGE_BPS) {
+            info!("   🧱 {:?} wall {:.0}x median at {} (${:.0}), {:.1} bps from {}",
+                wall.side,
+                wall.size_multiple,
+                wall.price,
+                wall.notional,
+                wall.distance_bps(price),
+                label
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillKind {
+    Open,
+    Reduce,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub symbol: String,
+    pub order_id: String,
+    pub kind: FillKind,
+    // Side of the position, not of the order
+    pub direction: TrendDirection,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub timestamp: i64,
+    // Intent an Open fill came from, which may have waited in the bot's
+    // entry queue well after the strategy sent it
+    pub entry: Option<EntryIntent>,
+}
+
+#[async_trait]
+pub trait Strategy: Send + Sync {
+    fn settings(&self) -> &StrategySettings;
+
+    async fn on_bar(&mut self, data: &MultiTimeframeData, ctx: &StrategyContext) -> Vec<Intent>;
+
+    fn on_fill(&mut self, _fill: &Fill) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    // Called every bot cycle
+    fn on_timer(&mut self, _now: i64) -> Vec<Intent> {
+        Vec::new()
+    }
+}
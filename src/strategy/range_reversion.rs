@@ -0,0 +1,395 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tracing::info;
+use crate::execution_v2::{TPLevel, TakeProfitLevels, TrailingStop};
+use crate::intelligence::{MarketRegime, Vwap};
+use crate::types::{MarketData, MultiTimeframeData, Timeframe, TrendDirection, to_f64};
+use super::{log_order_book, EntryIntent, Fill, FillKind, Intent, Strategy, StrategyContext, StrategySettings};
+
+const SIGNAL_TIMEFRAME: Timeframe = Timeframe::M15;
+// Range is the high/low of the last 12h of M15 candles
+const RANGE_LOOKBACK: usize = 48;
+// Range must be this many ATR wide to leave room for a target
+const MIN_RANGE_ATR: f64 = 3.0;
+// Entry only in the outer 15% of the range
+const EXTREME_SHARE: f64 = 0.15;
+// Oversold/overbought confirmation: RSI or a close outside the 20/2 bands
+const RSI_OVERSOLD: f64 = 35.0;
+const RSI_OVERBOUGHT: f64 = 65.0;
+const BOLLINGER_PERIOD: usize = 20;
+const BOLLINGER_STD_DEVS: f64 = 2.0;
+// Stop just outside the range
+const STOP_ATR_BUFFER: f64 = 0.5;
+// Farther target must pay at least this multiple of the risk
+const MIN_REWARD_RISK: f64 = 1.5;
+const PENDING_ENTRY_TTL_MS: i64 = 5 * 60 * 1000;
+
+struct ActiveTrade {
+    direction: TrendDirection,
+    targets: TakeProfitLevels,
+}
+
+// Fades the edges of a range while the regime is Ranging. Half the position
+// comes off at the nearer of range mid and session VWAP, the rest at the other.
+pub struct RangeReversionStrategy {
+    settings: StrategySettings,
+    pending: HashMap<String, (EntryIntent, i64)>,
+    trades: HashMap<String, ActiveTrade>,
+}
+
+impl RangeReversionStrategy {
+    pub fn new(risk_budget_percent: f64) -> Self {
+        Self {
+            settings: StrategySettings {
+                name: "range_reversion".to_string(),
+                symbols: Vec::new(),
+                timeframes: vec![Timeframe::M5, Timeframe::M15, Timeframe::H1],
+                bar_timeframe: SIGNAL_TIMEFRAME,
+                risk_budget_percent,
+            },
+            pending: HashMap::new(),
+            trades: HashMap::new(),
+        }
+    }
+
+    fn find_entry(&self, symbol: &str, data: &MarketData) -> Option<EntryIntent> {
+        if data.candles.len() < RANGE_LOOKBACK + 1 {
+            return None;
+        }
+
+        // Range from the candles before the signal candle
+        let last = data.last_candle()?;
+        let window = &data.candles[data.candles.len() - 1 - RANGE_LOOKBACK..data.candles.len() - 1];
+        let range_high = to_f64(window.iter().map(|c| c.high).max()?);
+        let range_low = to_f64(window.iter().map(|c| c.low).min()?);
+        let width = range_high - range_low;
+        let atr = to_f64(data.atr(14));
+        if atr == 0.0 || width < atr * MIN_RANGE_ATR {
+            return None;
+        }
+
+        let close = to_f64(last.close);
+        let rsi = data.rsi(14);
+        let (lower_band, upper_band) = bollinger_bands(data)?;
+
+        // Price probed the edge and the candle closed back towards the middle
+        let direction = if close <= range_low + width * EXTREME_SHARE
+            && (rsi < RSI_OVERSOLD || to_f64(last.low) < lower_band)
+            && last.is_bullish()
+        {
+            TrendDirection::Long
+        } else if close >= range_high - width * EXTREME_SHARE
+            && (rsi > RSI_OVERBOUGHT || to_f64(last.high) > upper_band)
+            && last.is_bearish()
+        {
+            TrendDirection::Short
+        } else {
+            return None;
+        };
+
+        let (stop_loss, edge, edge_price) = match direction {
+            TrendDirection::Long => (range_low.min(to_f64(last.low)) - atr * STOP_ATR_BUFFER, "low", range_low),
+            _ => (range_high.max(to_f64(last.high)) + atr * STOP_ATR_BUFFER, "high", range_high),
+        };
+
+        // Mid and VWAP that lie ahead of the entry, nearest first
+        let mid = (range_high + range_low) / 2.0;
+        let vwap = Vwap::session(&data.candles).map(|v| v.value);
+        let mut targets: Vec<f64> = [Some(mid), vwap]
+            .into_iter()
+            .flatten()
+            .filter(|t| match direction {
+                TrendDirection::Long => *t > close,
+                _ => *t < close,
+            })
+            .collect();
+        targets.sort_by(|a, b| (a - close).abs().total_cmp(&(b - close).abs()));
+
+        let (&near, &far) = (targets.first()?, targets.last()?);
+        let risk = (close - stop_loss).abs();
+        if risk == 0.0 || (far - close).abs() / risk < MIN_REWARD_RISK {
+            return None;
+        }
+
+        let entry_price = last.close;
+        let stop_loss = Decimal::try_from(stop_loss).ok()?;
+        let near = Decimal::try_from(near).ok()?;
+        let far = Decimal::try_from(far).ok()?;
+
+        info!("↔️  {} range {:.2}-{:.2} {:?} fade at {}, RSI {:.1}, targets {} / {}",
+            symbol, range_low, range_high, direction, entry_price, rsi, near, far);
+
+        Some(EntryIntent {
+            symbol: symbol.to_string(),
+            direction,
+            entry_price,
+            stop_loss,
+            targets: TakeProfitLevels {
+                tp1: TPLevel { price: near, size_percent: Decimal::from(50), hit: false },
+                tp2: TPLevel { price: far, size_percent: Decimal::from(50), hit: false },
+                // No runner: nothing is left to close at TP3
+                tp3: TPLevel { price: far, size_percent: Decimal::ZERO, hit: false },
+                trailing_stop: TrailingStop { enabled: false, distance: Decimal::ZERO, current_level: stop_loss },
+            },
+            confluence_score: 0,
+            reason: format!("Fade of range {} {:.2}, RSI {:.1}", edge, edge_price, rsi),
+        })
+    }
+
+    fn manage_trade(&mut self, symbol: &str, data: &MarketData, regime: Option<MarketRegime>) -> Vec<Intent> {
+        let Some(trade) = self.trades.get_mut(symbol) else {
+            return Vec::new();
+        };
+        let price = data.close();
+
+        // The range thesis is gone once the market starts trending or spiking
+        if let Some(regime) = regime.filter(|r| *r != MarketRegime::Ranging) {
+            return vec![Intent::Exit {
+                symbol: symbol.to_string(),
+                price,
+                size_percent: Decimal::from(100),
+                reason: format!("Regime changed to {:?}", regime),
+            }];
+        }
+
+        let direction = trade.direction;
+        let mut intents = Vec::new();
+        for (name, tp) in [("Target 1", &mut trade.targets.tp1), ("Target 2", &mut trade.targets.tp2)] {
+            let reached = match direction {
+                TrendDirection::Long => price >= tp.price,
+                _ => price <= tp.price,
+            };
+            if !tp.hit && reached {
+                tp.hit = true;
+                intents.push(Intent::Exit {
+                    symbol: symbol.to_string(),
+                    price,
+                    size_percent: tp.size_percent,
+                    reason: format!("{} hit at {}", name, tp.price),
+                });
+            }
+        }
+
+        intents
+    }
+}
+
+#[async_trait]
+impl Strategy for RangeReversionStrategy {
+    fn settings(&self) -> &StrategySettings {
+        &self.settings
+    }
+
+    async fn on_bar(&mut self, data: &MultiTimeframeData, ctx: &StrategyContext) -> Vec<Intent> {
+        let Some(signal_data) = data.aligned(SIGNAL_TIMEFRAME) else {
+            return Vec::new();
+        };
+
+        if ctx.has_position {
+            return self.manage_trade(&ctx.symbol, &signal_data, ctx.regime);
+        }
+
+        self.trades.remove(&ctx.symbol);
+
+        if ctx.regime != Some(MarketRegime::Ranging) || self.pending.contains_key(&ctx.symbol) {
+            return Vec::new();
+        }
+
+        match self.find_entry(&ctx.symbol, &signal_data) {
+            Some(entry) => {
+                log_order_book(data, &entry);
+                self.pending.insert(ctx.symbol.clone(), (entry.clone(), ctx.now));
+                vec![Intent::Enter(entry)]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        match fill.kind {
+            FillKind::Open => {
+                // The fill carries the intent, so entries the bot queued past
+                // the pending TTL are still managed
+                let entry = self.pending.remove(&fill.symbol).map(|(entry, _)| entry);
+                if let Some(entry) = fill.entry.clone().or(entry) {
+                    self.trades.insert(fill.symbol.clone(), ActiveTrade {
+                        direction: entry.direction,
+                        targets: entry.targets,
+                    });
+                }
+            }
+            FillKind::Reduce => {}
+            FillKind::Close => {
+                self.trades.remove(&fill.symbol);
+            }
+        }
+
+        Vec::new()
+    }
+
+    fn on_timer(&mut self, now: i64) -> Vec<Intent> {
+        self.pending.retain(|_, (_, sent_at)| now - *sent_at < PENDING_ENTRY_TTL_MS);
+        Vec::new()
+    }
+}
+
+// (lower, upper) Bollinger band of the latest candle
+fn bollinger_bands(data: &MarketData) -> Option<(f64, f64)> {
+    let window = data.candles.get(data.candles.len().checked_sub(BOLLINGER_PERIOD)?..)?;
+    let closes: Vec<f64> = window.iter().map(|c| to_f64(c.close)).collect();
+    let mean = closes.iter().sum::<f64>() / closes.len() as f64;
+    let std_dev = (closes.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / closes.len() as f64).sqrt();
+
+    Some((mean - std_dev * BOLLINGER_STD_DEVS, mean + std_dev * BOLLINGER_STD_DEVS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Candle;
+
+    // 2024-01-02 00:00 UTC, so every bar sits in one VWAP session
+    const BASE: i64 = 1_704_153_600_000;
+    const M15: i64 = 15 * 60_000;
+
+    fn dec(value: f64) -> Decimal {
+        Decimal::try_from(value).unwrap()
+    }
+
+    // Bars closing at `closes` with a 0.25 wick each side, then the signal bar
+    fn market_data(closes: &[f64], signal: (f64, f64, f64, f64)) -> MarketData {
+        let mut candles: Vec<Candle> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| {
+                let open = if i == 0 { close } else { closes[i - 1] };
+                Candle::new(BASE + i as i64 * M15, dec(open), dec(open.max(close) + 0.25), dec(open.min(close) - 0.25), dec(close), dec(100.0))
+            })
+            .collect();
+        let (open, high, low, close) = signal;
+        candles.push(Candle::new(BASE + closes.len() as i64 * M15, dec(open), dec(high), dec(low), dec(close), dec(100.0)));
+
+        MarketData {
+            symbol: "SOLUSDT".to_string(),
+            candles,
+            timeframe: SIGNAL_TIMEFRAME,
+        }
+    }
+
+    // Mirror image around 105, turning a long setup into a short one
+    fn mirrored(data: &MarketData) -> MarketData {
+        let flip = |value: Decimal| Decimal::from(210) - value;
+        let candles = data.candles
+            .iter()
+            .map(|c| Candle::new(c.timestamp, flip(c.open), flip(c.low), flip(c.high), flip(c.close), c.volume))
+            .collect();
+        MarketData { candles, ..data.clone() }
+    }
+
+    // Up from 101 to 110 over 24 bars, then back down to 101.5: the last 14
+    // moves are all down, so RSI is deeply oversold
+    fn falling_to_the_low() -> Vec<f64> {
+        let up = (0..24).map(|i| 101.0 + 9.0 * i as f64 / 23.0);
+        let down = (0..24).map(|i| 110.0 - 8.5 * (i + 1) as f64 / 24.0);
+        up.chain(down).collect()
+    }
+
+    // A spike to 110 early on, then price idling around 101.1
+    fn idling_at_the_low() -> Vec<f64> {
+        let spike = (0..20).map(|i| if i < 10 { 101.0 + i as f64 } else { 110.0 - (i - 10) as f64 });
+        let idle = (0..28).map(|i| if i % 2 == 0 { 101.0 } else { 101.2 });
+        spike.chain(idle).collect()
+    }
+
+    fn strategy() -> RangeReversionStrategy {
+        RangeReversionStrategy::new(1.0)
+    }
+
+    #[test]
+    fn oversold_close_back_from_the_range_low_goes_long() {
+        let data = market_data(&falling_to_the_low(), (101.3, 101.7, 100.9, 101.6));
+        assert!(data.rsi(14) < RSI_OVERSOLD);
+
+        let entry = strategy().find_entry("SOLUSDT", &data).expect("long fade");
+        assert_eq!(entry.direction, TrendDirection::Long);
+        assert_eq!(entry.entry_price, dec(101.6));
+        // Below both the range low and the signal wick
+        assert!(entry.stop_loss < dec(100.75));
+
+        // Range mid and session VWAP, nearest first, half the position each
+        let mid = dec(105.5);
+        let (tp1, tp2) = (&entry.targets.tp1, &entry.targets.tp2);
+        assert!(tp1.price == mid || tp2.price == mid);
+        assert!(entry.entry_price < tp1.price && tp1.price <= tp2.price);
+        assert_eq!((tp1.size_percent, tp2.size_percent, entry.targets.tp3.size_percent), (dec(50.0), dec(50.0), Decimal::ZERO));
+    }
+
+    #[test]
+    fn overbought_close_back_from_the_range_high_goes_short() {
+        let data = mirrored(&market_data(&falling_to_the_low(), (101.3, 101.7, 100.9, 101.6)));
+
+        let entry = strategy().find_entry("SOLUSDT", &data).expect("short fade");
+        assert_eq!(entry.direction, TrendDirection::Short);
+        assert!(entry.stop_loss > dec(109.25));
+        assert!(entry.targets.tp1.price == dec(104.5) || entry.targets.tp2.price == dec(104.5));
+        assert!(entry.entry_price > entry.targets.tp1.price && entry.targets.tp1.price >= entry.targets.tp2.price);
+    }
+
+    #[test]
+    fn edge_entries_need_rsi_or_bollinger_confirmation() {
+        // Neutral RSI and a wick inside the bands
+        let quiet = market_data(&idling_at_the_low(), (101.0, 101.2, 100.95, 101.15));
+        assert!(quiet.rsi(14) > RSI_OVERSOLD);
+        assert!(strategy().find_entry("SOLUSDT", &quiet).is_none());
+
+        // Same bar wicking through the lower band
+        let pierced = market_data(&idling_at_the_low(), (101.0, 101.2, 99.5, 101.15));
+        assert!(pierced.rsi(14) > RSI_OVERSOLD);
+        assert_eq!(strategy().find_entry("SOLUSDT", &pierced).unwrap().direction, TrendDirection::Long);
+
+        // Still falling: no close back towards the middle
+        let falling = market_data(&falling_to_the_low(), (101.6, 101.7, 100.9, 101.3));
+        assert!(strategy().find_entry("SOLUSDT", &falling).is_none());
+
+        // Mid-range is no edge
+        let middle = market_data(&falling_to_the_low()[..30], (104.0, 104.4, 103.6, 104.3));
+        assert!(strategy().find_entry("SOLUSDT", &middle).is_none());
+    }
+
+    #[test]
+    fn trade_is_closed_once_the_regime_leaves_ranging() {
+        let data = market_data(&falling_to_the_low(), (101.3, 101.7, 100.9, 101.6));
+        let mut strategy = strategy();
+        let entry = strategy.find_entry("SOLUSDT", &data).unwrap();
+        let (tp1, tp2) = (entry.targets.tp1.price, to_f64(entry.targets.tp2.price));
+        strategy.on_fill(&Fill {
+            symbol: "SOLUSDT".to_string(),
+            order_id: "1".to_string(),
+            kind: FillKind::Open,
+            direction: TrendDirection::Long,
+            price: entry.entry_price,
+            quantity: Decimal::ONE,
+            timestamp: 0,
+            entry: Some(entry),
+        });
+
+        assert!(strategy.manage_trade("SOLUSDT", &data, Some(MarketRegime::Ranging)).is_empty());
+
+        match strategy.manage_trade("SOLUSDT", &data, Some(MarketRegime::Trending)).as_slice() {
+            [Intent::Exit { size_percent, reason, .. }] => {
+                assert_eq!(*size_percent, Decimal::from(100));
+                assert!(reason.contains("Trending"));
+            }
+            other => panic!("expected a full exit, got {:?}", other),
+        }
+
+        // Back in the range, the first target takes half off once
+        let tp1 = to_f64(tp1);
+        assert!(tp2 - tp1 > 0.02);
+        let at_target = market_data(&falling_to_the_low(), (101.3, tp1 + 0.01, 100.9, tp1 + 0.01));
+        let exits = strategy.manage_trade("SOLUSDT", &at_target, Some(MarketRegime::Ranging));
+        assert!(matches!(exits.as_slice(), [Intent::Exit { size_percent, .. }] if *size_percent == dec(50.0)));
+        assert!(strategy.manage_trade("SOLUSDT", &at_target, Some(MarketRegime::Ranging)).is_empty());
+    }
+}
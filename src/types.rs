@@ -1,5 +1,12 @@
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Decimal to f64 for indicator math; values out of f64 range become 0
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TrendDirection {
@@ -8,7 +15,7 @@ pub enum TrendDirection {
     Neutral,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Timeframe {
     M1,
     M5,
@@ -17,9 +24,29 @@ pub enum Timeframe {
     H1,
     H4,
     D1,
+    // Any other bar length in minutes (3m, 2h, 12h, weekly = 10080...)
+    Custom(u32),
 }
 
+const DAY_MINUTES: u32 = 1_440;
+const WEEK_MINUTES: u32 = 10_080;
+// 1970-01-01 was a Thursday, the first Monday 00:00 UTC is 4 days later
+const FIRST_MONDAY_MS: i64 = 4 * 86_400_000;
+
 impl Timeframe {
+    pub fn from_minutes(minutes: u32) -> Self {
+        match minutes {
+            1 => Timeframe::M1,
+            5 => Timeframe::M5,
+            15 => Timeframe::M15,
+            30 => Timeframe::M30,
+            60 => Timeframe::H1,
+            240 => Timeframe::H4,
+            DAY_MINUTES => Timeframe::D1,
+            m => Timeframe::Custom(m),
+        }
+    }
+
     pub fn to_minutes(&self) -> u32 {
         match self {
             Timeframe::M1 => 1,
@@ -28,11 +55,93 @@ impl Timeframe {
             Timeframe::M30 => 30,
             Timeframe::H1 => 60,
             Timeframe::H4 => 240,
-            Timeframe::D1 => 1440,
+            Timeframe::D1 => DAY_MINUTES,
+            Timeframe::Custom(minutes) => *minutes,
+        }
+    }
+
+    pub fn to_millis(&self) -> i64 {
+        self.to_minutes() as i64 * 60_000
+    }
+
+    // Open time of the UTC bucket containing `timestamp` (ms). Intraday and
+    // daily bars are anchored at midnight UTC, weekly bars at Monday 00:00
+    // UTC like the exchanges do.
+    pub fn bucket_start(&self, timestamp: i64) -> i64 {
+        let length = self.to_millis().max(1);
+        let anchor = if self.to_minutes() % WEEK_MINUTES == 0 {
+            FIRST_MONDAY_MS
+        } else {
+            0
+        };
+
+        (timestamp - anchor).div_euclid(length) * length + anchor
+    }
+
+    // True when bars of `self` can be built exactly from bars of `lower`
+    pub fn is_multiple_of(&self, lower: Timeframe) -> bool {
+        let (target, source) = (self.to_minutes(), lower.to_minutes());
+        if source == 0 || target < source || target % source != 0 {
+            return false;
+        }
+
+        // Source buckets must not straddle target boundaries
+        let anchor = |tf: &Timeframe| tf.bucket_start(0) / 60_000;
+        (anchor(self) - anchor(&lower)).rem_euclid(source as i64) == 0
+    }
+}
+
+impl std::str::FromStr for Timeframe {
+    type Err = anyhow::Error;
+
+    // Accepts "5m", "2h", "1d", "1w" and the enum names ("M15", "H4")
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let split = |at: usize| value.is_char_boundary(at).then(|| value.split_at(at));
+
+        // Unit last ("15m"), then unit first ("M15")
+        let last = value.char_indices().last().map_or(0, |(i, _)| i);
+        let candidates = [
+            split(last).and_then(|(number, unit)| Some((number, unit_minutes(unit, false)?))),
+            split(1.min(value.len())).and_then(|(unit, number)| Some((number, unit_minutes(unit, true)?))),
+        ];
+
+        for (number, unit) in candidates.into_iter().flatten() {
+            let Ok(count) = number.parse::<u32>() else {
+                continue;
+            };
+            match count.checked_mul(unit) {
+                Some(minutes) if minutes > 0 => return Ok(Timeframe::from_minutes(minutes)),
+                Some(_) => anyhow::bail!("invalid timeframe: {} (zero length)", value),
+                None => anyhow::bail!("invalid timeframe: {} (too long)", value),
+            }
         }
+
+        anyhow::bail!("invalid timeframe: {}", value)
+    }
+}
+
+// Minutes per unit; suffixes are lower case, enum-style prefixes upper case
+fn unit_minutes(unit: &str, prefix: bool) -> Option<u32> {
+    match (unit, prefix) {
+        ("m", false) | ("M", true) => Some(1),
+        ("h", false) | ("H", true) => Some(60),
+        ("d", false) | ("D", true) => Some(DAY_MINUTES),
+        ("w", false) | ("W", true) => Some(WEEK_MINUTES),
+        _ => None,
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub timestamp: i64,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    // Some(true) when the aggressor was the buyer
+    #[serde(default)]
+    pub taker_buy: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Candle {
     pub timestamp: i64,
@@ -41,9 +150,41 @@ pub struct Candle {
     pub low: Decimal,
     pub close: Decimal,
     pub volume: Decimal,
+
+    // Not every venue reports these
+    #[serde(default)]
+    pub quote_volume: Option<Decimal>,
+    #[serde(default)]
+    pub trade_count: Option<u64>,
+    #[serde(default)]
+    pub taker_buy_base_volume: Option<Decimal>,
+    #[serde(default)]
+    pub taker_buy_quote_volume: Option<Decimal>,
 }
 
 impl Candle {
+    pub fn new(
+        timestamp: i64,
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+        volume: Decimal,
+    ) -> Self {
+        Self {
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            quote_volume: None,
+            trade_count: None,
+            taker_buy_base_volume: None,
+            taker_buy_quote_volume: None,
+        }
+    }
+
     pub fn range(&self) -> Decimal {
         self.high - self.low
     }
@@ -59,6 +200,19 @@ impl Candle {
     pub fn is_bearish(&self) -> bool {
         self.close < self.open
     }
+
+    pub fn upper_wick(&self) -> Decimal {
+        self.high - self.open.max(self.close)
+    }
+
+    pub fn lower_wick(&self) -> Decimal {
+        self.open.min(self.close) - self.low
+    }
+
+    // Candle timestamps are the bar open time
+    pub fn close_time(&self, timeframe: Timeframe) -> i64 {
+        self.timestamp + timeframe.to_millis()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -183,8 +337,11 @@ impl MarketData {
         let plus_di = (plus_dm_sum / tr_sum) * 100.0;
         let minus_di = (minus_dm_sum / tr_sum) * 100.0;
 
-        let dx = ((plus_di - minus_di).abs() / (plus_di + minus_di)) * 100.0;
-        dx
+        if plus_di + minus_di == 0.0 {
+            return 0.0;
+        }
+
+        ((plus_di - minus_di).abs() / (plus_di + minus_di)) * 100.0
     }
 
     // EMA (Exponential Moving Average)
@@ -206,6 +363,92 @@ impl MarketData {
         ema
     }
 
+    // EMA of closes for every candle (seeded with the first close)
+    pub fn ema_series(&self, period: usize) -> Vec<f64> {
+        let multiplier = 2.0 / (period as f64 + 1.0);
+        let mut series = Vec::with_capacity(self.candles.len());
+        let mut ema: Option<f64> = None;
+
+        for candle in &self.candles {
+            let close = candle.close.to_string().parse::<f64>().unwrap_or(0.0);
+            let value = match ema {
+                Some(prev) => (close - prev) * multiplier + prev,
+                None => close,
+            };
+            ema = Some(value);
+            series.push(value);
+        }
+
+        series
+    }
+
+    // MACD line, signal line and histogram for every candle
+    pub fn macd_series(&self, fast: usize, slow: usize, signal: usize) -> Vec<MacdPoint> {
+        let fast_ema = self.ema_series(fast);
+        let slow_ema = self.ema_series(slow);
+        let multiplier = 2.0 / (signal as f64 + 1.0);
+
+        let mut points: Vec<MacdPoint> = Vec::with_capacity(self.candles.len());
+        for (f, s) in fast_ema.iter().zip(slow_ema.iter()) {
+            let macd = f - s;
+            let signal_line = match points.last() {
+                Some(prev) => (macd - prev.signal) * multiplier + prev.signal,
+                None => macd,
+            };
+            points.push(MacdPoint {
+                macd,
+                signal: signal_line,
+                histogram: macd - signal_line,
+            });
+        }
+
+        points
+    }
+
+    // ATR over the `period` candles ending at each candle, same definition
+    // as `atr`; one value per candle from index `period` onwards
+    pub fn atr_series(&self, period: usize) -> Vec<f64> {
+        if period == 0 || self.candles.len() < period + 1 {
+            return Vec::new();
+        }
+
+        let true_ranges: Vec<f64> = self.candles
+            .windows(2)
+            .map(|pair| {
+                let high_low = pair[1].high - pair[1].low;
+                let high_close = (pair[1].high - pair[0].close).abs();
+                let low_close = (pair[1].low - pair[0].close).abs();
+                high_low.max(high_close).max(low_close).to_string().parse::<f64>().unwrap_or(0.0)
+            })
+            .collect();
+
+        true_ranges
+            .windows(period)
+            .map(|window| window.iter().sum::<f64>() / period as f64)
+            .collect()
+    }
+
+    // Bollinger bandwidth (upper - lower) / middle for every full window
+    pub fn bollinger_bandwidth_series(&self, period: usize, std_devs: f64) -> Vec<f64> {
+        if period == 0 || self.candles.len() < period {
+            return Vec::new();
+        }
+
+        let closes: Vec<f64> = self.candles
+            .iter()
+            .map(|c| c.close.to_string().parse::<f64>().unwrap_or(0.0))
+            .collect();
+
+        closes
+            .windows(period)
+            .map(|window| {
+                let mean = window.iter().sum::<f64>() / period as f64;
+                let variance = window.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / period as f64;
+                if mean == 0.0 { 0.0 } else { 2.0 * std_devs * variance.sqrt() / mean }
+            })
+            .collect()
+    }
+
     // SMA for volume
     pub fn volume_sma(&self, period: usize) -> Decimal {
         if self.candles.len() < period {
@@ -319,3 +562,266 @@ impl MarketData {
         5.0 // 0.05%
     }
 }
+
+// Correlations and betas need this many shared returns before they are trusted
+pub const MIN_PAIRED_RETURNS: usize = 30;
+
+// Log returns of `a` and `b` over the last `window` bars both series share,
+// joined on timestamp. None below MIN_PAIRED_RETURNS.
+pub fn paired_log_returns(a: &MarketData, b: &MarketData, window: usize) -> Option<(Vec<f64>, Vec<f64>)> {
+    let b_closes: HashMap<i64, f64> = b.candles.iter().map(|c| (c.timestamp, to_f64(c.close))).collect();
+
+    let paired: Vec<(f64, f64)> = a.candles
+        .iter()
+        .filter_map(|c| Some((to_f64(c.close), *b_closes.get(&c.timestamp)?)))
+        .collect();
+    let start = paired.len().saturating_sub(window + 1);
+
+    let (x, y): (Vec<f64>, Vec<f64>) = paired[start..]
+        .windows(2)
+        .filter(|w| w[0].0 > 0.0 && w[0].1 > 0.0 && w[1].0 > 0.0 && w[1].1 > 0.0)
+        .map(|w| ((w[1].0 / w[0].0).ln(), (w[1].1 / w[0].1).ln()))
+        .unzip();
+
+    (x.len() >= MIN_PAIRED_RETURNS).then_some((x, y))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdPoint {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookLevel {
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+// Order book snapshot; bids best (highest) first, asks best (lowest) first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub symbol: String,
+    pub timestamp: i64,
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+impl OrderBook {
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.first().map(|l| l.price)
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.first().map(|l| l.price)
+    }
+
+    pub fn mid(&self) -> Option<Decimal> {
+        Some((self.best_bid()? + self.best_ask()?) / Decimal::from(2))
+    }
+
+    pub fn spread_bps(&self) -> Option<f64> {
+        let (bid, ask) = (self.best_bid()?, self.best_ask()?);
+        if bid <= Decimal::ZERO || ask < bid {
+            return None;
+        }
+        ((ask - bid) / self.mid()? * Decimal::from(10_000)).to_string().parse().ok()
+    }
+
+    // Quote notional resting on the best `levels` levels of both sides
+    pub fn depth_usd(&self, levels: usize) -> f64 {
+        self.bids
+            .iter()
+            .take(levels)
+            .chain(self.asks.iter().take(levels))
+            .map(|l| (l.price * l.quantity).to_string().parse::<f64>().unwrap_or(0.0))
+            .sum()
+    }
+}
+
+// Forced close of a position by the exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Liquidation {
+    pub symbol: String,
+    pub timestamp: i64,
+    // Side of the liquidated position; a Long liquidation is a forced sell
+    pub side: TrendDirection,
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+impl Liquidation {
+    pub fn notional(&self) -> f64 {
+        to_f64(self.price * self.quantity)
+    }
+}
+
+// Per-symbol candle series for several timeframes evaluated at a single point
+// in time. Higher timeframes only expose bars that had closed by `as_of`, so
+// signals never see a bar that was still forming (no look-ahead).
+#[derive(Debug, Clone)]
+pub struct MultiTimeframeData {
+    pub symbol: String,
+    pub as_of: i64,
+    series: HashMap<Timeframe, MarketData>,
+    // Recent order book snapshots, oldest first
+    order_books: Vec<OrderBook>,
+    // Recent liquidations, oldest first
+    liquidations: Vec<Liquidation>,
+}
+
+impl MultiTimeframeData {
+    pub const DEFAULT_TIMEFRAMES: [Timeframe; 5] = [
+        Timeframe::M5,
+        Timeframe::M15,
+        Timeframe::H1,
+        Timeframe::H4,
+        Timeframe::D1,
+    ];
+
+    pub fn new(symbol: String, as_of: i64) -> Self {
+        Self {
+            symbol,
+            as_of,
+            series: HashMap::new(),
+            order_books: Vec::new(),
+            liquidations: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, mut data: MarketData) {
+        data.candles.sort_by_key(|c| c.timestamp);
+        self.series.insert(data.timeframe, data);
+    }
+
+    pub fn insert_order_book(&mut self, book: OrderBook) {
+        let index = self.order_books.partition_point(|b| b.timestamp <= book.timestamp);
+        self.order_books.insert(index, book);
+    }
+
+    // Live snapshots are taken just after `as_of`, so they are not filtered
+    // here; `at` drops the ones that come later when replaying
+    pub fn order_books(&self) -> &[OrderBook] {
+        &self.order_books
+    }
+
+    pub fn order_book(&self) -> Option<&OrderBook> {
+        self.order_books().last()
+    }
+
+    pub fn insert_liquidations(&mut self, liquidations: impl IntoIterator<Item = Liquidation>) {
+        self.liquidations.extend(liquidations);
+        self.liquidations.sort_by_key(|l| l.timestamp);
+    }
+
+    // Unfiltered like `order_books`
+    pub fn liquidations(&self) -> &[Liquidation] {
+        &self.liquidations
+    }
+
+    pub fn timeframes(&self) -> Vec<Timeframe> {
+        let mut timeframes: Vec<Timeframe> = self.series.keys().copied().collect();
+        timeframes.sort_by_key(|tf| tf.to_minutes());
+        timeframes
+    }
+
+    // Lowest timeframe held, used as the execution series
+    pub fn base_timeframe(&self) -> Option<Timeframe> {
+        self.timeframes().first().copied()
+    }
+
+    // Raw series as fetched, including a possibly still-forming last bar
+    pub fn raw(&self, timeframe: Timeframe) -> Option<&MarketData> {
+        self.series.get(&timeframe)
+    }
+
+    pub fn closed_candles(&self, timeframe: Timeframe) -> &[Candle] {
+        let Some(data) = self.series.get(&timeframe) else {
+            return &[];
+        };
+
+        let closed = data.candles
+            .partition_point(|c| c.close_time(timeframe) <= self.as_of);
+
+        &data.candles[..closed]
+    }
+
+    pub fn aligned(&self, timeframe: Timeframe) -> Option<MarketData> {
+        let data = self.series.get(&timeframe)?;
+
+        Some(MarketData {
+            symbol: data.symbol.clone(),
+            candles: self.closed_candles(timeframe).to_vec(),
+            timeframe,
+        })
+    }
+
+    // Same data evaluated at an earlier instant, e.g. when replaying history
+    pub fn at(&self, as_of: i64) -> Self {
+        Self {
+            symbol: self.symbol.clone(),
+            as_of,
+            series: self.series.clone(),
+            order_books: self.order_books
+                .iter()
+                .filter(|b| b.timestamp <= as_of)
+                .cloned()
+                .collect(),
+            liquidations: self.liquidations
+                .iter()
+                .filter(|l| l.timestamp <= as_of)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeframe_parses_suffix_and_enum_names() {
+        assert_eq!("15m".parse::<Timeframe>().unwrap(), Timeframe::M15);
+        assert_eq!(" 4h ".parse::<Timeframe>().unwrap(), Timeframe::H4);
+        assert_eq!("1d".parse::<Timeframe>().unwrap(), Timeframe::D1);
+        assert_eq!("M5".parse::<Timeframe>().unwrap(), Timeframe::M5);
+        assert_eq!("1w".parse::<Timeframe>().unwrap(), Timeframe::Custom(WEEK_MINUTES));
+        assert_eq!("3m".parse::<Timeframe>().unwrap(), Timeframe::Custom(3));
+    }
+
+    #[test]
+    fn timeframe_rejects_zero_overflow_and_garbage() {
+        for value in ["0m", "H0", "99999999d", "4294967295h", "", "m", "5", "5x", "5é", "é"] {
+            assert!(value.parse::<Timeframe>().is_err(), "{} parsed", value);
+        }
+    }
+
+    #[test]
+    fn paired_log_returns_join_on_timestamp() {
+        let series = |symbol: &str, bars: &[(i64, f64)]| MarketData {
+            symbol: symbol.to_string(),
+            candles: bars
+                .iter()
+                .map(|&(ts, close)| {
+                    let close = Decimal::try_from(close).unwrap();
+                    Candle::new(ts, close, close, close, close, Decimal::ONE)
+                })
+                .collect(),
+            timeframe: Timeframe::H1,
+        };
+
+        // `b` skips every tenth bar; `a` doubles every bar
+        let a: Vec<(i64, f64)> = (0..50).map(|i| (i, 2f64.powi(i as i32 % 20))).collect();
+        let b: Vec<(i64, f64)> = (0..50).filter(|i| i % 10 != 5).map(|i| (i, 100.0)).collect();
+
+        let (x, y) = paired_log_returns(&series("A", &a), &series("B", &b), 40).unwrap();
+        assert_eq!((x.len(), y.len()), (40, 40));
+        assert!(y.iter().all(|r| *r == 0.0));
+        assert!((x[x.len() - 1] - 2f64.ln()).abs() < 1e-12);
+
+        assert!(paired_log_returns(&series("A", &a), &series("B", &b), 20).is_none());
+        assert!(paired_log_returns(&series("A", &a), &series("B", &[]), 40).is_none());
+    }
+}